@@ -8,7 +8,7 @@ use axum::{
     Json, Router,
 };
 use axum_extra::extract::CookieJar;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tower_http::services::ServeDir;
 
 #[tokio::main]
@@ -63,7 +63,14 @@ async fn protected(jar: CookieJar) -> impl IntoResponse {
     let auth_hostname = env::var("AUTH_SERVICE_HOST_NAME").unwrap_or("0.0.0.0".to_owned());
     let url = format!("http://{}:3000/verify-token", auth_hostname);
 
-    let response = match api_client.post(&url).json(&verify_token_body).send().await {
+    let mut request = api_client.post(&url).json(&verify_token_body);
+    match client_access_token(&api_client, &auth_hostname).await {
+        Ok(Some(access_token)) => request = request.bearer_auth(access_token),
+        Ok(None) => {}
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+
+    let response = match request.send().await {
         Ok(response) => response,
         Err(_) => {
             return StatusCode::INTERNAL_SERVER_ERROR.into_response();
@@ -82,6 +89,39 @@ async fn protected(jar: CookieJar) -> impl IntoResponse {
     }
 }
 
+/// Authenticates us to the auth service with the client credentials grant, if
+/// `AUTH_SERVICE_CLIENT_ID` and `AUTH_SERVICE_CLIENT_SECRET` are set.
+async fn client_access_token(
+    api_client: &reqwest::Client,
+    auth_hostname: &str,
+) -> Result<Option<String>, reqwest::Error> {
+    let (client_id, client_secret) = match (
+        env::var("AUTH_SERVICE_CLIENT_ID"),
+        env::var("AUTH_SERVICE_CLIENT_SECRET"),
+    ) {
+        (Ok(client_id), Ok(client_secret)) if !client_id.is_empty() => (client_id, client_secret),
+        _ => return Ok(None),
+    };
+
+    let url = format!("http://{}:3000/token", auth_hostname);
+    let token = api_client
+        .post(&url)
+        .basic_auth(client_id, Some(client_secret))
+        .form(&[("grant_type", "client_credentials")])
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<TokenResponse>()
+        .await?;
+
+    Ok(Some(token.access_token))
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
 #[derive(Serialize)]
 pub struct ProtectedRouteResponse {
     pub img_url: String,
@@ -32,6 +32,19 @@ signupLoginLink.addEventListener("click", (e) => {
 
 // -----------------------------------------------------
 
+// Pages that need a logged in user, like the OAuth2 authorization, send users
+// here with the path to return to afterwards.
+function returnAfterLogin() {
+    const returnTo = new URLSearchParams(window.location.search).get("return_to");
+    // Only paths on this site, so the link can't send users elsewhere.
+    if (returnTo === null || !/^\/(?![\/\\])/.test(returnTo)) {
+        return false;
+    }
+
+    window.location.assign(returnTo);
+    return true;
+}
+
 const loginForm = document.getElementById("login-form");
 const loginButton = document.getElementById("login-form-submit");
 const loginErrAlter = document.getElementById("login-err-alert");
@@ -66,7 +79,9 @@ loginButton.addEventListener("click", (e) => {
             loginForm.email.value = "";
             loginForm.password.value = "";
             loginErrAlter.style.display = "none";
-            alert("You have successfully logged in.");
+            if (!returnAfterLogin()) {
+                alert("You have successfully logged in.");
+            }
         } else {
             response.json().then(data => {
                 let error_msg = data.error;
@@ -145,6 +160,9 @@ TwoFAButton.addEventListener("click", (e) => {
             TwoFAForm.email_code.value = "";
             TwoFAForm.login_attempt_id.value = "";
             TwoFAErrAlter.style.display = "none";
+            if (returnAfterLogin()) {
+                return;
+            }
             alert("You have successfully logged in.");
             loginSection.style.display = "block";
             twoFASection.style.display = "none";
@@ -161,4 +179,15 @@ TwoFAButton.addEventListener("click", (e) => {
             });
         }
     });
-});
\ No newline at end of file
+});
+
+// Logins through an identity provider come back here when the user has 2FA.
+const socialLoginParams = new URLSearchParams(window.location.search);
+if (socialLoginParams.has("login_attempt_id")) {
+    TwoFAForm.email.value = socialLoginParams.get("email");
+    TwoFAForm.login_attempt_id.value = socialLoginParams.get("login_attempt_id");
+
+    loginSection.style.display = "none";
+    twoFASection.style.display = "block";
+    signupSection.style.display = "none";
+}
\ No newline at end of file
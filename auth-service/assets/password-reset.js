@@ -0,0 +1,29 @@
+// Submits the form served at the link from the password reset email.
+const passwordResetForm = document.getElementById("password-reset-form");
+const passwordResetResult = document.getElementById("password-reset-result");
+
+passwordResetForm.addEventListener("submit", (e) => {
+    e.preventDefault();
+
+    const token = passwordResetForm.token.value;
+    const newPassword = passwordResetForm.newPassword.value;
+
+    fetch('/password-reset/confirm', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ token, newPassword }),
+    }).then(response => {
+        if (response.status === 200) {
+            passwordResetForm.style.display = "none";
+            passwordResetResult.innerText = "Your password has been reset. You can now log in.";
+        } else if (response.status === 401) {
+            passwordResetResult.innerText = "This link is invalid or has expired.";
+        } else {
+            response.json().then(data => {
+                passwordResetResult.innerText = data.error;
+            });
+        }
+    });
+});
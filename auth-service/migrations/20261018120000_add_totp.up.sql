@@ -0,0 +1,7 @@
+ALTER TABLE users ADD COLUMN IF NOT EXISTS two_fa_method TEXT NOT NULL DEFAULT 'email';
+
+CREATE TABLE IF NOT EXISTS totp_secrets (
+  email TEXT NOT NULL PRIMARY KEY REFERENCES users (email) ON DELETE CASCADE,
+  secret BYTEA,
+  pending_secret BYTEA
+);
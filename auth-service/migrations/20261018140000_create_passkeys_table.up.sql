@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS passkeys (
+  credential_id BYTEA NOT NULL PRIMARY KEY,
+  email TEXT NOT NULL REFERENCES users (email) ON DELETE CASCADE,
+  public_key BYTEA NOT NULL,
+  sign_count BIGINT NOT NULL DEFAULT 0,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS passkeys_email_idx ON passkeys (email);
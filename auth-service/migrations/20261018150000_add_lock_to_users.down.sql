@@ -0,0 +1,2 @@
+ALTER TABLE users DROP COLUMN IF EXISTS lock_reason;
+ALTER TABLE users DROP COLUMN IF EXISTS locked_until;
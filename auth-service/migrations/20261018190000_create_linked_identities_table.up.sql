@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS linked_identities (
+  provider TEXT NOT NULL,
+  subject TEXT NOT NULL,
+  email TEXT NOT NULL REFERENCES users (email) ON DELETE CASCADE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (provider, subject)
+);
+
+CREATE INDEX IF NOT EXISTS linked_identities_email_idx ON linked_identities (email);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS email_dead_letters;
+DROP TABLE IF EXISTS email_outbox;
@@ -0,0 +1,27 @@
+-- Recipients don't reference users, as some emails, like the one confirming
+-- an account deletion, outlive the user.
+CREATE TABLE IF NOT EXISTS email_outbox (
+  id UUID PRIMARY KEY,
+  recipient TEXT NOT NULL,
+  subject TEXT NOT NULL,
+  text_body TEXT NOT NULL,
+  html_body TEXT NOT NULL,
+  attempts INTEGER NOT NULL DEFAULT 0,
+  next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  last_error TEXT,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS email_outbox_next_attempt_at_idx ON email_outbox (next_attempt_at);
+
+CREATE TABLE IF NOT EXISTS email_dead_letters (
+  id UUID PRIMARY KEY,
+  recipient TEXT NOT NULL,
+  subject TEXT NOT NULL,
+  text_body TEXT NOT NULL,
+  html_body TEXT NOT NULL,
+  attempts INTEGER NOT NULL,
+  last_error TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL,
+  dead_lettered_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
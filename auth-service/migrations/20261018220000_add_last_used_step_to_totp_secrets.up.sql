@@ -0,0 +1 @@
+ALTER TABLE totp_secrets ADD COLUMN IF NOT EXISTS last_used_step BIGINT;
@@ -1,11 +1,29 @@
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use crate::domain::{BannedTokenStore, EmailClient, TwoFACodeStore, UserStore};
+use crate::domain::{
+    AuthorizationCodeStore, BannedTokenStore, EmailClient, EmailOutboxStore, IdentityProvider,
+    LinkedIdentityStore, OAuthClientStore, OneTimeTokenStore, PasskeyStore, RateLimitStore,
+    RecoveryCodeStore, RefreshTokenStore, ServiceClientStore, SessionStore, TotpSecretStore,
+    TwoFACodeStore, UserStore,
+};
 
 pub type UserStoreType = Arc<RwLock<dyn UserStore + Send + Sync>>;
 pub type BannedTokenStoreType = Arc<RwLock<dyn BannedTokenStore + Send + Sync>>;
 pub type TwoFACodeStoreType = Arc<RwLock<dyn TwoFACodeStore + Send + Sync>>;
+pub type RefreshTokenStoreType = Arc<RwLock<dyn RefreshTokenStore + Send + Sync>>;
+pub type OneTimeTokenStoreType = Arc<RwLock<dyn OneTimeTokenStore + Send + Sync>>;
+pub type TotpSecretStoreType = Arc<RwLock<dyn TotpSecretStore + Send + Sync>>;
+pub type RecoveryCodeStoreType = Arc<RwLock<dyn RecoveryCodeStore + Send + Sync>>;
+pub type PasskeyStoreType = Arc<RwLock<dyn PasskeyStore + Send + Sync>>;
+pub type RateLimitStoreType = Arc<RwLock<dyn RateLimitStore + Send + Sync>>;
+pub type SessionStoreType = Arc<RwLock<dyn SessionStore + Send + Sync>>;
+pub type OAuthClientStoreType = Arc<RwLock<dyn OAuthClientStore + Send + Sync>>;
+pub type AuthorizationCodeStoreType = Arc<RwLock<dyn AuthorizationCodeStore + Send + Sync>>;
+pub type ServiceClientStoreType = Arc<RwLock<dyn ServiceClientStore + Send + Sync>>;
+pub type LinkedIdentityStoreType = Arc<RwLock<dyn LinkedIdentityStore + Send + Sync>>;
+pub type EmailOutboxStoreType = Arc<RwLock<dyn EmailOutboxStore + Send + Sync>>;
+pub type IdentityProviderType = Arc<dyn IdentityProvider + Send + Sync>;
 pub type EmailClientType = Arc<RwLock<dyn EmailClient + Send + Sync>>;
 
 #[derive(Clone)]
@@ -14,20 +32,83 @@ pub struct AppState {
     pub banned_token_store: BannedTokenStoreType,
     pub two_fa_code_store: TwoFACodeStoreType,
     pub email_client: EmailClientType,
+    pub refresh_token_store: RefreshTokenStoreType,
+    pub one_time_token_store: OneTimeTokenStoreType,
+    pub totp_secret_store: TotpSecretStoreType,
+    pub recovery_code_store: RecoveryCodeStoreType,
+    pub passkey_store: PasskeyStoreType,
+    pub rate_limit_store: RateLimitStoreType,
+    pub session_store: SessionStoreType,
+    pub oauth_client_store: OAuthClientStoreType,
+    pub authorization_code_store: AuthorizationCodeStoreType,
+    pub service_client_store: ServiceClientStoreType,
+    pub linked_identity_store: LinkedIdentityStoreType,
+    /// Emails are queued here and delivered with `email_client` by the
+    /// outbox worker.
+    pub email_outbox_store: EmailOutboxStoreType,
+    /// Providers users can log in with through `/login/:provider`.
+    pub identity_providers: Vec<IdentityProviderType>,
+    /// Whether `/verify-token` only answers service clients with the
+    /// `tokens:verify` scope. Off by default so existing callers keep working.
+    pub verify_token_requires_client: bool,
 }
 
 impl AppState {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         user_store: UserStoreType,
         banned_token_store: BannedTokenStoreType,
         two_fa_code_store: TwoFACodeStoreType,
         email_client: EmailClientType,
+        refresh_token_store: RefreshTokenStoreType,
+        one_time_token_store: OneTimeTokenStoreType,
+        totp_secret_store: TotpSecretStoreType,
+        recovery_code_store: RecoveryCodeStoreType,
+        passkey_store: PasskeyStoreType,
+        rate_limit_store: RateLimitStoreType,
+        session_store: SessionStoreType,
+        oauth_client_store: OAuthClientStoreType,
+        authorization_code_store: AuthorizationCodeStoreType,
+        service_client_store: ServiceClientStoreType,
+        linked_identity_store: LinkedIdentityStoreType,
+        email_outbox_store: EmailOutboxStoreType,
     ) -> Self {
         Self {
             user_store,
             banned_token_store,
             two_fa_code_store,
             email_client,
+            refresh_token_store,
+            one_time_token_store,
+            totp_secret_store,
+            recovery_code_store,
+            passkey_store,
+            rate_limit_store,
+            session_store,
+            oauth_client_store,
+            authorization_code_store,
+            service_client_store,
+            linked_identity_store,
+            email_outbox_store,
+            identity_providers: Vec::new(),
+            verify_token_requires_client: false,
         }
     }
+
+    pub fn with_verify_token_requires_client(mut self, required: bool) -> Self {
+        self.verify_token_requires_client = required;
+        self
+    }
+
+    pub fn with_identity_provider(mut self, provider: IdentityProviderType) -> Self {
+        self.identity_providers.push(provider);
+        self
+    }
+
+    pub fn identity_provider(&self, name: &str) -> Option<IdentityProviderType> {
+        self.identity_providers
+            .iter()
+            .find(|provider| provider.name() == name)
+            .cloned()
+    }
 }
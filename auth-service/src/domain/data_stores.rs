@@ -1,8 +1,13 @@
 use crate::domain::Password;
 
-use super::{Email, User};
-use rand::{thread_rng, Rng};
+use super::{
+    AccountLock, AuthorizationCode, AuthorizationGrant, ClientSecret, Email, EmailMessage,
+    OAuthClient, ServiceClient, TwoFAMethod, User,
+};
+use chrono::{DateTime, Utc};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
 use serde::Serialize;
+use std::net::IpAddr;
 use uuid::Uuid;
 
 #[async_trait::async_trait]
@@ -11,6 +16,25 @@ pub trait UserStore {
     async fn get_user(&self, email: &Email) -> Result<User, UserStoreError>;
     async fn validate_user(&self, email: &Email, password: &Password)
         -> Result<(), UserStoreError>;
+    async fn update_password(
+        &mut self,
+        email: &Email,
+        password: Password,
+    ) -> Result<(), UserStoreError>;
+    async fn mark_email_verified(&mut self, email: &Email) -> Result<(), UserStoreError>;
+    async fn delete_user(&mut self, email: &Email) -> Result<(), UserStoreError>;
+    async fn set_two_fa(
+        &mut self,
+        email: &Email,
+        requires_2fa: bool,
+        method: TwoFAMethod,
+    ) -> Result<(), UserStoreError>;
+    /// Returns the new version.
+    async fn increment_token_version(&mut self, email: &Email) -> Result<i32, UserStoreError>;
+    async fn lock_user(&mut self, email: &Email, lock: AccountLock) -> Result<(), UserStoreError>;
+    async fn unlock_user(&mut self, email: &Email) -> Result<(), UserStoreError>;
+    /// Users whose lock has not expired yet.
+    async fn get_locked_users(&self) -> Result<Vec<User>, UserStoreError>;
 }
 
 #[derive(Debug, PartialEq)]
@@ -18,13 +42,14 @@ pub enum UserStoreError {
     UserAlreadyExists,
     UserNotFound,
     InvalidCredentials,
+    AccountLocked,
     UnexpectedError,
 }
 
 #[async_trait::async_trait]
 pub trait BannedTokenStore {
-    async fn add_token(&mut self, token: String) -> Result<(), BannedTokenStoreError>;
-    async fn contains_token(&self, token: String) -> Result<bool, BannedTokenStoreError>;
+    async fn add_token(&mut self, jti: String) -> Result<(), BannedTokenStoreError>;
+    async fn contains_token(&self, jti: String) -> Result<bool, BannedTokenStoreError>;
 }
 
 #[derive(Debug, PartialEq)]
@@ -34,18 +59,20 @@ pub enum BannedTokenStoreError {
     UnexpectedError,
 }
 
+/// Pending second login steps. Only users who get their code by email have a
+/// code stored; TOTP users just have the login attempt recorded.
 #[async_trait::async_trait]
 pub trait TwoFACodeStore {
     async fn add_code(
         &mut self,
         email: Email,
         login_attempt_id: LoginAttemptId,
-        code: TwoFACode,
+        code: Option<TwoFACode>,
     ) -> Result<(), TwoFACodeStoreError>;
     async fn get_code(
         &self,
         email: &Email,
-    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError>;
+    ) -> Result<(LoginAttemptId, Option<TwoFACode>), TwoFACodeStoreError>;
     async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError>;
 }
 
@@ -55,6 +82,302 @@ pub enum TwoFACodeStoreError {
     UnexpectedError,
 }
 
+#[async_trait::async_trait]
+pub trait RefreshTokenStore {
+    async fn add_token(
+        &mut self,
+        token: RefreshToken,
+        record: RefreshTokenRecord,
+    ) -> Result<(), RefreshTokenStoreError>;
+    async fn get_token(
+        &self,
+        token: &RefreshToken,
+    ) -> Result<RefreshTokenRecord, RefreshTokenStoreError>;
+    async fn mark_token_used(&mut self, token: &RefreshToken)
+        -> Result<(), RefreshTokenStoreError>;
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError>;
+    async fn revoke_user(&mut self, email: &Email) -> Result<(), RefreshTokenStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RefreshTokenStoreError {
+    TokenNotFound,
+    UnexpectedError,
+}
+
+/// TOTP shared secrets. A freshly enrolled secret stays pending until the user
+/// proves their authenticator app produces matching codes, so re-enrolling
+/// never breaks the secret that is currently in use. The time step of the last
+/// accepted code is kept so a code can't be used twice.
+#[async_trait::async_trait]
+pub trait TotpSecretStore {
+    async fn set_pending_secret(
+        &mut self,
+        email: &Email,
+        secret: Vec<u8>,
+    ) -> Result<(), TotpSecretStoreError>;
+    async fn get_pending_secret(&self, email: &Email) -> Result<Vec<u8>, TotpSecretStoreError>;
+    async fn confirm_pending_secret(&mut self, email: &Email) -> Result<(), TotpSecretStoreError>;
+    async fn get_secret(&self, email: &Email) -> Result<Vec<u8>, TotpSecretStoreError>;
+    /// Fails with `CodeAlreadyUsed` unless `step` is later than the step of
+    /// the last accepted code.
+    async fn record_used_step(
+        &mut self,
+        email: &Email,
+        step: u64,
+    ) -> Result<(), TotpSecretStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TotpSecretStoreError {
+    SecretNotFound,
+    CodeAlreadyUsed,
+    UnexpectedError,
+}
+
+/// Backup codes for users who lost access to their second factor. Each code
+/// can be redeemed once, and storing a new batch replaces the previous one.
+#[async_trait::async_trait]
+pub trait RecoveryCodeStore {
+    async fn replace_codes(
+        &mut self,
+        email: &Email,
+        codes: Vec<RecoveryCode>,
+    ) -> Result<(), RecoveryCodeStoreError>;
+    async fn consume_code(
+        &mut self,
+        email: &Email,
+        code: &RecoveryCode,
+    ) -> Result<(), RecoveryCodeStoreError>;
+    async fn count_codes(&self, email: &Email) -> Result<usize, RecoveryCodeStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RecoveryCodeStoreError {
+    CodeNotFound,
+    UnexpectedError,
+}
+
+/// WebAuthn credentials registered by users. A user may register several,
+/// e.g. one per device.
+#[async_trait::async_trait]
+pub trait PasskeyStore {
+    async fn add_passkey(&mut self, passkey: Passkey) -> Result<(), PasskeyStoreError>;
+    async fn get_passkeys(&self, email: &Email) -> Result<Vec<Passkey>, PasskeyStoreError>;
+    async fn update_sign_count(
+        &mut self,
+        credential_id: &[u8],
+        sign_count: u32,
+    ) -> Result<(), PasskeyStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PasskeyStoreError {
+    PasskeyAlreadyExists,
+    PasskeyNotFound,
+    UnexpectedError,
+}
+
+/// Failed authentication attempts, counted per key (an account, a client IP or
+/// a single 2FA login attempt). Records expire after the given TTL.
+#[async_trait::async_trait]
+pub trait RateLimitStore {
+    async fn get_attempts(&self, key: &str) -> Result<FailedAttempts, RateLimitStoreError>;
+    async fn set_attempts(
+        &mut self,
+        key: &str,
+        attempts: FailedAttempts,
+        ttl_seconds: i64,
+    ) -> Result<(), RateLimitStoreError>;
+    async fn clear_attempts(&mut self, key: &str) -> Result<(), RateLimitStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RateLimitStoreError {
+    UnexpectedError,
+}
+
+/// Every issued access token, keyed by its `jti`, so users can see where they
+/// are logged in and end sessions remotely. A session moves to the new `jti`
+/// each time its refresh token family issues a new access token.
+#[async_trait::async_trait]
+pub trait SessionStore {
+    /// Replaces any session with the same id.
+    async fn add_session(&mut self, session: Session) -> Result<(), SessionStoreError>;
+    async fn get_session(&self, id: &str) -> Result<Session, SessionStoreError>;
+    async fn get_sessions(&self, email: &Email) -> Result<Vec<Session>, SessionStoreError>;
+    async fn remove_session(&mut self, id: &str) -> Result<(), SessionStoreError>;
+    async fn remove_user_sessions(&mut self, email: &Email) -> Result<(), SessionStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SessionStoreError {
+    SessionNotFound,
+    UnexpectedError,
+}
+
+/// Registered OAuth2 clients, together with the scopes each user has allowed
+/// them so far.
+#[async_trait::async_trait]
+pub trait OAuthClientStore {
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthClientStoreError>;
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthClientStoreError>;
+    /// Scopes the user has consented to. Empty if they never authorized the
+    /// client.
+    async fn get_consent(
+        &self,
+        email: &Email,
+        client_id: &str,
+    ) -> Result<Vec<String>, OAuthClientStoreError>;
+    /// Adds to the scopes the user has consented to.
+    async fn grant_consent(
+        &mut self,
+        email: &Email,
+        client_id: &str,
+        scopes: &[String],
+    ) -> Result<(), OAuthClientStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum OAuthClientStoreError {
+    ClientAlreadyExists,
+    ClientNotFound,
+    UnexpectedError,
+}
+
+/// Registered service clients. Only a hash of each client's secret is kept.
+#[async_trait::async_trait]
+pub trait ServiceClientStore {
+    async fn add_client(
+        &mut self,
+        client: ServiceClient,
+        secret: &ClientSecret,
+    ) -> Result<(), ServiceClientStoreError>;
+    /// Returns the client if the secret is its own.
+    async fn validate_client(
+        &self,
+        client_id: &str,
+        secret: &ClientSecret,
+    ) -> Result<ServiceClient, ServiceClientStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ServiceClientStoreError {
+    ClientAlreadyExists,
+    InvalidCredentials,
+    UnexpectedError,
+}
+
+/// Accounts at external identity providers that users log in with, keyed by
+/// the provider's name and its id for the user.
+#[async_trait::async_trait]
+pub trait LinkedIdentityStore {
+    async fn add_identity(
+        &mut self,
+        provider: &str,
+        subject: &str,
+        email: &Email,
+    ) -> Result<(), LinkedIdentityStoreError>;
+    /// Returns the email of the user the identity is linked to.
+    async fn get_email(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Email, LinkedIdentityStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LinkedIdentityStoreError {
+    IdentityAlreadyLinked,
+    IdentityNotFound,
+    UnexpectedError,
+}
+
+/// Emails waiting for the outbox worker to deliver them, and the ones it
+/// gave up on.
+#[async_trait::async_trait]
+pub trait EmailOutboxStore {
+    async fn enqueue(
+        &mut self,
+        recipient: &Email,
+        message: &EmailMessage,
+    ) -> Result<(), EmailOutboxStoreError>;
+    /// Claims up to `limit` emails that are due and counts the attempt. They
+    /// are hidden from other workers for `lease_seconds`, after which they
+    /// are due again in case the worker died delivering them.
+    async fn claim_due(
+        &mut self,
+        limit: i64,
+        lease_seconds: i64,
+    ) -> Result<Vec<QueuedEmail>, EmailOutboxStoreError>;
+    /// Removes a delivered email.
+    async fn mark_delivered(&mut self, id: Uuid) -> Result<(), EmailOutboxStoreError>;
+    async fn schedule_retry(
+        &mut self,
+        id: Uuid,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), EmailOutboxStoreError>;
+    /// Moves an email that can't be delivered to the dead letters.
+    async fn dead_letter(&mut self, id: Uuid, error: &str) -> Result<(), EmailOutboxStoreError>;
+    async fn get_stats(&self) -> Result<EmailOutboxStats, EmailOutboxStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EmailOutboxStoreError {
+    EmailNotFound,
+    UnexpectedError,
+}
+
+/// Issued authorization codes. Like one-time tokens they are removed when
+/// consumed, and expire after `AUTHORIZATION_CODE_TTL_SECONDS`.
+#[async_trait::async_trait]
+pub trait AuthorizationCodeStore {
+    async fn add_code(
+        &mut self,
+        code: AuthorizationCode,
+        grant: AuthorizationGrant,
+    ) -> Result<(), AuthorizationCodeStoreError>;
+    async fn consume_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> Result<AuthorizationGrant, AuthorizationCodeStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AuthorizationCodeStoreError {
+    CodeNotFound,
+    UnexpectedError,
+}
+
+/// Single-use tokens that are emailed to a user, e.g. in password reset links.
+/// Consuming a token removes it, so it can only ever be redeemed once.
+#[async_trait::async_trait]
+pub trait OneTimeTokenStore {
+    async fn add_token(
+        &mut self,
+        token: OneTimeToken,
+        purpose: TokenPurpose,
+        email: Email,
+    ) -> Result<(), OneTimeTokenStoreError>;
+    async fn consume_token(
+        &mut self,
+        token: &OneTimeToken,
+        purpose: TokenPurpose,
+    ) -> Result<Email, OneTimeTokenStoreError>;
+    async fn last_issued_at(
+        &self,
+        email: &Email,
+        purpose: TokenPurpose,
+    ) -> Result<Option<DateTime<Utc>>, OneTimeTokenStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum OneTimeTokenStoreError {
+    TokenNotFound,
+    UnexpectedError,
+}
+
 #[derive(Serialize, Debug, Clone, PartialEq)]
 pub struct LoginAttemptId(String);
 
@@ -104,3 +427,222 @@ impl AsRef<str> for TwoFACode {
         &self.0
     }
 }
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RefreshToken(String);
+
+impl RefreshToken {
+    pub fn parse(token: String) -> Result<Self, String> {
+        if token.len() == REFRESH_TOKEN_LENGTH && token.chars().all(|c| c.is_ascii_alphanumeric()) {
+            Ok(Self(token))
+        } else {
+            Err("Invalid refresh token".to_string())
+        }
+    }
+}
+
+impl Default for RefreshToken {
+    fn default() -> Self {
+        let token = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(REFRESH_TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        Self(token)
+    }
+}
+
+impl AsRef<str> for RefreshToken {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+const REFRESH_TOKEN_LENGTH: usize = 64;
+
+/// A recovery code of the form `xxxxx-xxxxx`. Codes are case-insensitive and
+/// always stored lowercase.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RecoveryCode(String);
+
+impl RecoveryCode {
+    pub fn parse(code: String) -> Result<Self, String> {
+        let code = code.trim().to_ascii_lowercase();
+        let valid = code.len() == RECOVERY_CODE_LENGTH
+            && code.chars().enumerate().all(|(i, c)| {
+                if i == RECOVERY_CODE_GROUP_LENGTH {
+                    c == '-'
+                } else {
+                    c.is_ascii_lowercase() || c.is_ascii_digit()
+                }
+            });
+
+        if valid {
+            Ok(Self(code))
+        } else {
+            Err("Invalid recovery code".to_string())
+        }
+    }
+}
+
+impl Default for RecoveryCode {
+    fn default() -> Self {
+        let mut rng = thread_rng();
+        let mut group = || -> String {
+            (0..RECOVERY_CODE_GROUP_LENGTH)
+                .map(|_| {
+                    let i = rng.gen_range(0..RECOVERY_CODE_ALPHABET.len());
+                    RECOVERY_CODE_ALPHABET[i] as char
+                })
+                .collect()
+        };
+        Self(format!("{}-{}", group(), group()))
+    }
+}
+
+impl AsRef<str> for RecoveryCode {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+const RECOVERY_CODE_GROUP_LENGTH: usize = 5;
+const RECOVERY_CODE_LENGTH: usize = 2 * RECOVERY_CODE_GROUP_LENGTH + 1;
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OneTimeToken(String);
+
+impl OneTimeToken {
+    pub fn parse(token: String) -> Result<Self, String> {
+        if token.len() == ONE_TIME_TOKEN_LENGTH && token.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            Ok(Self(token))
+        } else {
+            Err("Invalid one-time token".to_string())
+        }
+    }
+}
+
+impl Default for OneTimeToken {
+    fn default() -> Self {
+        let token = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(ONE_TIME_TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        Self(token)
+    }
+}
+
+impl AsRef<str> for OneTimeToken {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+const ONE_TIME_TOKEN_LENGTH: usize = 48;
+
+/// What a one-time token was issued for. A token is only accepted for the
+/// purpose it was issued with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenPurpose {
+    PasswordReset,
+    EmailVerification,
+    /// The challenge of a WebAuthn ceremony.
+    PasskeyRegistration,
+    PasskeyLogin,
+    AccountUnlock,
+}
+
+impl TokenPurpose {
+    pub fn ttl_seconds(&self) -> i64 {
+        match self {
+            TokenPurpose::PasswordReset => 60 * 15,          // 15 minutes
+            TokenPurpose::EmailVerification => 60 * 60 * 24, // 24 hours
+            TokenPurpose::AccountUnlock => 60 * 60 * 24,     // 24 hours
+            TokenPurpose::PasskeyRegistration | TokenPurpose::PasskeyLogin => 60 * 5, // 5 minutes
+        }
+    }
+}
+
+impl AsRef<str> for TokenPurpose {
+    fn as_ref(&self) -> &str {
+        match self {
+            TokenPurpose::PasswordReset => "password_reset",
+            TokenPurpose::EmailVerification => "email_verification",
+            TokenPurpose::PasskeyRegistration => "passkey_registration",
+            TokenPurpose::PasskeyLogin => "passkey_login",
+            TokenPurpose::AccountUnlock => "account_unlock",
+        }
+    }
+}
+
+/// Every refresh token belongs to a family that starts at login. Rotating a
+/// token marks the old one as used and issues a new member of the same family,
+/// so presenting a used token means the family has leaked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RefreshTokenRecord {
+    pub email: Email,
+    pub family_id: String,
+    pub used: bool,
+}
+
+impl RefreshTokenRecord {
+    pub fn new(email: &Email, family_id: String) -> Self {
+        Self {
+            email: email.clone(),
+            family_id,
+            used: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Passkey {
+    pub credential_id: Vec<u8>,
+    pub email: Email,
+    /// SEC1 encoded P-256 public key.
+    pub public_key: Vec<u8>,
+    /// Signature counter reported by the authenticator. Authenticators that
+    /// don't implement it always report 0.
+    pub sign_count: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FailedAttempts {
+    pub count: u32,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Session {
+    /// The `jti` of the session's latest access token.
+    pub id: String,
+    pub email: Email,
+    /// The refresh token family that keeps the session alive.
+    pub family_id: String,
+    pub user_agent: Option<String>,
+    pub ip: IpAddr,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueuedEmail {
+    pub id: Uuid,
+    pub recipient: Email,
+    pub message: EmailMessage,
+    /// Delivery attempts so far, the claimed one included.
+    pub attempts: i32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EmailOutboxStats {
+    /// Emails waiting for delivery, retries included.
+    pub pending: i64,
+    pub dead_lettered: i64,
+    /// When the longest waiting email was queued, to tell a stuck queue from
+    /// a busy one.
+    pub oldest_pending_at: Option<DateTime<Utc>>,
+}
@@ -1,11 +1,31 @@
 use super::Email;
 
+/// A rendered email. The text body is for clients that don't show HTML.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmailMessage {
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: String,
+}
+
 #[async_trait::async_trait]
 pub trait EmailClient {
     async fn send_email(
         &self,
         recipient: &Email,
-        subject: &str,
-        content: &str,
-    ) -> Result<(), String>;
+        message: &EmailMessage,
+    ) -> Result<(), EmailClientError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EmailClientError {
+    /// The provider refused the email, like for an invalid address or bad
+    /// credentials. Sending it again won't help.
+    Rejected(String),
+    /// The provider didn't answer in time.
+    Timeout,
+    /// The provider couldn't be reached or failed on its side, so the email
+    /// may go through later.
+    Unavailable(String),
+    UnexpectedError(String),
 }
@@ -1,10 +1,60 @@
 #[derive(Debug, PartialEq)]
 pub enum AuthAPIError {
     UserAlreadyExists,
+    UserNotFound,
+    SessionNotFound,
+    IdentityProviderNotFound,
     InvalidCredentials,
     UnexpectedError,
     UnprocessableContent,
     IncorrectCredentials,
     MissingToken,
     InvalidToken,
+    /// The requested audience is not one of the configured `JWT_AUDIENCES`.
+    UnknownAudience,
+    /// The caller is not an authenticated service client.
+    InvalidClient,
+    /// The caller's token lacks the scope the route requires.
+    InsufficientScope,
+    EmailNotVerified,
+    TotpNotEnrolled,
+    TwoFANotEnabled,
+    RecoveryCodesAlreadyExist,
+    AccountLocked,
+    /// Carries the number of seconds until the client may retry.
+    TooManyRequests(u64),
+}
+
+/// Errors of the OAuth2 endpoints. They are reported to clients with the
+/// error codes of RFC 6749, either in the body or on the redirect.
+#[derive(Debug, PartialEq)]
+pub enum OAuthError {
+    InvalidRequest,
+    InvalidClient,
+    InvalidGrant,
+    InvalidScope,
+    AccessDenied,
+    UnsupportedGrantType,
+    UnsupportedResponseType,
+    UnsupportedTokenType,
+    /// The caller's token lacks the scope the endpoint requires (RFC 6750).
+    InsufficientScope,
+    ServerError,
+}
+
+impl AsRef<str> for OAuthError {
+    fn as_ref(&self) -> &str {
+        match self {
+            OAuthError::InvalidRequest => "invalid_request",
+            OAuthError::InvalidClient => "invalid_client",
+            OAuthError::InvalidGrant => "invalid_grant",
+            OAuthError::InvalidScope => "invalid_scope",
+            OAuthError::AccessDenied => "access_denied",
+            OAuthError::UnsupportedGrantType => "unsupported_grant_type",
+            OAuthError::UnsupportedResponseType => "unsupported_response_type",
+            OAuthError::UnsupportedTokenType => "unsupported_token_type",
+            OAuthError::InsufficientScope => "insufficient_scope",
+            OAuthError::ServerError => "server_error",
+        }
+    }
 }
@@ -0,0 +1,48 @@
+use super::{CodeChallenge, Email};
+
+/// Who an external identity provider says the user logging in is.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExternalIdentity {
+    /// The provider's stable id for the user. Unlike the email, it never
+    /// changes, so linked identities are looked up by it.
+    pub subject: String,
+    pub email: Email,
+    /// Whether the provider checked that the user owns the email. Identities
+    /// are only ever matched to our users by verified emails.
+    pub email_verified: bool,
+}
+
+/// A service users can log in with instead of a password, through the
+/// OAuth2 authorization code flow with PKCE.
+#[async_trait::async_trait]
+pub trait IdentityProvider {
+    /// Identifies the provider in the `/login/:provider` routes and in linked
+    /// identities, so it must not change once users have logged in.
+    fn name(&self) -> &str;
+
+    /// Where to send the user to log in with the provider.
+    fn authorization_url(
+        &self,
+        redirect_uri: &str,
+        state: &str,
+        code_challenge: &CodeChallenge,
+    ) -> String;
+
+    /// Trades the code the provider redirected the user back with for their
+    /// identity.
+    async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        redirect_uri: &str,
+    ) -> Result<ExternalIdentity, IdentityProviderError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum IdentityProviderError {
+    /// The provider rejected the authorization code.
+    InvalidCode,
+    /// The provider has no email address for the user.
+    MissingEmail,
+    UnexpectedError(String),
+}
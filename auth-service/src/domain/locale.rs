@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+/// The languages emails are translated into.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Fr,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 3] = [Locale::En, Locale::De, Locale::Fr];
+
+    /// Accepts language tags like `de` or `de-AT`. Regions share the
+    /// translation of their language.
+    pub fn parse(tag: &str) -> Result<Self, String> {
+        let language = tag.split(['-', '_']).next().unwrap_or_default();
+        Self::ALL
+            .into_iter()
+            .find(|locale| locale.as_ref().eq_ignore_ascii_case(language))
+            .ok_or_else(|| format!("{} is not a supported locale", tag))
+    }
+
+    /// Picks the supported language the client prefers most, from an
+    /// `Accept-Language` header value like `fr-CH, fr;q=0.9, en;q=0.8`.
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        let mut preferred: Option<(Locale, f32)> = None;
+        for entry in header.split(',') {
+            let mut parts = entry.split(';');
+            let Ok(locale) = Self::parse(parts.next().unwrap_or_default().trim()) else {
+                continue;
+            };
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())
+                .unwrap_or(0.0);
+            // Ties go to the language listed first.
+            if quality > 0.0 && preferred.is_none_or(|(_, best)| quality > best) {
+                preferred = Some((locale, quality));
+            }
+        }
+        preferred.map(|(locale, _)| locale)
+    }
+}
+
+impl AsRef<str> for Locale {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::En => "en",
+            Self::De => "de",
+            Self::Fr => "fr",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale_should_parse_language_tags() {
+        assert_eq!(Locale::parse("de"), Ok(Locale::De));
+        assert_eq!(Locale::parse("fr-CA"), Ok(Locale::Fr));
+        assert_eq!(Locale::parse("EN_gb"), Ok(Locale::En));
+        assert!(Locale::parse("es").is_err());
+        assert!(Locale::parse("").is_err());
+    }
+
+    #[test]
+    fn accept_language_should_pick_preferred_supported_locale() {
+        let test_cases = [
+            ("de", Some(Locale::De)),
+            ("es-ES, fr;q=0.8, en;q=0.5", Some(Locale::Fr)),
+            ("en;q=0.3, de-AT;q=0.7", Some(Locale::De)),
+            ("fr, de", Some(Locale::Fr)),
+            ("de;q=0, en;q=0.1", Some(Locale::En)),
+            ("*", None),
+            ("es, it;q=0.9", None),
+            ("", None),
+        ];
+
+        for (header, expected) in test_cases {
+            assert_eq!(Locale::from_accept_language(header), expected, "{}", header);
+        }
+    }
+}
@@ -2,6 +2,9 @@ pub mod data_stores;
 pub mod email;
 pub mod email_client;
 pub mod error;
+pub mod identity_provider;
+pub mod locale;
+pub mod oauth;
 pub mod password;
 pub mod user;
 
@@ -9,5 +12,8 @@ pub use data_stores::*;
 pub use email::*;
 pub use email_client::*;
 pub use error::*;
+pub use identity_provider::*;
+pub use locale::*;
+pub use oauth::*;
 pub use password::*;
 pub use user::*;
@@ -0,0 +1,341 @@
+use super::Email;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+/// Scopes clients may request, with the description shown on the consent
+/// page.
+pub const SUPPORTED_SCOPES: &[(&str, &str)] = &[
+    ("openid", "Sign you in with your account"),
+    ("email", "See your email address"),
+    ("profile", "See your account id"),
+];
+
+/// An application that logs users in through us. Clients are public, so they
+/// prove they started an authorization with PKCE rather than a secret.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OAuthClient {
+    pub client_id: String,
+    pub name: String,
+    /// Authorization codes are only ever sent to one of these.
+    pub redirect_uris: Vec<String>,
+}
+
+impl OAuthClient {
+    pub fn new(name: String, redirect_uris: Vec<String>) -> Result<Self, String> {
+        let name = name.trim().to_owned();
+        if name.is_empty() {
+            return Err("Client name must not be empty".to_string());
+        }
+        if redirect_uris.is_empty() {
+            return Err("At least one redirect URI is required".to_string());
+        }
+        for redirect_uri in &redirect_uris {
+            let url = Url::parse(redirect_uri).map_err(|_| "Invalid redirect URI".to_string())?;
+            if url.cannot_be_a_base() || url.fragment().is_some() {
+                return Err("Invalid redirect URI".to_string());
+            }
+        }
+
+        Ok(Self {
+            client_id: uuid::Uuid::new_v4().to_string(),
+            name,
+            redirect_uris,
+        })
+    }
+
+    /// Redirect URIs have to match a registered one exactly.
+    pub fn allows_redirect_uri(&self, redirect_uri: &str) -> bool {
+        self.redirect_uris.iter().any(|uri| uri == redirect_uri)
+    }
+}
+
+/// Scopes service clients can be registered with.
+pub const SERVICE_SCOPES: &[(&str, &str)] = &[
+    (
+        VERIFY_TOKEN_SCOPE,
+        "Verify user access tokens through /verify-token",
+    ),
+    (
+        INTROSPECT_TOKEN_SCOPE,
+        "Inspect any of our tokens through /introspect",
+    ),
+];
+
+pub const VERIFY_TOKEN_SCOPE: &str = "tokens:verify";
+pub const INTROSPECT_TOKEN_SCOPE: &str = "tokens:introspect";
+
+/// A backend service that authenticates as itself, with the client
+/// credentials grant, rather than on behalf of a user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServiceClient {
+    pub client_id: String,
+    pub name: String,
+    /// The most a token issued to the client may carry.
+    pub scopes: Vec<String>,
+}
+
+impl ServiceClient {
+    pub fn new(name: String, scopes: Vec<String>) -> Result<Self, String> {
+        let name = name.trim().to_owned();
+        if name.is_empty() {
+            return Err("Client name must not be empty".to_string());
+        }
+        let scopes = parse_service_scopes(scopes.iter().map(String::as_str))?;
+        if scopes.is_empty() {
+            return Err("At least one scope is required".to_string());
+        }
+
+        Ok(Self {
+            client_id: uuid::Uuid::new_v4().to_string(),
+            name,
+            scopes,
+        })
+    }
+
+    /// The scopes a token request is granted. Requesting none grants all the
+    /// client's scopes.
+    pub fn grant_scopes(&self, scope: Option<&str>) -> Result<Vec<String>, String> {
+        let scopes = parse_service_scopes(scope.unwrap_or_default().split_whitespace())?;
+        if scopes.is_empty() {
+            return Ok(self.scopes.clone());
+        }
+        match scopes.iter().all(|scope| self.scopes.contains(scope)) {
+            true => Ok(scopes),
+            false => Err("Scope not granted to client".to_string()),
+        }
+    }
+}
+
+/// Generated by us when a service client is registered, and only shown
+/// once. Stores keep a hash of it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ClientSecret(String);
+
+impl ClientSecret {
+    pub fn parse(secret: String) -> Result<Self, String> {
+        match secret.is_empty() {
+            true => Err("Invalid client secret".to_string()),
+            false => Ok(Self(secret)),
+        }
+    }
+}
+
+impl Default for ClientSecret {
+    fn default() -> Self {
+        let secret = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(CLIENT_SECRET_LENGTH)
+            .map(char::from)
+            .collect();
+        Self(secret)
+    }
+}
+
+impl AsRef<str> for ClientSecret {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+const CLIENT_SECRET_LENGTH: usize = 48;
+
+/// Splits a space-delimited `scope` parameter, rejecting scopes we don't
+/// support. Duplicates are dropped.
+pub fn parse_scopes(scope: Option<&str>) -> Result<Vec<String>, String> {
+    let mut scopes: Vec<String> = Vec::new();
+    for scope in scope.unwrap_or_default().split_whitespace() {
+        if !SUPPORTED_SCOPES.iter().any(|(name, _)| *name == scope) {
+            return Err(format!("Unsupported scope: {}", scope));
+        }
+        if !scopes.iter().any(|existing| existing == scope) {
+            scopes.push(scope.to_owned());
+        }
+    }
+    Ok(scopes)
+}
+
+fn parse_service_scopes<'a>(
+    requested: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<String>, String> {
+    let mut scopes: Vec<String> = Vec::new();
+    for scope in requested {
+        if !SERVICE_SCOPES.iter().any(|(name, _)| *name == scope) {
+            return Err(format!("Unsupported scope: {}", scope));
+        }
+        if !scopes.iter().any(|existing| existing == scope) {
+            scopes.push(scope.to_owned());
+        }
+    }
+    Ok(scopes)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AuthorizationCode(String);
+
+impl AuthorizationCode {
+    pub fn parse(code: String) -> Result<Self, String> {
+        if code.len() == AUTHORIZATION_CODE_LENGTH
+            && code.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            Ok(Self(code))
+        } else {
+            Err("Invalid authorization code".to_string())
+        }
+    }
+}
+
+impl Default for AuthorizationCode {
+    fn default() -> Self {
+        let code = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(AUTHORIZATION_CODE_LENGTH)
+            .map(char::from)
+            .collect();
+        Self(code)
+    }
+}
+
+impl AsRef<str> for AuthorizationCode {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+const AUTHORIZATION_CODE_LENGTH: usize = 48;
+
+/// Authorization codes are exchanged by the client's backend right after the
+/// redirect, so they only need to live briefly.
+pub const AUTHORIZATION_CODE_TTL_SECONDS: i64 = 60;
+
+/// What the user authorized when an authorization code was issued.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthorizationGrant {
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub email: Email,
+    pub scopes: Vec<String>,
+    pub code_challenge: CodeChallenge,
+    /// Passed on to the ID token, if one is issued.
+    pub nonce: Option<String>,
+}
+
+/// A PKCE code challenge (RFC 7636). Only the `S256` method is supported:
+/// the challenge is the unpadded base64url encoded SHA-256 of the verifier.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CodeChallenge(String);
+
+impl CodeChallenge {
+    pub fn parse(challenge: String) -> Result<Self, String> {
+        match URL_SAFE_NO_PAD.decode(&challenge) {
+            Ok(digest) if digest.len() == 32 => Ok(Self(challenge)),
+            _ => Err("Invalid code challenge".to_string()),
+        }
+    }
+
+    /// The challenge for a verifier of our own, for when we are the client.
+    pub fn from_verifier(code_verifier: &str) -> Self {
+        Self(URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier)))
+    }
+
+    pub fn verify(&self, code_verifier: &str) -> bool {
+        let valid_verifier = (43..=128).contains(&code_verifier.len())
+            && code_verifier
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-._~".contains(c));
+
+        valid_verifier && URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier)) == self.0
+    }
+}
+
+impl AsRef<str> for CodeChallenge {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The example from RFC 7636, appendix B.
+    const CODE_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    const CODE_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+    #[test]
+    fn test_code_challenge_verifies_matching_verifier() {
+        let challenge = CodeChallenge::parse(CODE_CHALLENGE.to_owned()).unwrap();
+        assert!(challenge.verify(CODE_VERIFIER));
+        assert!(!challenge.verify(&CODE_VERIFIER.replace('d', "e")));
+        assert!(!challenge.verify("too-short"));
+    }
+
+    #[test]
+    fn test_code_challenge_from_verifier() {
+        assert_eq!(
+            CodeChallenge::from_verifier(CODE_VERIFIER).as_ref(),
+            CODE_CHALLENGE
+        );
+    }
+
+    #[test]
+    fn test_code_challenge_rejects_malformed_challenge() {
+        assert!(CodeChallenge::parse("not base64!".to_owned()).is_err());
+        assert!(CodeChallenge::parse("c2hvcnQ".to_owned()).is_err());
+    }
+
+    #[test]
+    fn test_parse_scopes() {
+        assert_eq!(parse_scopes(None), Ok(vec![]));
+        assert_eq!(
+            parse_scopes(Some("email  profile email")),
+            Ok(vec!["email".to_owned(), "profile".to_owned()])
+        );
+        assert!(parse_scopes(Some("email admin")).is_err());
+    }
+
+    #[test]
+    fn test_service_client_grants_registered_scopes() {
+        let client = ServiceClient::new(
+            "app-service".to_owned(),
+            vec![VERIFY_TOKEN_SCOPE.to_owned()],
+        )
+        .unwrap();
+
+        assert_eq!(
+            client.grant_scopes(None),
+            Ok(vec![VERIFY_TOKEN_SCOPE.to_owned()])
+        );
+        assert_eq!(
+            client.grant_scopes(Some(VERIFY_TOKEN_SCOPE)),
+            Ok(vec![VERIFY_TOKEN_SCOPE.to_owned()])
+        );
+        assert!(client.grant_scopes(Some("email")).is_err());
+
+        assert!(ServiceClient::new("app-service".to_owned(), vec![]).is_err());
+        assert!(ServiceClient::new("app-service".to_owned(), vec!["email".to_owned()]).is_err());
+    }
+
+    #[test]
+    fn test_new_client_validates_redirect_uris() {
+        let client = OAuthClient::new(
+            "Wiki".to_owned(),
+            vec!["https://wiki.example.com/callback".to_owned()],
+        )
+        .unwrap();
+        assert!(client.allows_redirect_uri("https://wiki.example.com/callback"));
+        assert!(!client.allows_redirect_uri("https://wiki.example.com/callback/"));
+
+        assert!(OAuthClient::new("Wiki".to_owned(), vec![]).is_err());
+        assert!(OAuthClient::new("Wiki".to_owned(), vec!["/callback".to_owned()]).is_err());
+        assert!(OAuthClient::new(
+            "Wiki".to_owned(),
+            vec!["https://wiki.example.com/#callback".to_owned()]
+        )
+        .is_err());
+        assert!(
+            OAuthClient::new(" ".to_owned(), vec!["https://wiki.example.com".to_owned()]).is_err()
+        );
+    }
+}
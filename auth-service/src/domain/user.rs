@@ -1,18 +1,82 @@
-use super::{email::Email, password::Password};
+use super::{email::Email, locale::Locale, password::Password};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct User {
+    pub id: Uuid,
     pub email: Email,
     pub password: Password,
     pub requires_2fa: bool,
+    pub email_verified: bool,
+    pub two_fa_method: TwoFAMethod,
+    pub lock: Option<AccountLock>,
+    /// Embedded in every token issued to the user. Bumping it invalidates
+    /// all tokens issued before.
+    pub token_version: i32,
+    /// The language of the user's emails. Without one, emails follow the
+    /// `Accept-Language` of the request that sends them.
+    pub locale: Option<Locale>,
 }
 
 impl User {
     pub fn new(email: Email, password: Password, requires_2fa: bool) -> Self {
         User {
+            id: Uuid::new_v4(),
             email,
             password,
             requires_2fa,
+            email_verified: false,
+            two_fa_method: TwoFAMethod::Email,
+            lock: None,
+            token_version: 0,
+            locale: None,
+        }
+    }
+
+    /// Whether the account is locked right now. Expired locks are kept
+    /// around until the account is unlocked or locked again.
+    pub fn is_locked(&self) -> bool {
+        self.lock
+            .as_ref()
+            .is_some_and(|lock| lock.locked_until > Utc::now())
+    }
+}
+
+/// A durable lock on an account, set after repeated failed logins.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AccountLock {
+    pub locked_until: DateTime<Utc>,
+    pub reason: String,
+}
+
+/// How a user with `requires_2fa` proves the second factor at login.
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TwoFAMethod {
+    /// A six digit code sent through the `EmailClient`.
+    #[default]
+    Email,
+    /// A code from an authenticator app, see RFC 6238.
+    Totp,
+}
+
+impl TwoFAMethod {
+    pub fn parse(method: &str) -> Result<Self, String> {
+        match method {
+            "email" => Ok(Self::Email),
+            "totp" => Ok(Self::Totp),
+            _ => Err(format!("{} is not a valid 2FA method", method)),
+        }
+    }
+}
+
+impl AsRef<str> for TwoFAMethod {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Email => "email",
+            Self::Totp => "totp",
         }
     }
 }
@@ -1,17 +1,30 @@
 use app_state::AppState;
 use axum::{
-    http::{Method, StatusCode},
+    extract::connect_info::IntoMakeServiceWithConnectInfo,
+    extract::ConnectInfo,
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::AddExtension,
     response::{IntoResponse, Response},
-    routing::post,
+    routing::{delete, get, post},
     serve::Serve,
     Json, Router,
 };
-use domain::error::AuthAPIError;
+use domain::error::{AuthAPIError, OAuthError};
 use redis::{Client, RedisResult};
-use routes::{login, logout, signup, verify_2fa, verify_token};
+use routes::{
+    admin_unlock_account, authorize, authorize_consent, change_password, confirm_password_reset,
+    confirm_totp, delete_account, delete_session, enroll_totp, finish_passkey_login,
+    finish_passkey_registration, finish_social_login, generate_recovery_codes,
+    get_email_outbox_stats, get_locked_accounts, get_sessions, introspect, jwks, login, logout,
+    logout_all, openid_configuration, password_reset_form, refresh, regenerate_recovery_codes,
+    register_oauth_client, register_service_client, request_password_reset, resend_2fa,
+    resend_verification_email, revoke, set_two_fa_method, signup, start_passkey_login,
+    start_passkey_registration, start_social_login, token, unlock_account, unlock_account_link,
+    userinfo, verify_2fa, verify_email, verify_email_link, verify_token,
+};
 use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgPoolOptions, PgPool};
-use std::error::Error;
+use std::{error::Error, net::SocketAddr};
 use tower_http::{cors::CorsLayer, services::ServeDir, trace::TraceLayer};
 use utils::{make_span_with_request_id, on_request, on_response};
 
@@ -23,7 +36,10 @@ pub mod utils;
 
 // This struct encapsulates our application-related logic.
 pub struct Application {
-    server: Serve<Router, Router>,
+    server: Serve<
+        IntoMakeServiceWithConnectInfo<Router, SocketAddr>,
+        AddExtension<Router, ConnectInfo<SocketAddr>>,
+    >,
     // address is exposed as a public field
     // so we have access to it in tests.
     pub address: String,
@@ -37,7 +53,7 @@ impl Application {
         ];
 
         let cors = CorsLayer::new()
-            .allow_methods([Method::GET, Method::POST])
+            .allow_methods([Method::GET, Method::POST, Method::DELETE])
             .allow_credentials(true)
             .allow_origin(allowed_origins);
 
@@ -45,9 +61,57 @@ impl Application {
             .nest_service("/", ServeDir::new("assets"))
             .route("/signup", post(signup))
             .route("/login", post(login))
+            .route("/login/:provider", get(start_social_login))
+            .route("/login/:provider/callback", get(finish_social_login))
             .route("/logout", post(logout))
+            .route("/logout-all", post(logout_all))
+            .route("/refresh", post(refresh))
+            .route("/change-password", post(change_password))
+            .route("/account", delete(delete_account))
+            .route("/password-reset", get(password_reset_form))
+            .route("/password-reset/request", post(request_password_reset))
+            .route("/password-reset/confirm", post(confirm_password_reset))
+            .route("/sessions", get(get_sessions))
+            .route("/sessions/:id", delete(delete_session))
             .route("/verify-2fa", post(verify_2fa))
+            .route("/resend-2fa", post(resend_2fa))
+            .route("/2fa/method", post(set_two_fa_method))
+            .route("/totp/enroll", post(enroll_totp))
+            .route("/totp/confirm", post(confirm_totp))
+            .route("/recovery-codes", post(generate_recovery_codes))
+            .route(
+                "/recovery-codes/regenerate",
+                post(regenerate_recovery_codes),
+            )
+            .route("/passkeys/register/start", post(start_passkey_registration))
+            .route(
+                "/passkeys/register/finish",
+                post(finish_passkey_registration),
+            )
+            .route("/passkeys/login/start", post(start_passkey_login))
+            .route("/passkeys/login/finish", post(finish_passkey_login))
+            .route(
+                "/unlock-account",
+                get(unlock_account_link).post(unlock_account),
+            )
+            .route("/admin/locked-accounts", get(get_locked_accounts))
+            .route("/admin/unlock-account", post(admin_unlock_account))
+            .route("/admin/oauth-clients", post(register_oauth_client))
+            .route("/admin/service-clients", post(register_service_client))
+            .route("/admin/email-outbox", get(get_email_outbox_stats))
+            .route("/authorize", get(authorize).post(authorize_consent))
+            .route("/token", post(token))
+            .route("/introspect", post(introspect))
+            .route("/revoke", post(revoke))
+            .route("/userinfo", get(userinfo).post(userinfo))
+            .route("/verify-email", get(verify_email_link).post(verify_email))
+            .route("/verify-email/resend", post(resend_verification_email))
             .route("/verify-token", post(verify_token))
+            .route("/.well-known/jwks.json", get(jwks))
+            .route(
+                "/.well-known/openid-configuration",
+                get(openid_configuration),
+            )
             .with_state(app_state)
             .layer(cors)
             .layer(
@@ -59,7 +123,11 @@ impl Application {
 
         let listener = tokio::net::TcpListener::bind(address).await?;
         let address = listener.local_addr()?.to_string();
-        let server = axum::serve(listener, router);
+        // Handlers read the client address for per-IP rate limiting.
+        let server = axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        );
 
         Ok(Self { server, address })
     }
@@ -81,17 +149,39 @@ impl IntoResponse for AuthAPIError {
             // 400::BAD_REQUEST
             AuthAPIError::InvalidCredentials => (StatusCode::BAD_REQUEST, "Invalid credentials"),
             AuthAPIError::MissingToken => (StatusCode::BAD_REQUEST, "Missing token"),
+            AuthAPIError::TotpNotEnrolled => (StatusCode::BAD_REQUEST, "TOTP not enrolled"),
+            AuthAPIError::TwoFANotEnabled => (StatusCode::BAD_REQUEST, "2FA not enabled"),
+            AuthAPIError::UnknownAudience => (StatusCode::BAD_REQUEST, "Unknown audience"),
             // 401::UNAUTHORIZED
             AuthAPIError::IncorrectCredentials => {
                 (StatusCode::UNAUTHORIZED, "Incorrect credentials")
             }
             AuthAPIError::InvalidToken => (StatusCode::UNAUTHORIZED, "Invalid Token"),
+            AuthAPIError::InvalidClient => (StatusCode::UNAUTHORIZED, "Invalid client credentials"),
+            // 403::FORBIDDEN
+            AuthAPIError::EmailNotVerified => (StatusCode::FORBIDDEN, "Email not verified"),
+            AuthAPIError::InsufficientScope => (StatusCode::FORBIDDEN, "Insufficient scope"),
+            // 404::NOT_FOUND
+            AuthAPIError::UserNotFound => (StatusCode::NOT_FOUND, "User not found"),
+            AuthAPIError::SessionNotFound => (StatusCode::NOT_FOUND, "Session not found"),
+            AuthAPIError::IdentityProviderNotFound => {
+                (StatusCode::NOT_FOUND, "Identity provider not found")
+            }
             // 409::CONFLICT
             AuthAPIError::UserAlreadyExists => (StatusCode::CONFLICT, "User already exists"),
+            AuthAPIError::RecoveryCodesAlreadyExist => {
+                (StatusCode::CONFLICT, "Recovery codes already exist")
+            }
             // 422::UNPROCESSABLE_ENTITY
             AuthAPIError::UnprocessableContent => {
                 (StatusCode::UNPROCESSABLE_ENTITY, "Malformed credentials")
             }
+            // 423::LOCKED
+            AuthAPIError::AccountLocked => (StatusCode::LOCKED, "Account locked"),
+            // 429::TOO_MANY_REQUESTS
+            AuthAPIError::TooManyRequests(_) => {
+                (StatusCode::TOO_MANY_REQUESTS, "Too many requests")
+            }
             // 500::INTERNAL_SERVER_ERROR
             AuthAPIError::UnexpectedError => {
                 (StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error")
@@ -100,7 +190,39 @@ impl IntoResponse for AuthAPIError {
         let body = Json(ErrorResponse {
             error: error_message.to_string(),
         });
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+
+        if let AuthAPIError::TooManyRequests(retry_after_seconds) = self {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_seconds));
+        }
+
+        response
+    }
+}
+
+impl IntoResponse for OAuthError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            OAuthError::InvalidClient => StatusCode::UNAUTHORIZED,
+            OAuthError::InsufficientScope => StatusCode::FORBIDDEN,
+            OAuthError::ServerError => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        let body = Json(ErrorResponse {
+            error: self.as_ref().to_string(),
+        });
+        let mut response = (status, [(header::CACHE_CONTROL, "no-store")], body).into_response();
+
+        // RFC 6749 asks for a challenge with failed client authentication.
+        if self == OAuthError::InvalidClient {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Basic"));
+        }
+
+        response
     }
 }
 
@@ -1,36 +1,108 @@
 use auth_service::{
-    app_state::AppState,
+    app_state::{AppState, EmailClientType},
     get_postgres_pool, get_redis_client,
-    services::{MockEmailClient, PostgresUserStore, RedisBannedTokenStore, RedisTwoFACodeStore},
+    services::{
+        EmailOutboxWorker, HttpEmailClient, HttpEmailConfig, MockEmailClient,
+        OAuth2IdentityProvider, OAuth2ProviderConfig, PostgresEmailOutboxStore,
+        PostgresLinkedIdentityStore, PostgresOAuthClientStore, PostgresPasskeyStore,
+        PostgresRecoveryCodeStore, PostgresServiceClientStore, PostgresTotpSecretStore,
+        PostgresUserStore, RedisAuthorizationCodeStore, RedisBannedTokenStore,
+        RedisOneTimeTokenStore, RedisRateLimitStore, RedisRefreshTokenStore, RedisSessionStore,
+        RedisTwoFACodeStore, SmtpConfig, SmtpEmailClient, SmtpTls,
+    },
     utils::{
-        constants::{prod, DATABASE_URL},
-        init_tracing, REDIS_HOST_NAME,
+        auth::{reload_key_ring, KEY_RING},
+        constants::{
+            prod, DATABASE_URL, EMAIL_API_MAX_RETRIES, EMAIL_API_RETRY_DELAY_MILLIS,
+            EMAIL_API_TIMEOUT_SECONDS, EMAIL_API_TOKEN, EMAIL_API_URL, EMAIL_PROVIDER,
+            EMAIL_SENDER, GITHUB_CLIENT_CREDENTIALS, GOOGLE_CLIENT_CREDENTIALS, SMTP_CREDENTIALS,
+            SMTP_HOST, SMTP_PORT, SMTP_TIMEOUT_SECONDS, SMTP_TLS, TOTP_ENCRYPTION_KEY,
+            VERIFY_TOKEN_REQUIRES_CLIENT,
+        },
+        init_tracing,
+        totp::SecretCipher,
+        REDIS_HOST_NAME,
     },
     Application,
 };
 use sqlx::PgPool;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::RwLock,
+};
 
 #[tokio::main]
 async fn main() {
     init_tracing();
+    lazy_static::initialize(&KEY_RING);
+    tokio::spawn(reload_key_ring_on_hangup());
     let pg_pool = configure_postgresql().await;
     let redis_connection = Arc::new(RwLock::new(configure_redis()));
 
-    let user_store = Arc::new(RwLock::new(PostgresUserStore::new(pg_pool)));
+    let totp_cipher = SecretCipher::from_base64(&TOTP_ENCRYPTION_KEY)
+        .expect("TOTP_ENCRYPTION_KEY must be 32 bytes encoded as base64.");
+
+    let user_store = Arc::new(RwLock::new(PostgresUserStore::new(pg_pool.clone())));
     let banned_token_store = Arc::new(RwLock::new(RedisBannedTokenStore::new(
         redis_connection.clone(),
     )));
-    let two_fa_code_store = Arc::new(RwLock::new(RedisTwoFACodeStore::new(redis_connection)));
-    let email_client = Arc::new(RwLock::new(MockEmailClient));
+    let two_fa_code_store = Arc::new(RwLock::new(RedisTwoFACodeStore::new(
+        redis_connection.clone(),
+    )));
+    let refresh_token_store = Arc::new(RwLock::new(RedisRefreshTokenStore::new(
+        redis_connection.clone(),
+    )));
+    let one_time_token_store = Arc::new(RwLock::new(RedisOneTimeTokenStore::new(
+        redis_connection.clone(),
+    )));
+    let rate_limit_store = Arc::new(RwLock::new(RedisRateLimitStore::new(
+        redis_connection.clone(),
+    )));
+    let session_store = Arc::new(RwLock::new(RedisSessionStore::new(
+        redis_connection.clone(),
+    )));
+    let authorization_code_store = Arc::new(RwLock::new(RedisAuthorizationCodeStore::new(
+        redis_connection,
+    )));
+    let totp_secret_store = Arc::new(RwLock::new(PostgresTotpSecretStore::new(
+        pg_pool.clone(),
+        totp_cipher,
+    )));
+    let recovery_code_store =
+        Arc::new(RwLock::new(PostgresRecoveryCodeStore::new(pg_pool.clone())));
+    let passkey_store = Arc::new(RwLock::new(PostgresPasskeyStore::new(pg_pool.clone())));
+    let oauth_client_store = Arc::new(RwLock::new(PostgresOAuthClientStore::new(pg_pool.clone())));
+    let service_client_store = Arc::new(RwLock::new(PostgresServiceClientStore::new(
+        pg_pool.clone(),
+    )));
+    let linked_identity_store = Arc::new(RwLock::new(PostgresLinkedIdentityStore::new(
+        pg_pool.clone(),
+    )));
+    let email_outbox_store = Arc::new(RwLock::new(PostgresEmailOutboxStore::new(pg_pool)));
+    let email_client = configure_email_client();
+    tokio::spawn(EmailOutboxWorker::new(email_outbox_store.clone(), email_client.clone()).run());
 
     let app_state = AppState::new(
         user_store,
         banned_token_store,
         two_fa_code_store,
         email_client,
-    );
+        refresh_token_store,
+        one_time_token_store,
+        totp_secret_store,
+        recovery_code_store,
+        passkey_store,
+        rate_limit_store,
+        session_store,
+        oauth_client_store,
+        authorization_code_store,
+        service_client_store,
+        linked_identity_store,
+        email_outbox_store,
+    )
+    .with_verify_token_requires_client(*VERIFY_TOKEN_REQUIRES_CLIENT);
+    let app_state = configure_identity_providers(app_state);
     let app = Application::build(app_state, prod::APP_ADDRESS)
         .await
         .expect("Failed to build app");
@@ -49,9 +121,79 @@ async fn configure_postgresql() -> PgPool {
     pg_pool
 }
 
+/// Picks the email client by `EMAIL_PROVIDER`: `mock` (the default) only
+/// prints emails, which is enough for local development, `smtp` sends them
+/// through an SMTP server and `http` through the email provider's HTTP API.
+fn configure_email_client() -> EmailClientType {
+    match EMAIL_PROVIDER.as_str() {
+        "mock" => Arc::new(RwLock::new(MockEmailClient)),
+        "smtp" => Arc::new(RwLock::new(configure_smtp_email_client())),
+        "http" => Arc::new(RwLock::new(configure_http_email_client())),
+        provider => panic!("Unknown EMAIL_PROVIDER: {}", provider),
+    }
+}
+
+fn configure_smtp_email_client() -> SmtpEmailClient {
+    let config = SmtpConfig {
+        host: SMTP_HOST.clone().expect("SMTP_HOST must be set."),
+        port: *SMTP_PORT,
+        tls: SmtpTls::parse(&SMTP_TLS).expect("Invalid SMTP_TLS"),
+        credentials: SMTP_CREDENTIALS.clone(),
+        sender: EMAIL_SENDER.clone(),
+        timeout: Duration::from_secs(SMTP_TIMEOUT_SECONDS),
+    };
+
+    SmtpEmailClient::new(config).expect("Failed to configure SMTP email client")
+}
+
+fn configure_http_email_client() -> HttpEmailClient {
+    let config = HttpEmailConfig {
+        base_url: EMAIL_API_URL.clone(),
+        api_token: EMAIL_API_TOKEN
+            .clone()
+            .expect("EMAIL_API_TOKEN must be set."),
+        sender: EMAIL_SENDER.clone(),
+        timeout: Duration::from_secs(EMAIL_API_TIMEOUT_SECONDS),
+        max_retries: EMAIL_API_MAX_RETRIES,
+        retry_delay: Duration::from_millis(EMAIL_API_RETRY_DELAY_MILLIS),
+    };
+
+    HttpEmailClient::new(config).expect("Failed to configure HTTP email client")
+}
+
+/// Registers the providers we have client credentials for.
+fn configure_identity_providers(mut app_state: AppState) -> AppState {
+    let configs = [
+        GOOGLE_CLIENT_CREDENTIALS
+            .clone()
+            .map(|(id, secret)| OAuth2ProviderConfig::google(id, secret)),
+        GITHUB_CLIENT_CREDENTIALS
+            .clone()
+            .map(|(id, secret)| OAuth2ProviderConfig::github(id, secret)),
+    ];
+    for config in configs.into_iter().flatten() {
+        let provider =
+            OAuth2IdentityProvider::new(config).expect("Failed to configure identity provider");
+        app_state = app_state.with_identity_provider(Arc::new(provider));
+    }
+
+    app_state
+}
+
 fn configure_redis() -> redis::Connection {
     get_redis_client(REDIS_HOST_NAME.to_owned())
         .expect("Failed to get Redis client")
         .get_connection()
         .expect("Failed to get Redis Connection")
 }
+
+async fn reload_key_ring_on_hangup() {
+    let mut hangup = signal(SignalKind::hangup()).expect("Failed to listen for SIGHUP");
+
+    while hangup.recv().await.is_some() {
+        match reload_key_ring() {
+            Ok(()) => tracing::info!("Reloaded JWT signing keys"),
+            Err(e) => tracing::error!("Failed to reload JWT signing keys: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, ClientSecret, Email, OAuthClient, ServiceClient},
+    routes::lift_account_lock,
+    utils::{auth::bearer_token, constants::ADMIN_API_KEY},
+};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[tracing::instrument(name = "List locked accounts", skip_all, err(Debug))]
+pub async fn get_locked_accounts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_admin(&headers)?;
+
+    let locked_accounts = state
+        .user_store
+        .read()
+        .await
+        .get_locked_users()
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?
+        .into_iter()
+        .filter_map(|user| {
+            user.lock.map(|lock| LockedAccount {
+                email: user.email.as_ref().to_owned(),
+                locked_until: lock.locked_until.to_rfc3339(),
+                reason: lock.reason,
+            })
+        })
+        .collect();
+
+    let response = Json(LockedAccountsResponse { locked_accounts });
+
+    Ok((StatusCode::OK, response))
+}
+
+#[tracing::instrument(name = "Admin unlock account", skip_all, err(Debug))]
+pub async fn admin_unlock_account(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<AdminUnlockAccountRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_admin(&headers)?;
+
+    let email = Email::parse(request.email).map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    lift_account_lock(&state, &email).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[tracing::instrument(name = "Register OAuth client", skip_all, err(Debug))]
+pub async fn register_oauth_client(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<RegisterOAuthClientRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_admin(&headers)?;
+
+    let client = OAuthClient::new(request.name, request.redirect_uris)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    state
+        .oauth_client_store
+        .write()
+        .await
+        .add_client(client.clone())
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    let response = Json(OAuthClientResponse {
+        client_id: client.client_id,
+        name: client.name,
+        redirect_uris: client.redirect_uris,
+    });
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Registers a backend service. The secret is only ever returned here.
+#[tracing::instrument(name = "Register service client", skip_all, err(Debug))]
+pub async fn register_service_client(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<RegisterServiceClientRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_admin(&headers)?;
+
+    let client = ServiceClient::new(request.name, request.scopes)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let secret = ClientSecret::default();
+
+    state
+        .service_client_store
+        .write()
+        .await
+        .add_client(client.clone(), &secret)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    let response = Json(ServiceClientResponse {
+        client_id: client.client_id,
+        client_secret: secret.as_ref().to_owned(),
+        name: client.name,
+        scopes: client.scopes,
+    });
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// How many emails wait in the outbox, and how many it gave up on.
+#[tracing::instrument(name = "Get email outbox stats", skip_all, err(Debug))]
+pub async fn get_email_outbox_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_admin(&headers)?;
+
+    let stats = state
+        .email_outbox_store
+        .read()
+        .await
+        .get_stats()
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    let response = Json(EmailOutboxStatsResponse {
+        pending: stats.pending,
+        dead_lettered: stats.dead_lettered,
+        oldest_pending_at: stats
+            .oldest_pending_at
+            .map(|oldest_pending_at| oldest_pending_at.to_rfc3339()),
+    });
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Checks the `Authorization: Bearer` header against `ADMIN_API_KEY`.
+fn authorize_admin(headers: &HeaderMap) -> Result<(), AuthAPIError> {
+    let token = bearer_token(headers).ok_or(AuthAPIError::MissingToken)?;
+
+    let admin_api_key = ADMIN_API_KEY.as_deref().ok_or(AuthAPIError::InvalidToken)?;
+
+    // Comparing digests keeps the time taken independent of how much of the
+    // key was guessed right.
+    if Sha256::digest(token) != Sha256::digest(admin_api_key) {
+        return Err(AuthAPIError::InvalidToken);
+    }
+
+    Ok(())
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct LockedAccountsResponse {
+    #[serde(rename = "lockedAccounts")]
+    pub locked_accounts: Vec<LockedAccount>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct LockedAccount {
+    pub email: String,
+    #[serde(rename = "lockedUntil")]
+    pub locked_until: String,
+    pub reason: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct EmailOutboxStatsResponse {
+    pub pending: i64,
+    #[serde(rename = "deadLettered")]
+    pub dead_lettered: i64,
+    #[serde(rename = "oldestPendingAt")]
+    pub oldest_pending_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AdminUnlockAccountRequest {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct RegisterOAuthClientRequest {
+    pub name: String,
+    #[serde(rename = "redirectUris")]
+    pub redirect_uris: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OAuthClientResponse {
+    #[serde(rename = "clientId")]
+    pub client_id: String,
+    pub name: String,
+    #[serde(rename = "redirectUris")]
+    pub redirect_uris: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RegisterServiceClientRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ServiceClientResponse {
+    #[serde(rename = "clientId")]
+    pub client_id: String,
+    #[serde(rename = "clientSecret")]
+    pub client_secret: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+}
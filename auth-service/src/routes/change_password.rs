@@ -0,0 +1,147 @@
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, Email, Password, UserStoreError},
+    routes::send_security_alert,
+    utils::{
+        auth::{authenticated_claims, generate_session_cookies, ClientInfo},
+        email_templates::{requested_locale, SecurityEvent},
+    },
+};
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::extract::CookieJar;
+use serde::Deserialize;
+use std::net::SocketAddr;
+
+#[tracing::instrument(name = "Change password", skip_all)]
+pub async fn change_password(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Json(request): Json<ChangePasswordRequest>,
+) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    let claims = match authenticated_claims(&state, &jar).await {
+        Ok(claims) => claims,
+        Err(e) => return (jar, Err(e)),
+    };
+    let email = match Email::parse(claims.email.clone()) {
+        Ok(val) => val,
+        Err(_) => return (jar, Err(AuthAPIError::InvalidToken)),
+    };
+
+    let current_password = match Password::parse(request.current_password) {
+        Ok(val) => val,
+        Err(_) => return (jar, Err(AuthAPIError::InvalidCredentials)),
+    };
+    let new_password = match Password::parse(request.new_password) {
+        Ok(val) => val,
+        Err(_) => return (jar, Err(AuthAPIError::InvalidCredentials)),
+    };
+
+    let mut user_store = state.user_store.write().await;
+
+    if let Err(e) = user_store
+        .validate_user(&email, &current_password)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+            UserStoreError::InvalidCredentials => AuthAPIError::IncorrectCredentials,
+            UserStoreError::AccountLocked => AuthAPIError::AccountLocked,
+            _ => AuthAPIError::UnexpectedError,
+        })
+    {
+        return (jar, Err(e));
+    }
+
+    if user_store
+        .update_password(&email, new_password)
+        .await
+        .is_err()
+    {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+
+    // Invalidates the access tokens of every other session. The user is read
+    // afterwards so the fresh session below carries the new version.
+    if user_store.increment_token_version(&email).await.is_err() {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+
+    let user = match user_store.get_user(&email).await {
+        Ok(user) => user,
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+    };
+    drop(user_store);
+
+    // End every session of the user, then start a fresh one for this client so
+    // changing the password doesn't log the user out here.
+    if state
+        .refresh_token_store
+        .write()
+        .await
+        .revoke_user(&email)
+        .await
+        .is_err()
+    {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+    if state
+        .session_store
+        .write()
+        .await
+        .remove_user_sessions(&email)
+        .await
+        .is_err()
+    {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+    if state
+        .banned_token_store
+        .write()
+        .await
+        .add_token(claims.jti)
+        .await
+        .is_err()
+    {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+
+    let (auth_cookie, refresh_cookie) = match generate_session_cookies(
+        &user,
+        None,
+        &ClientInfo::new(&headers, addr),
+        state.refresh_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    {
+        Ok(val) => val,
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+    };
+
+    let updated_jar = jar.add(auth_cookie).add(refresh_cookie);
+
+    send_security_alert(
+        &state,
+        &email,
+        SecurityEvent::PasswordChanged,
+        addr.ip(),
+        requested_locale(&headers),
+    )
+    .await;
+
+    (updated_jar, Ok(StatusCode::OK))
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    #[serde(rename = "currentPassword")]
+    pub current_password: String,
+    #[serde(rename = "newPassword")]
+    pub new_password: String,
+}
@@ -0,0 +1,126 @@
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, Email, Password, TwoFACodeStoreError, UserStoreError},
+    routes::queue_email,
+    utils::{
+        auth::authenticated_claims,
+        constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+        email_templates::{requested_locale, EmailTemplate},
+    },
+};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::extract::CookieJar;
+use serde::Deserialize;
+
+#[tracing::instrument(name = "Delete account", skip_all)]
+pub async fn delete_account(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Json(request): Json<DeleteAccountRequest>,
+) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    let claims = match authenticated_claims(&state, &jar).await {
+        Ok(claims) => claims,
+        Err(e) => return (jar, Err(e)),
+    };
+    let email = match Email::parse(claims.email.clone()) {
+        Ok(val) => val,
+        Err(_) => return (jar, Err(AuthAPIError::InvalidToken)),
+    };
+    let password = match Password::parse(request.password) {
+        Ok(val) => val,
+        Err(_) => return (jar, Err(AuthAPIError::InvalidCredentials)),
+    };
+
+    let mut user_store = state.user_store.write().await;
+
+    if let Err(e) = user_store
+        .validate_user(&email, &password)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+            UserStoreError::InvalidCredentials => AuthAPIError::IncorrectCredentials,
+            UserStoreError::AccountLocked => AuthAPIError::AccountLocked,
+            _ => AuthAPIError::UnexpectedError,
+        })
+    {
+        return (jar, Err(e));
+    }
+
+    if user_store.delete_user(&email).await.is_err() {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+    drop(user_store);
+
+    match state
+        .two_fa_code_store
+        .write()
+        .await
+        .remove_code(&email)
+        .await
+    {
+        Ok(()) | Err(TwoFACodeStoreError::LoginAttemptIdNotFound) => {}
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+    }
+
+    if state
+        .refresh_token_store
+        .write()
+        .await
+        .revoke_user(&email)
+        .await
+        .is_err()
+    {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+
+    if state
+        .session_store
+        .write()
+        .await
+        .remove_user_sessions(&email)
+        .await
+        .is_err()
+    {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+
+    if state
+        .banned_token_store
+        .write()
+        .await
+        .add_token(claims.jti)
+        .await
+        .is_err()
+    {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+
+    // The account is gone either way, so a failed email is only logged.
+    if let Err(e) = queue_email(
+        &state,
+        &email,
+        &EmailTemplate::AccountDeleted,
+        requested_locale(&headers),
+    )
+    .await
+    {
+        tracing::error!("Failed to send account deletion email: {}", e);
+    }
+
+    let updated_jar = jar
+        .remove(JWT_COOKIE_NAME)
+        .remove(REFRESH_TOKEN_COOKIE_NAME);
+
+    (updated_jar, Ok(StatusCode::OK))
+}
+
+#[derive(Deserialize)]
+pub struct DeleteAccountRequest {
+    pub password: String,
+}
@@ -0,0 +1,50 @@
+use crate::{
+    app_state::AppState,
+    domain::{Email, Locale},
+    utils::email_templates::{EmailTemplate, SecurityEvent},
+};
+use std::net::IpAddr;
+
+/// Renders the email in the recipient's language and queues it for delivery.
+/// The locale saved for the user wins over the one the request asks for.
+pub async fn queue_email(
+    state: &AppState,
+    recipient: &Email,
+    template: &EmailTemplate<'_>,
+    requested_locale: Option<Locale>,
+) -> Result<(), String> {
+    // Deleted accounts have no user left to read the locale from.
+    let user_locale = state
+        .user_store
+        .read()
+        .await
+        .get_user(recipient)
+        .await
+        .ok()
+        .and_then(|user| user.locale);
+    let message = template.render(user_locale.or(requested_locale).unwrap_or_default())?;
+
+    state
+        .email_outbox_store
+        .write()
+        .await
+        .enqueue(recipient, &message)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+/// Tells the user about a change to their account, so they notice when
+/// someone else made it. The change stands either way, so a failed alert is
+/// only logged.
+pub async fn send_security_alert(
+    state: &AppState,
+    email: &Email,
+    event: SecurityEvent,
+    ip_address: IpAddr,
+    requested_locale: Option<Locale>,
+) {
+    let template = EmailTemplate::SecurityAlert { event, ip_address };
+    if let Err(e) = queue_email(state, email, &template, requested_locale).await {
+        tracing::error!("Failed to send security alert: {}", e);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{
+    app_state::AppState,
+    domain::{
+        BannedTokenStoreError, ClientSecret, OAuthError, RefreshToken, RefreshTokenStoreError,
+        ServiceClientStoreError, INTROSPECT_TOKEN_SCOPE,
+    },
+    routes::end_token_family,
+    utils::auth::{
+        basic_credentials, bearer_token, decode_access_token, validate_access_token,
+        validate_service_token, AccessTokenClaims,
+    },
+};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Form, Json,
+};
+use serde::{Deserialize, Serialize};
+
+/// Token introspection (RFC 7662), so API gateways can check tokens without
+/// knowing our token formats. Only service clients with the
+/// `tokens:introspect` scope may ask.
+#[tracing::instrument(name = "Introspect", skip_all, err(Debug))]
+pub async fn introspect(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(request): Form<TokenTypeRequest>,
+) -> Result<impl IntoResponse, OAuthError> {
+    authorize_introspection(&state, &headers).await?;
+    let token = request.token.ok_or(OAuthError::InvalidRequest)?;
+
+    // Refresh tokens are opaque, so they are told apart from our JWTs by
+    // their format rather than the optional `token_type_hint`.
+    let response = match RefreshToken::parse(token.clone()) {
+        Ok(refresh_token) => introspect_refresh_token(&state, &refresh_token).await?,
+        Err(_) => introspect_access_token(&state, &token).await,
+    };
+
+    Ok(([(header::CACHE_CONTROL, "no-store")], Json(response)))
+}
+
+/// Token revocation (RFC 7009). Holding a token is enough to revoke it, and
+/// unknown or expired tokens are accepted silently, as the spec asks.
+#[tracing::instrument(name = "Revoke", skip_all, err(Debug))]
+pub async fn revoke(
+    State(state): State<AppState>,
+    Form(request): Form<TokenTypeRequest>,
+) -> Result<impl IntoResponse, OAuthError> {
+    match request.token_type_hint.as_deref() {
+        None | Some("access_token") | Some("refresh_token") => {}
+        Some(_) => return Err(OAuthError::UnsupportedTokenType),
+    }
+    let token = request.token.ok_or(OAuthError::InvalidRequest)?;
+
+    if let Ok(refresh_token) = RefreshToken::parse(token.clone()) {
+        // Revoking a refresh token ends its whole session, including the
+        // access token it issued last.
+        let record = state
+            .refresh_token_store
+            .read()
+            .await
+            .get_token(&refresh_token)
+            .await;
+        match record {
+            Ok(record) => {
+                end_token_family(&state, &record)
+                    .await
+                    .map_err(|_| OAuthError::ServerError)?;
+                ban_token(&state, token).await?;
+            }
+            Err(RefreshTokenStoreError::TokenNotFound) => {}
+            Err(_) => return Err(OAuthError::ServerError),
+        }
+    } else if let Ok(claims) = decode_access_token(&token) {
+        ban_token(&state, claims.jti().to_owned()).await?;
+    }
+
+    Ok((StatusCode::OK, [(header::CACHE_CONTROL, "no-store")]))
+}
+
+/// Access tokens are banned by their `jti`, refresh tokens by their value.
+async fn ban_token(state: &AppState, token: String) -> Result<(), OAuthError> {
+    match state
+        .banned_token_store
+        .write()
+        .await
+        .add_token(token)
+        .await
+    {
+        Ok(()) | Err(BannedTokenStoreError::TokenAlreadyBanned) => Ok(()),
+        Err(_) => Err(OAuthError::ServerError),
+    }
+}
+
+/// Callers authenticate with a service client's credentials, or with an
+/// access token issued to one.
+async fn authorize_introspection(state: &AppState, headers: &HeaderMap) -> Result<(), OAuthError> {
+    let scopes = if let Some((client_id, secret)) = basic_credentials(headers) {
+        let secret = ClientSecret::parse(secret).map_err(|_| OAuthError::InvalidClient)?;
+        state
+            .service_client_store
+            .read()
+            .await
+            .validate_client(&client_id, &secret)
+            .await
+            .map_err(|e| match e {
+                ServiceClientStoreError::InvalidCredentials => OAuthError::InvalidClient,
+                _ => OAuthError::ServerError,
+            })?
+            .scopes
+    } else if let Some(token) = bearer_token(headers) {
+        validate_service_token(token, state.banned_token_store.clone())
+            .await
+            .map_err(|_| OAuthError::InvalidClient)?
+            .scope
+            .split_whitespace()
+            .map(str::to_owned)
+            .collect()
+    } else {
+        return Err(OAuthError::InvalidClient);
+    };
+
+    match scopes.iter().any(|scope| scope == INTROSPECT_TOKEN_SCOPE) {
+        true => Ok(()),
+        false => Err(OAuthError::InsufficientScope),
+    }
+}
+
+async fn introspect_refresh_token(
+    state: &AppState,
+    token: &RefreshToken,
+) -> Result<IntrospectionResponse, OAuthError> {
+    match state
+        .refresh_token_store
+        .read()
+        .await
+        .get_token(token)
+        .await
+    {
+        Ok(record) if !record.used => Ok(IntrospectionResponse {
+            username: Some(record.email.as_ref().to_owned()),
+            token_type: Some("refresh_token".to_owned()),
+            ..IntrospectionResponse::active()
+        }),
+        Ok(_) | Err(RefreshTokenStoreError::TokenNotFound) => Ok(IntrospectionResponse::inactive()),
+        Err(_) => Err(OAuthError::ServerError),
+    }
+}
+
+async fn introspect_access_token(state: &AppState, token: &str) -> IntrospectionResponse {
+    let claims = match validate_access_token(
+        token,
+        state.banned_token_store.clone(),
+        state.user_store.clone(),
+    )
+    .await
+    {
+        Ok(claims) => claims,
+        Err(_) => return IntrospectionResponse::inactive(),
+    };
+
+    let response = IntrospectionResponse {
+        token_type: Some("Bearer".to_owned()),
+        ..IntrospectionResponse::active()
+    };
+    match claims {
+        AccessTokenClaims::User(claims) => IntrospectionResponse {
+            // Only tokens issued to OAuth2 clients carry scopes, and their
+            // sole audience is the client.
+            client_id: claims
+                .scope
+                .as_ref()
+                .and_then(|_| claims.aud.first().cloned()),
+            scope: claims.scope,
+            username: Some(claims.email),
+            sub: Some(claims.sub),
+            aud: Some(claims.aud),
+            iss: Some(claims.iss),
+            exp: Some(claims.exp),
+            iat: Some(claims.iat),
+            nbf: Some(claims.nbf),
+            jti: Some(claims.jti),
+            ..response
+        },
+        AccessTokenClaims::Service(claims) => IntrospectionResponse {
+            client_id: Some(claims.sub.clone()),
+            scope: Some(claims.scope),
+            sub: Some(claims.sub),
+            aud: Some(claims.aud),
+            iss: Some(claims.iss),
+            exp: Some(claims.exp),
+            iat: Some(claims.iat),
+            nbf: Some(claims.nbf),
+            jti: Some(claims.jti),
+            ..response
+        },
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TokenTypeRequest {
+    pub token: Option<String>,
+    /// `access_token` or `refresh_token`.
+    pub token_type_hint: Option<String>,
+}
+
+/// Everything but `active` is left out for inactive tokens.
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct IntrospectionResponse {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// The user's email.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aud: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+}
+
+impl IntrospectionResponse {
+    fn active() -> Self {
+        Self {
+            active: true,
+            ..Self::default()
+        }
+    }
+
+    fn inactive() -> Self {
+        Self::default()
+    }
+}
@@ -0,0 +1,6 @@
+use crate::utils::auth::get_jwks;
+use axum::{response::IntoResponse, Json};
+
+pub async fn jwks() -> impl IntoResponse {
+    Json(get_jwks())
+}
@@ -1,14 +1,32 @@
 use crate::{
     app_state::AppState,
-    domain::{AuthAPIError, Email, LoginAttemptId, Password, TwoFACode, UserStoreError},
-    utils::auth::generate_auth_cookie,
+    domain::{
+        AuthAPIError, Email, Locale, LoginAttemptId, Password, TwoFACode, TwoFAMethod, User,
+        UserStoreError,
+    },
+    routes::{lock_account, queue_email},
+    utils::{
+        auth::{generate_session_cookies, ClientInfo},
+        email_templates::{requested_locale, EmailTemplate},
+        rate_limit::{
+            check_rate_limit, clear_failures, record_failure, RateLimitKey, ACCOUNT_LOCK_THRESHOLD,
+        },
+    },
+};
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
 };
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use axum_extra::extract::CookieJar;
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 
 pub async fn login(
     State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     jar: CookieJar,
     Json(request): Json<LoginRequest>,
 ) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
@@ -21,6 +39,11 @@ pub async fn login(
         Err(_) => return (jar, Err(AuthAPIError::InvalidCredentials)),
     };
 
+    let rate_limit_keys = [RateLimitKey::Account(&email), RateLimitKey::Ip(addr.ip())];
+    if let Err(e) = check_rate_limit(&state.rate_limit_store, &rate_limit_keys).await {
+        return (jar, Err(e));
+    }
+
     let user_store = state.user_store.read().await;
 
     if let Err(e) = user_store
@@ -29,78 +52,154 @@ pub async fn login(
         .map_err(|e| match e {
             UserStoreError::UserNotFound => AuthAPIError::IncorrectCredentials,
             UserStoreError::InvalidCredentials => AuthAPIError::IncorrectCredentials,
+            UserStoreError::AccountLocked => AuthAPIError::AccountLocked,
             _ => AuthAPIError::UnexpectedError,
         })
     {
-        return (jar, Err(e));
+        if e != AuthAPIError::IncorrectCredentials {
+            return (jar, Err(e));
+        }
+        drop(user_store);
+
+        return match record_failure(&state.rate_limit_store, &rate_limit_keys).await {
+            Ok(counts) if counts[0] >= ACCOUNT_LOCK_THRESHOLD => {
+                match lock_account(&state, &email, requested_locale(&headers)).await {
+                    Ok(_) => (jar, Err(AuthAPIError::AccountLocked)),
+                    Err(e) => {
+                        tracing::error!("Failed to lock account: {}", e);
+                        (jar, Err(AuthAPIError::UnexpectedError))
+                    }
+                }
+            }
+            Ok(_) => (jar, Err(e)),
+            Err(e) => (jar, Err(e)),
+        };
     }
 
     let user = match user_store.get_user(&email).await {
         Ok(user) => user,
         Err(_) => return (jar, Err(AuthAPIError::IncorrectCredentials)),
     };
+    drop(user_store);
+
+    if !user.email_verified {
+        return (jar, Err(AuthAPIError::EmailNotVerified));
+    }
 
     match user.requires_2fa {
-        true => handle_2fa(&user.email, &state, jar).await,
-        false => handle_no_2fa(&user.email, jar).await,
+        true => handle_2fa(&user, &state, requested_locale(&headers), jar).await,
+        false => {
+            let client = ClientInfo::new(&headers, addr);
+            handle_no_2fa(&user, &state, &client, jar).await
+        }
     }
 }
 
 async fn handle_2fa(
-    email: &Email,
+    user: &User,
     state: &AppState,
+    requested_locale: Option<Locale>,
     jar: CookieJar,
 ) -> (
     CookieJar,
     Result<(StatusCode, Json<LoginResponse>), AuthAPIError>,
 ) {
+    match start_2fa_login(state, user, requested_locale).await {
+        Ok(login_attempt_id) => (
+            jar,
+            Ok((
+                StatusCode::PARTIAL_CONTENT,
+                Json(LoginResponse::TwoFactorAuth(TwoFactorAuthResponse {
+                    message: "2FA required".to_string(),
+                    login_attempt_id: login_attempt_id.as_ref().to_string(),
+                    two_fa_method: user.two_fa_method,
+                })),
+            )),
+        ),
+        Err(e) => (jar, Err(e)),
+    }
+}
+
+/// Starts the second step of a login, which `/verify-2fa` completes.
+pub async fn start_2fa_login(
+    state: &AppState,
+    user: &User,
+    requested_locale: Option<Locale>,
+) -> Result<LoginAttemptId, AuthAPIError> {
     let login_attempt_id = LoginAttemptId::default();
-    let two_fa_code = TwoFACode::default();
-    let email_client = state.email_client.read().await;
+    let email = &user.email;
 
-    match state
+    // The login attempt is recorded for TOTP users too. They answer with a
+    // code from their authenticator app, so no code is generated for them.
+    let two_fa_code = match user.two_fa_method {
+        TwoFAMethod::Email => Some(TwoFACode::default()),
+        TwoFAMethod::Totp => None,
+    };
+    state
         .two_fa_code_store
         .write()
         .await
         .add_code(email.clone(), login_attempt_id.clone(), two_fa_code.clone())
         .await
-    {
-        Ok(_) => {
-            if email_client
-                .send_email(email, "2FA Code", two_fa_code.as_ref())
-                .await
-                .is_err()
-            {
-                return (jar, Err(AuthAPIError::UnexpectedError));
-            }
-            (
-                jar,
-                Ok((
-                    StatusCode::PARTIAL_CONTENT,
-                    Json(LoginResponse::TwoFactorAuth(TwoFactorAuthResponse {
-                        message: "2FA required".to_string(),
-                        login_attempt_id: login_attempt_id.as_ref().to_string(),
-                    })),
-                )),
-            )
-        }
-        Err(_) => (jar, Err(AuthAPIError::UnexpectedError)),
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    if let Some(two_fa_code) = two_fa_code {
+        send_2fa_code(state, email, &two_fa_code, requested_locale).await?;
     }
+
+    Ok(login_attempt_id)
 }
 
-async fn handle_no_2fa(
+/// Queues the email with the code for the second step of a login.
+pub async fn send_2fa_code(
+    state: &AppState,
     email: &Email,
+    two_fa_code: &TwoFACode,
+    requested_locale: Option<Locale>,
+) -> Result<(), AuthAPIError> {
+    let template = EmailTemplate::TwoFACode {
+        code: two_fa_code.as_ref(),
+    };
+    queue_email(state, email, &template, requested_locale)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)
+}
+
+async fn handle_no_2fa(
+    user: &User,
+    state: &AppState,
+    client: &ClientInfo,
     jar: CookieJar,
 ) -> (
     CookieJar,
     Result<(StatusCode, Json<LoginResponse>), AuthAPIError>,
 ) {
-    let auth_cookie = match generate_auth_cookie(email) {
+    let (auth_cookie, refresh_cookie) = match generate_session_cookies(
+        user,
+        None,
+        client,
+        state.refresh_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    {
         Ok(val) => val,
         Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
     };
 
-    let updated_jar = jar.add(auth_cookie);
+    // Failures only reset once the login is complete. Clearing them after the
+    // password step would hand out fresh guesses at the 2FA code.
+    if clear_failures(
+        &state.rate_limit_store,
+        &[RateLimitKey::Account(&user.email)],
+    )
+    .await
+    .is_err()
+    {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+
+    let updated_jar = jar.add(auth_cookie).add(refresh_cookie);
 
     (
         updated_jar,
@@ -120,6 +219,8 @@ pub struct TwoFactorAuthResponse {
     pub message: String,
     #[serde(rename = "loginAttemptId")]
     pub login_attempt_id: String,
+    #[serde(rename = "twoFAMethod")]
+    pub two_fa_method: TwoFAMethod,
 }
 
 #[derive(Deserialize)]
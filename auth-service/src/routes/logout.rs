@@ -1,7 +1,10 @@
 use crate::{
     app_state::AppState,
-    domain::AuthAPIError,
-    utils::{auth::validate_token, constants::JWT_COOKIE_NAME},
+    domain::{AuthAPIError, RefreshToken, SessionStoreError, UserStoreError},
+    utils::{
+        auth::{authenticated_claims, authenticated_email},
+        constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+    },
 };
 use axum::{extract::State, http::status::StatusCode, response::IntoResponse};
 use axum_extra::extract::CookieJar;
@@ -10,33 +13,104 @@ pub async fn logout(
     State(state): State<AppState>,
     jar: CookieJar,
 ) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
-    let cookie = jar.get(JWT_COOKIE_NAME);
-    if cookie.is_none() {
-        return (jar, Err(AuthAPIError::MissingToken));
+    let claims = match authenticated_claims(&state, &jar).await {
+        Ok(claims) => claims,
+        Err(e) => return (jar, Err(e)),
+    };
+
+    if state
+        .banned_token_store
+        .write()
+        .await
+        .add_token(claims.jti.clone())
+        .await
+        .is_err()
+    {
+        return (jar, Err(AuthAPIError::UnexpectedError));
     }
 
-    let cookie = cookie.unwrap();
-    let token = cookie.value().to_owned();
+    match state
+        .session_store
+        .write()
+        .await
+        .remove_session(&claims.jti)
+        .await
+    {
+        Ok(_) | Err(SessionStoreError::SessionNotFound) => {}
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+    }
+
+    // Logging out ends the whole session, not just the current access token.
+    if let Some(token) = jar
+        .get(REFRESH_TOKEN_COOKIE_NAME)
+        .and_then(|cookie| RefreshToken::parse(cookie.value().to_owned()).ok())
+    {
+        let mut refresh_token_store = state.refresh_token_store.write().await;
+        if let Ok(record) = refresh_token_store.get_token(&token).await {
+            if refresh_token_store
+                .revoke_family(&record.family_id)
+                .await
+                .is_err()
+            {
+                return (jar, Err(AuthAPIError::UnexpectedError));
+            }
+        }
+    }
 
-    let banned_token_store = state.banned_token_store;
+    let updated_jar = jar
+        .remove(JWT_COOKIE_NAME)
+        .remove(REFRESH_TOKEN_COOKIE_NAME);
 
-    if validate_token(token.as_ref(), banned_token_store.clone())
+    (updated_jar, Ok(StatusCode::OK))
+}
+
+/// Ends every session of the user. Bumping the token version invalidates all
+/// access tokens issued so far, including ones we hold no session for.
+pub async fn logout_all(
+    State(state): State<AppState>,
+    jar: CookieJar,
+) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    let email = match authenticated_email(&state, &jar).await {
+        Ok(val) => val,
+        Err(e) => return (jar, Err(e)),
+    };
+
+    match state
+        .user_store
+        .write()
+        .await
+        .increment_token_version(&email)
         .await
-        .is_err()
     {
-        return (jar, Err(AuthAPIError::InvalidToken));
+        Ok(_) => {}
+        Err(UserStoreError::UserNotFound) => return (jar, Err(AuthAPIError::InvalidToken)),
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
     }
 
-    if banned_token_store
+    if state
+        .refresh_token_store
         .write()
         .await
-        .add_token(token)
+        .revoke_user(&email)
         .await
         .is_err()
     {
         return (jar, Err(AuthAPIError::UnexpectedError));
     }
-    let updated_jar = jar.remove(JWT_COOKIE_NAME);
+    if state
+        .session_store
+        .write()
+        .await
+        .remove_user_sessions(&email)
+        .await
+        .is_err()
+    {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+
+    let updated_jar = jar
+        .remove(JWT_COOKIE_NAME)
+        .remove(REFRESH_TOKEN_COOKIE_NAME);
 
     (updated_jar, Ok(StatusCode::OK))
 }
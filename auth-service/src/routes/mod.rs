@@ -1,11 +1,49 @@
+mod admin;
+mod change_password;
+mod delete_account;
+mod emails;
+mod introspection;
+mod jwks;
 mod login;
 mod logout;
+mod oauth;
+mod openid;
+mod pages;
+mod passkeys;
+mod password_reset;
+mod recovery_codes;
+mod refresh;
+mod resend_2fa;
+mod sessions;
 mod signup;
+mod social_login;
+mod totp;
+mod unlock_account;
 mod verify_2fa;
+mod verify_email;
 mod verify_token;
 
+pub use admin::*;
+pub use change_password::*;
+pub use delete_account::*;
+pub use emails::*;
+pub use introspection::*;
+pub use jwks::*;
 pub use login::*;
 pub use logout::*;
+pub use oauth::*;
+pub use openid::*;
+pub use pages::*;
+pub use passkeys::*;
+pub use password_reset::*;
+pub use recovery_codes::*;
+pub use refresh::*;
+pub use resend_2fa::*;
+pub use sessions::*;
 pub use signup::*;
+pub use social_login::*;
+pub use totp::*;
+pub use unlock_account::*;
 pub use verify_2fa::*;
+pub use verify_email::*;
 pub use verify_token::*;
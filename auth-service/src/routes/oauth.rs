@@ -0,0 +1,466 @@
+use crate::{
+    app_state::AppState,
+    domain::{
+        parse_scopes, AuthorizationCode, AuthorizationCodeStoreError, AuthorizationGrant,
+        ClientSecret, CodeChallenge, Email, OAuthClient, OAuthClientStoreError, OAuthError,
+        ServiceClientStoreError, UserStoreError, SUPPORTED_SCOPES,
+    },
+    routes::escape_html,
+    utils::auth::{
+        authenticated_email, basic_credentials, generate_client_access_token, generate_id_token,
+        generate_service_token, SERVICE_TOKEN_TTL_SECONDS, TOKEN_TTL_SECONDS,
+    },
+};
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    response::{Html, IntoResponse, Redirect, Response},
+    Form, Json,
+};
+use axum_extra::extract::CookieJar;
+use serde::{Deserialize, Serialize};
+use url::{form_urlencoded, Url};
+
+/// Start of the authorization code grant. Users who are logged in and have
+/// already consented to the requested scopes are sent straight back to the
+/// client with a code, everyone else is shown the login or consent page first.
+#[tracing::instrument(name = "Authorize", skip_all, err(Debug))]
+pub async fn authorize(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    Query(request): Query<AuthorizationRequest>,
+) -> Result<Response, OAuthError> {
+    let authorization = check_client(&state, &request).await?;
+    let (scopes, code_challenge) = match check_request(&request) {
+        Ok(val) => val,
+        Err(e) => return Ok(authorization.redirect(&[("error", e.as_ref())])),
+    };
+
+    let email = match authenticated_email(&state, &jar).await {
+        Ok(email) => email,
+        Err(_) => return Ok(login_redirect(&request)),
+    };
+
+    let consent = state
+        .oauth_client_store
+        .read()
+        .await
+        .get_consent(&email, &authorization.client.client_id)
+        .await
+        .map_err(|_| OAuthError::ServerError)?;
+    if !scopes.iter().all(|scope| consent.contains(scope)) {
+        return Ok(consent_page(&authorization.client, &scopes, &request).into_response());
+    }
+
+    issue_code(&state, &authorization, email, scopes, code_challenge).await
+}
+
+/// Submission of the consent page. The form is only accepted together with
+/// the JWT cookie, which browsers don't send on cross-site posts as it is
+/// `SameSite=Lax`.
+#[tracing::instrument(name = "Authorize consent", skip_all, err(Debug))]
+pub async fn authorize_consent(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    Form(consent): Form<ConsentRequest>,
+) -> Result<Response, OAuthError> {
+    let request = consent.authorization;
+    let authorization = check_client(&state, &request).await?;
+    let (scopes, code_challenge) = match check_request(&request) {
+        Ok(val) => val,
+        Err(e) => return Ok(authorization.redirect(&[("error", e.as_ref())])),
+    };
+
+    let email = match authenticated_email(&state, &jar).await {
+        Ok(email) => email,
+        Err(_) => return Ok(login_redirect(&request)),
+    };
+
+    if consent.decision != "approve" {
+        return Ok(authorization.redirect(&[("error", OAuthError::AccessDenied.as_ref())]));
+    }
+
+    state
+        .oauth_client_store
+        .write()
+        .await
+        .grant_consent(&email, &authorization.client.client_id, &scopes)
+        .await
+        .map_err(|_| OAuthError::ServerError)?;
+
+    issue_code(&state, &authorization, email, scopes, code_challenge).await
+}
+
+#[tracing::instrument(name = "Token", skip_all, err(Debug))]
+pub async fn token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(request): Form<TokenRequest>,
+) -> Result<impl IntoResponse, OAuthError> {
+    let response = match request.grant_type.as_deref() {
+        Some("authorization_code") => exchange_authorization_code(&state, request).await?,
+        Some("client_credentials") => client_credentials(&state, &headers, request).await?,
+        Some(_) => return Err(OAuthError::UnsupportedGrantType),
+        None => return Err(OAuthError::InvalidRequest),
+    };
+
+    Ok(([(header::CACHE_CONTROL, "no-store")], Json(response)))
+}
+
+async fn exchange_authorization_code(
+    state: &AppState,
+    request: TokenRequest,
+) -> Result<TokenResponse, OAuthError> {
+    let (code, client_id, redirect_uri, code_verifier) = match (
+        request.code,
+        request.client_id,
+        request.redirect_uri,
+        request.code_verifier,
+    ) {
+        (Some(code), Some(client_id), Some(redirect_uri), Some(code_verifier)) => {
+            (code, client_id, redirect_uri, code_verifier)
+        }
+        _ => return Err(OAuthError::InvalidRequest),
+    };
+    let code = AuthorizationCode::parse(code).map_err(|_| OAuthError::InvalidGrant)?;
+
+    let grant = state
+        .authorization_code_store
+        .write()
+        .await
+        .consume_code(&code)
+        .await
+        .map_err(|e| match e {
+            AuthorizationCodeStoreError::CodeNotFound => OAuthError::InvalidGrant,
+            AuthorizationCodeStoreError::UnexpectedError => OAuthError::ServerError,
+        })?;
+
+    // Only whoever started the authorization can redeem the code. The grant
+    // holds the redirect URI in its normalized form.
+    let redirect_uri = Url::parse(&redirect_uri).map_err(|_| OAuthError::InvalidGrant)?;
+    if grant.client_id != client_id
+        || grant.redirect_uri != redirect_uri.as_str()
+        || !grant.code_challenge.verify(&code_verifier)
+    {
+        return Err(OAuthError::InvalidGrant);
+    }
+
+    let user = state
+        .user_store
+        .read()
+        .await
+        .get_user(&grant.email)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => OAuthError::InvalidGrant,
+            _ => OAuthError::ServerError,
+        })?;
+
+    let access_token = generate_client_access_token(&user, &client_id, &grant.scopes)
+        .map_err(|_| OAuthError::ServerError)?;
+    // Only OpenID Connect requests get to know who logged in.
+    let id_token = match grant.scopes.iter().any(|scope| scope == "openid") {
+        true => Some(
+            generate_id_token(&user, &client_id, grant.nonce, &grant.scopes)
+                .map_err(|_| OAuthError::ServerError)?,
+        ),
+        false => None,
+    };
+
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer".to_owned(),
+        expires_in: TOKEN_TTL_SECONDS,
+        scope: grant.scopes.join(" "),
+        id_token,
+    })
+}
+
+/// Issues a service client an access token for itself. Clients authenticate
+/// with HTTP Basic, or with `client_id` and `client_secret` in the form.
+async fn client_credentials(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: TokenRequest,
+) -> Result<TokenResponse, OAuthError> {
+    let (client_id, secret) = match (basic_credentials(headers), request.client_id) {
+        (Some(credentials), _) => credentials,
+        (None, Some(client_id)) => (
+            client_id,
+            request.client_secret.ok_or(OAuthError::InvalidClient)?,
+        ),
+        (None, None) => return Err(OAuthError::InvalidClient),
+    };
+    let secret = ClientSecret::parse(secret).map_err(|_| OAuthError::InvalidClient)?;
+
+    let client = state
+        .service_client_store
+        .read()
+        .await
+        .validate_client(&client_id, &secret)
+        .await
+        .map_err(|e| match e {
+            ServiceClientStoreError::InvalidCredentials => OAuthError::InvalidClient,
+            _ => OAuthError::ServerError,
+        })?;
+
+    let scopes = client
+        .grant_scopes(request.scope.as_deref())
+        .map_err(|_| OAuthError::InvalidScope)?;
+    let access_token =
+        generate_service_token(&client, &scopes).map_err(|_| OAuthError::ServerError)?;
+
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer".to_owned(),
+        expires_in: SERVICE_TOKEN_TTL_SECONDS,
+        scope: scopes.join(" "),
+        id_token: None,
+    })
+}
+
+/// An authorization request from a known client with one of its registered
+/// redirect URIs, so errors from here on can be reported to the client.
+struct ClientAuthorization {
+    client: OAuthClient,
+    redirect_uri: Url,
+    state: Option<String>,
+    nonce: Option<String>,
+}
+
+impl ClientAuthorization {
+    fn redirect(&self, params: &[(&str, &str)]) -> Response {
+        let mut redirect_uri = self.redirect_uri.clone();
+        {
+            let mut query = redirect_uri.query_pairs_mut();
+            query.extend_pairs(params);
+            if let Some(state) = &self.state {
+                query.append_pair("state", state);
+            }
+        }
+
+        Redirect::to(redirect_uri.as_str()).into_response()
+    }
+}
+
+/// Problems with the client or its redirect URI are reported to the user
+/// instead, as redirecting would send them to an unverified location.
+async fn check_client(
+    state: &AppState,
+    request: &AuthorizationRequest,
+) -> Result<ClientAuthorization, OAuthError> {
+    let client_id = request
+        .client_id
+        .as_deref()
+        .ok_or(OAuthError::InvalidRequest)?;
+    let redirect_uri = request
+        .redirect_uri
+        .as_deref()
+        .ok_or(OAuthError::InvalidRequest)?;
+
+    let client = state
+        .oauth_client_store
+        .read()
+        .await
+        .get_client(client_id)
+        .await
+        .map_err(|e| match e {
+            OAuthClientStoreError::ClientNotFound => OAuthError::InvalidRequest,
+            _ => OAuthError::ServerError,
+        })?;
+
+    if !client.allows_redirect_uri(redirect_uri) {
+        return Err(OAuthError::InvalidRequest);
+    }
+    let redirect_uri = Url::parse(redirect_uri).map_err(|_| OAuthError::InvalidRequest)?;
+
+    Ok(ClientAuthorization {
+        client,
+        redirect_uri,
+        state: request.state.clone(),
+        nonce: request.nonce.clone(),
+    })
+}
+
+fn check_request(
+    request: &AuthorizationRequest,
+) -> Result<(Vec<String>, CodeChallenge), OAuthError> {
+    match request.response_type.as_deref() {
+        Some("code") => {}
+        Some(_) => return Err(OAuthError::UnsupportedResponseType),
+        None => return Err(OAuthError::InvalidRequest),
+    }
+
+    let scopes = parse_scopes(request.scope.as_deref()).map_err(|_| OAuthError::InvalidScope)?;
+
+    // PKCE is mandatory, and the plain method is refused as it doesn't
+    // protect codes that are intercepted together with the request.
+    if request.code_challenge_method.as_deref() != Some("S256") {
+        return Err(OAuthError::InvalidRequest);
+    }
+    let code_challenge = request
+        .code_challenge
+        .clone()
+        .and_then(|challenge| CodeChallenge::parse(challenge).ok())
+        .ok_or(OAuthError::InvalidRequest)?;
+
+    Ok((scopes, code_challenge))
+}
+
+async fn issue_code(
+    state: &AppState,
+    authorization: &ClientAuthorization,
+    email: Email,
+    scopes: Vec<String>,
+    code_challenge: CodeChallenge,
+) -> Result<Response, OAuthError> {
+    let code = AuthorizationCode::default();
+    let grant = AuthorizationGrant {
+        client_id: authorization.client.client_id.clone(),
+        redirect_uri: authorization.redirect_uri.to_string(),
+        email,
+        scopes,
+        code_challenge,
+        nonce: authorization.nonce.clone(),
+    };
+
+    state
+        .authorization_code_store
+        .write()
+        .await
+        .add_code(code.clone(), grant)
+        .await
+        .map_err(|_| OAuthError::ServerError)?;
+
+    Ok(authorization.redirect(&[("code", code.as_ref())]))
+}
+
+/// Sends the user to the login page, which returns them to the authorization
+/// once they are logged in.
+fn login_redirect(request: &AuthorizationRequest) -> Response {
+    let authorize_path = format!(
+        "/authorize?{}",
+        form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(request.params())
+            .finish()
+    );
+    let login_path = format!(
+        "/?{}",
+        form_urlencoded::Serializer::new(String::new())
+            .append_pair("return_to", &authorize_path)
+            .finish()
+    );
+
+    Redirect::to(&login_path).into_response()
+}
+
+fn consent_page(
+    client: &OAuthClient,
+    scopes: &[String],
+    request: &AuthorizationRequest,
+) -> Html<String> {
+    let permissions: String = SUPPORTED_SCOPES
+        .iter()
+        .filter(|(name, _)| scopes.iter().any(|scope| scope == name))
+        .map(|(_, description)| format!("<li>{}</li>", description))
+        .collect();
+    let fields: String = request
+        .params()
+        .into_iter()
+        .map(|(name, value)| {
+            format!(
+                r#"<input type="hidden" name="{}" value="{}">"#,
+                name,
+                escape_html(value)
+            )
+        })
+        .collect();
+
+    Html(format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Authorize {name}</title>
+    <link rel="stylesheet" href="https://cdn.jsdelivr.net/npm/bootstrap@5.2.2/dist/css/bootstrap.min.css">
+</head>
+<body class="container py-5 text-center">
+    <h2>{name} wants to access your account</h2>
+    <ul class="list-unstyled my-4"><li>Know who you are</li>{permissions}</ul>
+    <form method="post" action="/authorize">
+        {fields}
+        <button class="btn btn-dark" type="submit" name="decision" value="approve">Allow</button>
+        <button class="btn btn-outline-dark" type="submit" name="decision" value="deny">Deny</button>
+    </form>
+</body>
+</html>"#,
+        name = escape_html(&client.name),
+        permissions = permissions,
+        fields = fields,
+    ))
+}
+
+/// Parameters of an authorization request. They are all optional here so
+/// that missing ones are reported as OAuth2 errors.
+#[derive(Deserialize, Debug)]
+pub struct AuthorizationRequest {
+    pub response_type: Option<String>,
+    pub client_id: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub nonce: Option<String>,
+}
+
+impl AuthorizationRequest {
+    /// The parameters that were given, to carry the request across the login
+    /// and consent pages.
+    fn params(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("response_type", &self.response_type),
+            ("client_id", &self.client_id),
+            ("redirect_uri", &self.redirect_uri),
+            ("scope", &self.scope),
+            ("state", &self.state),
+            ("code_challenge", &self.code_challenge),
+            ("code_challenge_method", &self.code_challenge_method),
+            ("nonce", &self.nonce),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.as_deref().map(|value| (name, value)))
+        .collect()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ConsentRequest {
+    #[serde(flatten)]
+    pub authorization: AuthorizationRequest,
+    /// `approve` or `deny`.
+    pub decision: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TokenRequest {
+    pub grant_type: Option<String>,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub client_id: Option<String>,
+    pub code_verifier: Option<String>,
+    pub client_secret: Option<String>,
+    pub scope: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    /// Seconds until the access token expires.
+    pub expires_in: i64,
+    pub scope: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+}
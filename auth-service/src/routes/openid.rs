@@ -0,0 +1,133 @@
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, Email, UserStoreError, SUPPORTED_SCOPES},
+    utils::{
+        auth::{bearer_token, signing_algorithm, validate_client_access_token},
+        constants::PUBLIC_BASE_URL,
+    },
+};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use jsonwebtoken::Algorithm;
+use serde::{Deserialize, Serialize};
+
+/// OpenID Connect discovery, so client libraries can configure themselves
+/// from our issuer URL alone.
+pub async fn openid_configuration() -> impl IntoResponse {
+    let issuer = PUBLIC_BASE_URL.clone();
+    let strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect();
+
+    Json(OpenIdConfiguration {
+        authorization_endpoint: format!("{}/authorize", issuer),
+        token_endpoint: format!("{}/token", issuer),
+        userinfo_endpoint: format!("{}/userinfo", issuer),
+        introspection_endpoint: format!("{}/introspect", issuer),
+        revocation_endpoint: format!("{}/revoke", issuer),
+        jwks_uri: format!("{}/.well-known/jwks.json", issuer),
+        issuer,
+        response_types_supported: strings(&["code"]),
+        grant_types_supported: strings(&["authorization_code", "client_credentials"]),
+        subject_types_supported: strings(&["public"]),
+        id_token_signing_alg_values_supported: vec![signing_algorithm()],
+        scopes_supported: SUPPORTED_SCOPES
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect(),
+        token_endpoint_auth_methods_supported: strings(&[
+            "none",
+            "client_secret_basic",
+            "client_secret_post",
+        ]),
+        code_challenge_methods_supported: strings(&["S256"]),
+        claims_supported: strings(&[
+            "sub",
+            "iss",
+            "aud",
+            "exp",
+            "iat",
+            "nonce",
+            "email",
+            "email_verified",
+        ]),
+    })
+}
+
+/// Claims about the user an OAuth2 client's access token was issued for.
+/// Requires the `openid` scope, and the email claims the `email` scope.
+#[tracing::instrument(name = "User info", skip_all, err(Debug))]
+pub async fn userinfo(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let token = bearer_token(&headers).ok_or(AuthAPIError::MissingToken)?;
+    let claims = validate_client_access_token(
+        token,
+        state.banned_token_store.clone(),
+        state.user_store.clone(),
+    )
+    .await
+    .map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let scopes: Vec<&str> = claims
+        .scope
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect();
+    if !scopes.contains(&"openid") {
+        return Err(AuthAPIError::InvalidToken);
+    }
+
+    let email = Email::parse(claims.email).map_err(|_| AuthAPIError::InvalidToken)?;
+    let user = state
+        .user_store
+        .read()
+        .await
+        .get_user(&email)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+            _ => AuthAPIError::UnexpectedError,
+        })?;
+
+    let with_email = scopes.contains(&"email");
+    let response = Json(UserInfoResponse {
+        sub: user.id.to_string(),
+        email: with_email.then(|| user.email.as_ref().to_owned()),
+        email_verified: with_email.then_some(user.email_verified),
+    });
+
+    Ok((StatusCode::OK, response))
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OpenIdConfiguration {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: String,
+    pub introspection_endpoint: String,
+    pub revocation_endpoint: String,
+    pub jwks_uri: String,
+    pub response_types_supported: Vec<String>,
+    pub grant_types_supported: Vec<String>,
+    pub subject_types_supported: Vec<String>,
+    pub id_token_signing_alg_values_supported: Vec<Algorithm>,
+    pub scopes_supported: Vec<String>,
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+    pub code_challenge_methods_supported: Vec<String>,
+    pub claims_supported: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct UserInfoResponse {
+    pub sub: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+}
@@ -0,0 +1,58 @@
+use crate::domain::AuthAPIError;
+use axum::{http::StatusCode, response::Html};
+
+/// Page shown after following a link from one of our emails, telling the user
+/// whether the link worked.
+pub fn link_result_page(
+    title: &str,
+    result: Result<&str, AuthAPIError>,
+) -> (StatusCode, Html<String>) {
+    let (status, message) = match result {
+        Ok(message) => (StatusCode::OK, message),
+        Err(AuthAPIError::InvalidToken) => (
+            StatusCode::UNAUTHORIZED,
+            "This link is invalid or has expired.",
+        ),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Something went wrong, please try again later.",
+        ),
+    };
+
+    let body = format!(
+        r#"<p class="my-4">{}</p>
+    <a class="btn btn-dark" href="/">Go to login</a>"#,
+        escape_html(message)
+    );
+    (status, page(title, &body))
+}
+
+/// A standalone page in the style of the login page.
+pub fn page(title: &str, body: &str) -> Html<String> {
+    Html(format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{title}</title>
+    <link rel="stylesheet" href="https://cdn.jsdelivr.net/npm/bootstrap@5.2.2/dist/css/bootstrap.min.css">
+</head>
+<body class="container py-5 text-center">
+    <h2>{title}</h2>
+    {body}
+</body>
+</html>"#,
+        title = escape_html(title),
+        body = body,
+    ))
+}
+
+pub fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
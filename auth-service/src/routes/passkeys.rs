@@ -0,0 +1,472 @@
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuthAPIError, Email, LoginAttemptId, OneTimeToken, OneTimeTokenStoreError, Passkey,
+        PasskeyStoreError, TokenPurpose, User,
+    },
+    routes::send_security_alert,
+    utils::{
+        auth::{authenticated_user, generate_session_cookies, ClientInfo},
+        constants::{PUBLIC_BASE_URL, WEBAUTHN_RP_ID},
+        email_templates::{requested_locale, SecurityEvent},
+        webauthn::{
+            decode_base64url, encode_base64url, parse_attestation_object,
+            verify_assertion_signature, verify_client_data, AuthenticatorData, Ceremony,
+            WebAuthnError, COSE_ALG_ES256, PUBLIC_KEY_CREDENTIAL_TYPE,
+        },
+    },
+};
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::extract::CookieJar;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+pub const WEBAUTHN_RP_NAME: &str = "auth-service";
+
+#[tracing::instrument(name = "Start passkey registration", skip_all)]
+pub async fn start_passkey_registration(
+    State(state): State<AppState>,
+    jar: CookieJar,
+) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    let user = match authenticated_user(&state, &jar).await {
+        Ok(val) => val,
+        Err(e) => return (jar, Err(e)),
+    };
+
+    let challenge =
+        match issue_challenge(&state, TokenPurpose::PasskeyRegistration, &user.email).await {
+            Ok(val) => val,
+            Err(e) => return (jar, Err(e)),
+        };
+    let exclude_credentials = match credential_descriptors(&state, &user.email).await {
+        Ok(val) => val,
+        Err(e) => return (jar, Err(e)),
+    };
+
+    let response = Json(PasskeyRegistrationOptions {
+        challenge,
+        rp: RelyingParty {
+            id: WEBAUTHN_RP_ID.to_owned(),
+            name: WEBAUTHN_RP_NAME.to_owned(),
+        },
+        user: PasskeyUser {
+            id: encode_base64url(user.id.as_bytes()),
+            name: user.email.as_ref().to_owned(),
+            display_name: user.email.as_ref().to_owned(),
+        },
+        pub_key_cred_params: vec![CredentialParameter {
+            credential_type: PUBLIC_KEY_CREDENTIAL_TYPE.to_owned(),
+            alg: COSE_ALG_ES256,
+        }],
+        timeout: challenge_timeout_ms(TokenPurpose::PasskeyRegistration),
+        attestation: "none".to_owned(),
+        exclude_credentials,
+    });
+
+    (jar, Ok((StatusCode::OK, response)))
+}
+
+#[tracing::instrument(name = "Finish passkey registration", skip_all)]
+pub async fn finish_passkey_registration(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Json(request): Json<PasskeyRegistrationRequest>,
+) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    let user = match authenticated_user(&state, &jar).await {
+        Ok(val) => val,
+        Err(e) => return (jar, Err(e)),
+    };
+
+    if let Err(e) = register_passkey(&state, &user, request).await {
+        return (jar, Err(e));
+    }
+
+    send_security_alert(
+        &state,
+        &user.email,
+        SecurityEvent::PasskeyAdded,
+        addr.ip(),
+        requested_locale(&headers),
+    )
+    .await;
+
+    (jar, Ok(StatusCode::CREATED))
+}
+
+async fn register_passkey(
+    state: &AppState,
+    user: &User,
+    request: PasskeyRegistrationRequest,
+) -> Result<(), AuthAPIError> {
+    let credential_id = decode_base64url(&request.id).map_err(webauthn_error)?;
+    let client_data_json =
+        decode_base64url(&request.response.client_data_json).map_err(webauthn_error)?;
+    let attestation_object =
+        decode_base64url(&request.response.attestation_object).map_err(webauthn_error)?;
+
+    let challenge = verify_client_data(&client_data_json, Ceremony::Registration, &PUBLIC_BASE_URL)
+        .map_err(webauthn_error)?;
+    let challenge_email =
+        consume_challenge(state, challenge, TokenPurpose::PasskeyRegistration).await?;
+    if challenge_email != user.email {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    let authenticator_data =
+        parse_attestation_object(&attestation_object).map_err(webauthn_error)?;
+    authenticator_data
+        .verify(&WEBAUTHN_RP_ID, false)
+        .map_err(webauthn_error)?;
+
+    let credential = authenticator_data
+        .attested_credential
+        .ok_or(AuthAPIError::InvalidCredentials)?;
+    if credential.credential_id != credential_id {
+        return Err(AuthAPIError::InvalidCredentials);
+    }
+
+    state
+        .passkey_store
+        .write()
+        .await
+        .add_passkey(Passkey {
+            credential_id: credential.credential_id,
+            email: user.email.clone(),
+            public_key: credential.public_key,
+            sign_count: authenticator_data.sign_count,
+        })
+        .await
+        .map_err(|e| match e {
+            PasskeyStoreError::PasskeyAlreadyExists => AuthAPIError::InvalidCredentials,
+            _ => AuthAPIError::UnexpectedError,
+        })
+}
+
+#[tracing::instrument(name = "Start passkey login", skip_all, err(Debug))]
+pub async fn start_passkey_login(
+    State(state): State<AppState>,
+    Json(request): Json<PasskeyLoginStartRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let email = Email::parse(request.email).map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    let challenge = issue_challenge(&state, TokenPurpose::PasskeyLogin, &email).await?;
+    let allow_credentials = credential_descriptors(&state, &email).await?;
+
+    let response = Json(PasskeyLoginOptions {
+        challenge,
+        rp_id: WEBAUTHN_RP_ID.to_owned(),
+        allow_credentials,
+        timeout: challenge_timeout_ms(TokenPurpose::PasskeyLogin),
+        user_verification: "preferred".to_owned(),
+    });
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Logs in with a passkey. Without a `loginAttemptId` the passkey replaces the
+/// password and must have verified the user. With one it answers the 2FA
+/// challenge of a password login in place of `verify_2fa`, where user presence
+/// is enough.
+#[tracing::instrument(name = "Finish passkey login", skip_all)]
+pub async fn finish_passkey_login(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Json(request): Json<PasskeyLoginRequest>,
+) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    let email = match Email::parse(request.email.clone()) {
+        Ok(val) => val,
+        Err(_) => return (jar, Err(AuthAPIError::InvalidCredentials)),
+    };
+    let login_attempt_id = match request.login_attempt_id.clone().map(LoginAttemptId::parse) {
+        Some(Ok(val)) => Some(val),
+        Some(Err(_)) => return (jar, Err(AuthAPIError::InvalidCredentials)),
+        None => None,
+    };
+
+    if let Err(e) =
+        verify_passkey_assertion(&state, &email, login_attempt_id.is_none(), request).await
+    {
+        return (jar, Err(e));
+    }
+
+    let user = match state.user_store.read().await.get_user(&email).await {
+        Ok(user) => user,
+        Err(_) => return (jar, Err(AuthAPIError::IncorrectCredentials)),
+    };
+    if user.is_locked() {
+        return (jar, Err(AuthAPIError::AccountLocked));
+    }
+    if !user.email_verified {
+        return (jar, Err(AuthAPIError::EmailNotVerified));
+    }
+
+    if let Some(login_attempt_id) = login_attempt_id {
+        let mut two_fa_code_store = state.two_fa_code_store.write().await;
+
+        let expected_login_attempt_id = match two_fa_code_store.get_code(&email).await {
+            Ok((val, _)) => val,
+            Err(_) => return (jar, Err(AuthAPIError::IncorrectCredentials)),
+        };
+        if login_attempt_id != expected_login_attempt_id {
+            return (jar, Err(AuthAPIError::IncorrectCredentials));
+        }
+        if two_fa_code_store.remove_code(&email).await.is_err() {
+            return (jar, Err(AuthAPIError::UnexpectedError));
+        }
+    }
+
+    let (auth_cookie, refresh_cookie) = match generate_session_cookies(
+        &user,
+        None,
+        &ClientInfo::new(&headers, addr),
+        state.refresh_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    {
+        Ok(val) => val,
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+    };
+
+    let updated_jar = jar.add(auth_cookie).add(refresh_cookie);
+
+    (updated_jar, Ok(StatusCode::OK))
+}
+
+async fn verify_passkey_assertion(
+    state: &AppState,
+    email: &Email,
+    require_user_verification: bool,
+    request: PasskeyLoginRequest,
+) -> Result<(), AuthAPIError> {
+    let credential_id = decode_base64url(&request.id).map_err(webauthn_error)?;
+    let client_data_json =
+        decode_base64url(&request.response.client_data_json).map_err(webauthn_error)?;
+    let authenticator_data_bytes =
+        decode_base64url(&request.response.authenticator_data).map_err(webauthn_error)?;
+    let signature = decode_base64url(&request.response.signature).map_err(webauthn_error)?;
+
+    let challenge = verify_client_data(
+        &client_data_json,
+        Ceremony::Authentication,
+        &PUBLIC_BASE_URL,
+    )
+    .map_err(webauthn_error)?;
+    let challenge_email = consume_challenge(state, challenge, TokenPurpose::PasskeyLogin).await?;
+    if &challenge_email != email {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    let passkey = state
+        .passkey_store
+        .read()
+        .await
+        .get_passkeys(email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?
+        .into_iter()
+        .find(|passkey| passkey.credential_id == credential_id)
+        .ok_or(AuthAPIError::IncorrectCredentials)?;
+
+    let authenticator_data =
+        AuthenticatorData::parse(&authenticator_data_bytes).map_err(webauthn_error)?;
+    authenticator_data
+        .verify(&WEBAUTHN_RP_ID, require_user_verification)
+        .map_err(webauthn_error)?;
+    verify_assertion_signature(
+        &passkey.public_key,
+        &authenticator_data_bytes,
+        &client_data_json,
+        &signature,
+    )
+    .map_err(webauthn_error)?;
+
+    // A counter that doesn't move forward means the credential may have been
+    // cloned. Authenticators without a counter always report 0.
+    let sign_count = authenticator_data.sign_count;
+    if (sign_count != 0 || passkey.sign_count != 0) && sign_count <= passkey.sign_count {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    state
+        .passkey_store
+        .write()
+        .await
+        .update_sign_count(&passkey.credential_id, sign_count)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)
+}
+
+/// Challenges are one-time tokens, so each can only answer a single ceremony.
+async fn issue_challenge(
+    state: &AppState,
+    purpose: TokenPurpose,
+    email: &Email,
+) -> Result<String, AuthAPIError> {
+    let challenge = OneTimeToken::default();
+
+    state
+        .one_time_token_store
+        .write()
+        .await
+        .add_token(challenge.clone(), purpose, email.clone())
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    Ok(encode_base64url(challenge.as_ref().as_bytes()))
+}
+
+async fn consume_challenge(
+    state: &AppState,
+    challenge: Vec<u8>,
+    purpose: TokenPurpose,
+) -> Result<Email, AuthAPIError> {
+    let challenge = String::from_utf8(challenge)
+        .ok()
+        .and_then(|challenge| OneTimeToken::parse(challenge).ok())
+        .ok_or(AuthAPIError::IncorrectCredentials)?;
+
+    state
+        .one_time_token_store
+        .write()
+        .await
+        .consume_token(&challenge, purpose)
+        .await
+        .map_err(|e| match e {
+            OneTimeTokenStoreError::TokenNotFound => AuthAPIError::IncorrectCredentials,
+            _ => AuthAPIError::UnexpectedError,
+        })
+}
+
+async fn credential_descriptors(
+    state: &AppState,
+    email: &Email,
+) -> Result<Vec<CredentialDescriptor>, AuthAPIError> {
+    let passkeys = state
+        .passkey_store
+        .read()
+        .await
+        .get_passkeys(email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    Ok(passkeys
+        .iter()
+        .map(|passkey| CredentialDescriptor {
+            credential_type: PUBLIC_KEY_CREDENTIAL_TYPE.to_owned(),
+            id: encode_base64url(&passkey.credential_id),
+        })
+        .collect())
+}
+
+fn challenge_timeout_ms(purpose: TokenPurpose) -> u64 {
+    purpose.ttl_seconds().unsigned_abs() * 1000
+}
+
+fn webauthn_error(e: WebAuthnError) -> AuthAPIError {
+    match e {
+        WebAuthnError::MalformedResponse | WebAuthnError::UnsupportedKey => {
+            AuthAPIError::InvalidCredentials
+        }
+        _ => AuthAPIError::IncorrectCredentials,
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasskeyRegistrationOptions {
+    pub challenge: String,
+    pub rp: RelyingParty,
+    pub user: PasskeyUser,
+    #[serde(rename = "pubKeyCredParams")]
+    pub pub_key_cred_params: Vec<CredentialParameter>,
+    pub timeout: u64,
+    pub attestation: String,
+    #[serde(rename = "excludeCredentials")]
+    pub exclude_credentials: Vec<CredentialDescriptor>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RelyingParty {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasskeyUser {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CredentialParameter {
+    #[serde(rename = "type")]
+    pub credential_type: String,
+    pub alg: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CredentialDescriptor {
+    #[serde(rename = "type")]
+    pub credential_type: String,
+    pub id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasskeyLoginOptions {
+    pub challenge: String,
+    #[serde(rename = "rpId")]
+    pub rp_id: String,
+    #[serde(rename = "allowCredentials")]
+    pub allow_credentials: Vec<CredentialDescriptor>,
+    pub timeout: u64,
+    #[serde(rename = "userVerification")]
+    pub user_verification: String,
+}
+
+#[derive(Deserialize)]
+pub struct PasskeyRegistrationRequest {
+    pub id: String,
+    pub response: AttestationResponse,
+}
+
+#[derive(Deserialize)]
+pub struct AttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "attestationObject")]
+    pub attestation_object: String,
+}
+
+#[derive(Deserialize)]
+pub struct PasskeyLoginStartRequest {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct PasskeyLoginRequest {
+    pub email: String,
+    #[serde(rename = "loginAttemptId")]
+    pub login_attempt_id: Option<String>,
+    pub id: String,
+    pub response: AssertionResponse,
+}
+
+#[derive(Deserialize)]
+pub struct AssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "authenticatorData")]
+    pub authenticator_data: String,
+    pub signature: String,
+}
@@ -0,0 +1,183 @@
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuthAPIError, Email, Locale, OneTimeToken, OneTimeTokenStoreError, Password, TokenPurpose,
+        UserStoreError,
+    },
+    routes::{escape_html, page, queue_email, send_security_alert},
+    utils::{
+        constants::PUBLIC_BASE_URL,
+        email_templates::{requested_locale, EmailTemplate, SecurityEvent},
+    },
+};
+use axum::{
+    extract::{ConnectInfo, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{Html, IntoResponse},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+#[tracing::instrument(name = "Request password reset", skip_all, err(Debug))]
+pub async fn request_password_reset(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<PasswordResetRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let email = Email::parse(request.email).map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    // The response must not tell whether the account exists, so failures past
+    // this point are only logged.
+    if let Err(e) = send_password_reset_email(&state, email, requested_locale(&headers)).await {
+        tracing::error!("Failed to send password reset email: {}", e);
+    }
+
+    let response = Json(PasswordResetResponse {
+        message: "If the account exists, a password reset link has been sent.".to_string(),
+    });
+
+    Ok((StatusCode::OK, response))
+}
+
+async fn send_password_reset_email(
+    state: &AppState,
+    email: Email,
+    requested_locale: Option<Locale>,
+) -> Result<(), String> {
+    match state.user_store.read().await.get_user(&email).await {
+        Ok(_) => {}
+        Err(UserStoreError::UserNotFound) => return Ok(()),
+        Err(e) => return Err(format!("{:?}", e)),
+    }
+
+    let token = OneTimeToken::default();
+    state
+        .one_time_token_store
+        .write()
+        .await
+        .add_token(token.clone(), TokenPurpose::PasswordReset, email.clone())
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+
+    let link = format!(
+        "{}/password-reset?token={}",
+        PUBLIC_BASE_URL.as_str(),
+        token.as_ref()
+    );
+    let template = EmailTemplate::PasswordReset {
+        link: &link,
+        expires_in_minutes: TokenPurpose::PasswordReset.ttl_seconds() / 60,
+    };
+    queue_email(state, &email, &template, requested_locale).await
+}
+
+/// Target of the link in the password reset email: a form that submits the
+/// token with the new password to `/password-reset/confirm`.
+#[tracing::instrument(name = "Password reset form", skip_all)]
+pub async fn password_reset_form(Query(request): Query<PasswordResetFormRequest>) -> Html<String> {
+    let body = format!(
+        r#"<form id="password-reset-form" class="my-4 mx-auto" style="max-width: 400px">
+        <input type="hidden" name="token" value="{}">
+        <input class="form-control mb-3" type="password" name="newPassword" placeholder="New password" minlength="8" required>
+        <button class="btn btn-dark" type="submit">Reset password</button>
+    </form>
+    <p id="password-reset-result"></p>
+    <a class="btn btn-outline-dark" href="/">Go to login</a>
+    <script src="/password-reset.js"></script>"#,
+        escape_html(&request.token)
+    );
+
+    page("Choose a new password", &body)
+}
+
+#[tracing::instrument(name = "Confirm password reset", skip_all, err(Debug))]
+pub async fn confirm_password_reset(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<PasswordResetConfirmRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let token = OneTimeToken::parse(request.token).map_err(|_| AuthAPIError::InvalidToken)?;
+    let password =
+        Password::parse(request.new_password).map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    let email = state
+        .one_time_token_store
+        .write()
+        .await
+        .consume_token(&token, TokenPurpose::PasswordReset)
+        .await
+        .map_err(|e| match e {
+            OneTimeTokenStoreError::TokenNotFound => AuthAPIError::InvalidToken,
+            OneTimeTokenStoreError::UnexpectedError => AuthAPIError::UnexpectedError,
+        })?;
+
+    let mut user_store = state.user_store.write().await;
+    user_store
+        .update_password(&email, password)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+            _ => AuthAPIError::UnexpectedError,
+        })?;
+
+    // Whoever knew the old password may still hold a session or tokens.
+    user_store
+        .increment_token_version(&email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    drop(user_store);
+
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .revoke_user(&email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    state
+        .session_store
+        .write()
+        .await
+        .remove_user_sessions(&email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    send_security_alert(
+        &state,
+        &email,
+        SecurityEvent::PasswordChanged,
+        addr.ip(),
+        requested_locale(&headers),
+    )
+    .await;
+
+    let response = Json(PasswordResetResponse {
+        message: "Password has been reset.".to_string(),
+    });
+
+    Ok((StatusCode::OK, response))
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+pub struct PasswordResetResponse {
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct PasswordResetRequest {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct PasswordResetFormRequest {
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+pub struct PasswordResetConfirmRequest {
+    pub token: String,
+    #[serde(rename = "newPassword")]
+    pub new_password: String,
+}
@@ -0,0 +1,117 @@
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, Email, RecoveryCode},
+    routes::send_security_alert,
+    utils::{
+        auth::authenticated_user,
+        email_templates::{requested_locale, SecurityEvent},
+    },
+};
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::extract::CookieJar;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+#[tracing::instrument(name = "Generate recovery codes", skip_all)]
+pub async fn generate_recovery_codes(
+    State(state): State<AppState>,
+    jar: CookieJar,
+) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    let email = match authenticated_2fa_email(&state, &jar).await {
+        Ok(val) => val,
+        Err(e) => return (jar, Err(e)),
+    };
+
+    // The first batch is only ever shown once; replacing it has to be asked
+    // for explicitly through the regenerate route.
+    match state
+        .recovery_code_store
+        .read()
+        .await
+        .count_codes(&email)
+        .await
+    {
+        Ok(0) => {}
+        Ok(_) => return (jar, Err(AuthAPIError::RecoveryCodesAlreadyExist)),
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+    }
+
+    let response = issue_recovery_codes(&state, &email).await;
+
+    (jar, response)
+}
+
+#[tracing::instrument(name = "Regenerate recovery codes", skip_all)]
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    jar: CookieJar,
+) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    let email = match authenticated_2fa_email(&state, &jar).await {
+        Ok(val) => val,
+        Err(e) => return (jar, Err(e)),
+    };
+
+    let response = issue_recovery_codes(&state, &email).await;
+    if response.is_ok() {
+        send_security_alert(
+            &state,
+            &email,
+            SecurityEvent::RecoveryCodesGenerated,
+            addr.ip(),
+            requested_locale(&headers),
+        )
+        .await;
+    }
+
+    (jar, response)
+}
+
+/// Replaces the stored batch and returns the plain codes. This is the only
+/// time they are ever visible.
+async fn issue_recovery_codes(
+    state: &AppState,
+    email: &Email,
+) -> Result<(StatusCode, Json<RecoveryCodesResponse>), AuthAPIError> {
+    let codes: Vec<RecoveryCode> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| RecoveryCode::default())
+        .collect();
+
+    state
+        .recovery_code_store
+        .write()
+        .await
+        .replace_codes(email, codes.clone())
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    let response = Json(RecoveryCodesResponse {
+        recovery_codes: codes.iter().map(|code| code.as_ref().to_owned()).collect(),
+    });
+
+    Ok((StatusCode::CREATED, response))
+}
+
+async fn authenticated_2fa_email(state: &AppState, jar: &CookieJar) -> Result<Email, AuthAPIError> {
+    let user = authenticated_user(state, jar).await?;
+
+    if !user.requires_2fa {
+        return Err(AuthAPIError::TwoFANotEnabled);
+    }
+
+    Ok(user.email)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecoveryCodesResponse {
+    #[serde(rename = "recoveryCodes")]
+    pub recovery_codes: Vec<String>,
+}
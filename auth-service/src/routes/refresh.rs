@@ -0,0 +1,84 @@
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, RefreshToken, RefreshTokenStoreError, UserStoreError},
+    routes::end_token_family,
+    utils::{
+        auth::{generate_session_cookies, ClientInfo},
+        constants::REFRESH_TOKEN_COOKIE_NAME,
+    },
+};
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use axum_extra::extract::CookieJar;
+use std::net::SocketAddr;
+
+#[tracing::instrument(name = "Refresh", skip_all)]
+pub async fn refresh(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    jar: CookieJar,
+) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    let token = match jar.get(REFRESH_TOKEN_COOKIE_NAME) {
+        Some(cookie) => cookie.value().to_owned(),
+        None => return (jar, Err(AuthAPIError::MissingToken)),
+    };
+    let token = match RefreshToken::parse(token) {
+        Ok(val) => val,
+        Err(_) => return (jar, Err(AuthAPIError::InvalidToken)),
+    };
+
+    let mut refresh_token_store = state.refresh_token_store.write().await;
+
+    let record = match refresh_token_store.get_token(&token).await {
+        Ok(val) => val,
+        Err(RefreshTokenStoreError::TokenNotFound) => {
+            return (jar, Err(AuthAPIError::InvalidToken))
+        }
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+    };
+
+    // A rotated token showing up again means it was copied. We can't tell the
+    // legitimate client from the attacker, so the whole family goes.
+    if record.used {
+        tracing::warn!("Refresh token reuse detected, revoking token family");
+        drop(refresh_token_store);
+
+        if let Err(e) = end_token_family(&state, &record).await {
+            return (jar, Err(e));
+        }
+        return (jar, Err(AuthAPIError::InvalidToken));
+    }
+
+    if refresh_token_store.mark_token_used(&token).await.is_err() {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+    drop(refresh_token_store);
+
+    // The user may have been removed since the session started.
+    let user = match state.user_store.read().await.get_user(&record.email).await {
+        Ok(user) => user,
+        Err(UserStoreError::UserNotFound) => return (jar, Err(AuthAPIError::InvalidToken)),
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+    };
+
+    let (auth_cookie, refresh_cookie) = match generate_session_cookies(
+        &user,
+        Some(record.family_id),
+        &ClientInfo::new(&headers, addr),
+        state.refresh_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    {
+        Ok(val) => val,
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+    };
+
+    let updated_jar = jar.add(auth_cookie).add(refresh_cookie);
+
+    (updated_jar, Ok(StatusCode::OK))
+}
@@ -0,0 +1,94 @@
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, Email, LoginAttemptId, TwoFAMethod},
+    routes::send_2fa_code,
+    utils::{
+        email_templates::requested_locale,
+        rate_limit::{
+            check_rate_limit, record_failure, RateLimitKey, MAX_TWO_FA_CODE_RESENDS,
+            TWO_FA_ATTEMPT_WINDOW_SECONDS,
+        },
+    },
+};
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+/// Emails the code of a pending 2FA login again, for when the first email
+/// was lost or delayed. The code stays the same, so either email works.
+#[tracing::instrument(name = "Resend 2FA code", skip_all, err(Debug))]
+pub async fn resend_2fa(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<Resend2FARequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let email = Email::parse(request.email).map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let login_attempt_id = LoginAttemptId::parse(request.login_attempt_id)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    let rate_limit_keys = [RateLimitKey::Account(&email), RateLimitKey::Ip(addr.ip())];
+    check_rate_limit(&state.rate_limit_store, &rate_limit_keys).await?;
+
+    // Guessed login attempt ids count as failures, like wrong codes do.
+    let two_fa_code = match state.two_fa_code_store.read().await.get_code(&email).await {
+        Ok((expected_login_attempt_id, two_fa_code))
+            if expected_login_attempt_id == login_attempt_id =>
+        {
+            two_fa_code
+        }
+        _ => {
+            record_failure(&state.rate_limit_store, &rate_limit_keys).await?;
+            return Err(AuthAPIError::IncorrectCredentials);
+        }
+    };
+
+    let user = state
+        .user_store
+        .read()
+        .await
+        .get_user(&email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    // TOTP users read their codes from their app.
+    let two_fa_code = match two_fa_code {
+        Some(two_fa_code) if user.two_fa_method == TwoFAMethod::Email => two_fa_code,
+        _ => return Err(AuthAPIError::TwoFANotEnabled),
+    };
+
+    let resends = record_failure(
+        &state.rate_limit_store,
+        &[RateLimitKey::TwoFAResend(&login_attempt_id)],
+    )
+    .await?;
+    if resends[0] > MAX_TWO_FA_CODE_RESENDS {
+        return Err(AuthAPIError::TooManyRequests(
+            TWO_FA_ATTEMPT_WINDOW_SECONDS.unsigned_abs(),
+        ));
+    }
+
+    send_2fa_code(&state, &email, &two_fa_code, requested_locale(&headers)).await?;
+
+    let response = Json(Resend2FAResponse {
+        message: "2FA code sent.".to_string(),
+    });
+
+    Ok((StatusCode::OK, response))
+}
+
+#[derive(Deserialize)]
+pub struct Resend2FARequest {
+    pub email: String,
+    #[serde(rename = "loginAttemptId")]
+    pub login_attempt_id: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+pub struct Resend2FAResponse {
+    pub message: String,
+}
@@ -0,0 +1,185 @@
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, Email, RefreshTokenRecord, Session, SessionStoreError},
+    utils::{
+        auth::authenticated_claims,
+        constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+    },
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::extract::{cookie::Cookie, CookieJar};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+#[tracing::instrument(name = "List sessions", skip_all, err(Debug))]
+pub async fn get_sessions(
+    State(state): State<AppState>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let claims = authenticated_claims(&state, &jar).await?;
+    let email = Email::parse(claims.email).map_err(|_| AuthAPIError::InvalidToken)?;
+
+    touch_session(&state, &claims.jti).await?;
+
+    let mut sessions = state
+        .session_store
+        .read()
+        .await
+        .get_sessions(&email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    sessions.sort_by_key(|session| Reverse(session.last_seen_at));
+
+    let sessions = sessions
+        .into_iter()
+        .map(|session| SessionResponse {
+            current: session.id == claims.jti,
+            id: session.id,
+            user_agent: session.user_agent,
+            ip: session.ip.to_string(),
+            created_at: session.created_at.to_rfc3339(),
+            last_seen_at: session.last_seen_at.to_rfc3339(),
+        })
+        .collect();
+
+    Ok((StatusCode::OK, Json(SessionsResponse { sessions })))
+}
+
+pub async fn delete_session(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    Path(id): Path<String>,
+) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    let claims = match authenticated_claims(&state, &jar).await {
+        Ok(val) => val,
+        Err(e) => return (jar, Err(e)),
+    };
+
+    let session = match state.session_store.read().await.get_session(&id).await {
+        Ok(session) if session.email.as_ref() == claims.email => session,
+        // Other users' sessions are indistinguishable from missing ones.
+        Ok(_) | Err(SessionStoreError::SessionNotFound) => {
+            return (jar, Err(AuthAPIError::SessionNotFound))
+        }
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+    };
+
+    if let Err(e) = end_session(&state, &session).await {
+        return (jar, Err(e));
+    }
+
+    // Ending the current session is a logout. The path has to match the one
+    // the cookies were set with, as this route isn't at the root.
+    let jar = match session.id == claims.jti {
+        true => jar
+            .remove(Cookie::build(JWT_COOKIE_NAME).path("/"))
+            .remove(Cookie::build(REFRESH_TOKEN_COOKIE_NAME).path("/")),
+        false => jar,
+    };
+
+    (jar, Ok(StatusCode::NO_CONTENT))
+}
+
+/// Bans the session's access token and revokes the refresh token family that
+/// would otherwise issue new ones.
+pub async fn end_session(state: &AppState, session: &Session) -> Result<(), AuthAPIError> {
+    state
+        .banned_token_store
+        .write()
+        .await
+        .add_token(session.id.clone())
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .revoke_family(&session.family_id)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    match state
+        .session_store
+        .write()
+        .await
+        .remove_session(&session.id)
+        .await
+    {
+        Ok(_) | Err(SessionStoreError::SessionNotFound) => Ok(()),
+        Err(_) => Err(AuthAPIError::UnexpectedError),
+    }
+}
+
+/// Revokes a refresh token's family, together with the access token the family
+/// issued last.
+pub async fn end_token_family(
+    state: &AppState,
+    record: &RefreshTokenRecord,
+) -> Result<(), AuthAPIError> {
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .revoke_family(&record.family_id)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    let sessions = state
+        .session_store
+        .read()
+        .await
+        .get_sessions(&record.email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    for session in sessions
+        .iter()
+        .filter(|session| session.family_id == record.family_id)
+    {
+        end_session(state, session).await?;
+    }
+
+    Ok(())
+}
+
+/// Records that the session's access token was just used. Tokens issued
+/// without a session are ignored.
+pub async fn touch_session(state: &AppState, id: &str) -> Result<(), AuthAPIError> {
+    let mut session_store = state.session_store.write().await;
+
+    let mut session = match session_store.get_session(id).await {
+        Ok(session) => session,
+        Err(SessionStoreError::SessionNotFound) => return Ok(()),
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    };
+    session.last_seen_at = Utc::now();
+
+    session_store
+        .add_session(session)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionsResponse {
+    pub sessions: Vec<SessionResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionResponse {
+    pub id: String,
+    #[serde(rename = "userAgent")]
+    pub user_agent: Option<String>,
+    pub ip: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "lastSeenAt")]
+    pub last_seen_at: String,
+    /// Whether this is the session of the token the request was made with.
+    pub current: bool,
+}
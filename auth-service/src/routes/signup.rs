@@ -1,20 +1,36 @@
 use crate::{
     app_state::AppState,
-    domain::{AuthAPIError, Email, Password, User},
+    domain::{AuthAPIError, Email, Locale, Password, User},
+    routes::send_verification_email,
+    utils::email_templates::requested_locale,
+};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
 };
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use serde::{Deserialize, Serialize};
 
 #[tracing::instrument(name = "Signup", skip_all, err(Debug))]
 pub async fn signup(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(request): Json<SignupRequest>,
 ) -> Result<impl IntoResponse, AuthAPIError> {
     let email = Email::parse(request.email).map_err(|_| AuthAPIError::InvalidCredentials)?;
     let password =
         Password::parse(request.password).map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let locale = request
+        .locale
+        .map(|locale| Locale::parse(&locale))
+        .transpose()
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
 
-    let user = User::new(email, password, request.requires_2fa);
+    let user = User {
+        locale,
+        ..User::new(email.clone(), password, request.requires_2fa)
+    };
 
     let mut user_store = state.user_store.write().await;
 
@@ -25,6 +41,13 @@ pub async fn signup(
     if user_store.add_user(user).await.is_err() {
         return Err(AuthAPIError::UnexpectedError);
     }
+    drop(user_store);
+
+    // The account exists at this point, so a failed email is only logged.
+    // The user can ask for another one through `/verify-email/resend`.
+    if let Err(e) = send_verification_email(&state, email, requested_locale(&headers)).await {
+        tracing::error!("Failed to send verification email: {}", e);
+    }
 
     let response = Json(SignupResponse {
         message: "User created successfully!".to_string(),
@@ -44,4 +67,7 @@ pub struct SignupRequest {
     pub password: String,
     #[serde(rename = "requires2FA")]
     pub requires_2fa: bool,
+    /// The language of the user's emails, like `de`. Without one, emails
+    /// follow the browser's language.
+    pub locale: Option<String>,
 }
@@ -0,0 +1,328 @@
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuthAPIError, CodeChallenge, Email, ExternalIdentity, IdentityProviderError,
+        LinkedIdentityStoreError, Password, User, UserStoreError,
+    },
+    routes::start_2fa_login,
+    utils::{
+        auth::{generate_session_cookies, ClientInfo},
+        constants::{PUBLIC_BASE_URL, SOCIAL_LOGIN_COOKIE_NAME},
+        email_templates::requested_locale,
+    },
+};
+use axum::{
+    extract::{ConnectInfo, Path, Query, State},
+    http::HeaderMap,
+    response::Redirect,
+};
+use axum_extra::extract::{
+    cookie::{Cookie, SameSite},
+    CookieJar,
+};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use url::form_urlencoded;
+
+/// How long users have to log in at the provider.
+const SOCIAL_LOGIN_TTL_SECONDS: i64 = 600;
+
+/// Sends the user to log in with an external identity provider. The state
+/// and PKCE verifier of the login are kept in a cookie until they return.
+#[tracing::instrument(name = "Start social login", skip_all)]
+pub async fn start_social_login(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    jar: CookieJar,
+    Query(request): Query<SocialLoginRequest>,
+) -> (CookieJar, Result<Redirect, AuthAPIError>) {
+    let provider = match state.identity_provider(&provider) {
+        Some(provider) => provider,
+        None => return (jar, Err(AuthAPIError::IdentityProviderNotFound)),
+    };
+
+    let login = PendingSocialLogin {
+        state: random_string(32),
+        code_verifier: random_string(64),
+        return_to: request.return_to.filter(|path| is_local_path(path)),
+    };
+    let authorization_url = provider.authorization_url(
+        &redirect_uri(provider.name()),
+        &login.state,
+        &CodeChallenge::from_verifier(&login.code_verifier),
+    );
+
+    (
+        jar.add(login.cookie(provider.name())),
+        Ok(Redirect::to(&authorization_url)),
+    )
+}
+
+/// Where the provider sends the user back to. Logs them in as the user the
+/// identity is linked to, linking it first if this is its first login.
+#[tracing::instrument(name = "Finish social login", skip_all)]
+pub async fn finish_social_login(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(provider): Path<String>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Query(request): Query<SocialLoginCallback>,
+) -> (CookieJar, Result<Redirect, AuthAPIError>) {
+    let provider = match state.identity_provider(&provider) {
+        Some(provider) => provider,
+        None => return (jar, Err(AuthAPIError::IdentityProviderNotFound)),
+    };
+
+    // The login can only be finished once, whatever the outcome.
+    let login = jar
+        .get(SOCIAL_LOGIN_COOKIE_NAME)
+        .and_then(|cookie| PendingSocialLogin::parse(cookie.value()));
+    let jar =
+        jar.remove(Cookie::build(SOCIAL_LOGIN_COOKIE_NAME).path(cookie_path(provider.name())));
+
+    // A state that doesn't match the cookie means the user didn't start this
+    // login, which is how login CSRF looks.
+    let login = match login {
+        Some(login) if request.state.as_ref() == Some(&login.state) => login,
+        _ => return (jar, Err(AuthAPIError::InvalidCredentials)),
+    };
+    // Users who deny access at the provider come back with an error instead.
+    let code = match request.code {
+        Some(code) => code,
+        None => return (jar, Err(AuthAPIError::IncorrectCredentials)),
+    };
+
+    let identity = match provider
+        .exchange_code(&code, &login.code_verifier, &redirect_uri(provider.name()))
+        .await
+    {
+        Ok(identity) => identity,
+        Err(IdentityProviderError::InvalidCode) => {
+            return (jar, Err(AuthAPIError::IncorrectCredentials))
+        }
+        Err(IdentityProviderError::MissingEmail) => {
+            return (jar, Err(AuthAPIError::EmailNotVerified))
+        }
+        Err(IdentityProviderError::UnexpectedError(e)) => {
+            tracing::error!("Login with {} failed: {}", provider.name(), e);
+            return (jar, Err(AuthAPIError::UnexpectedError));
+        }
+    };
+
+    let user = match resolve_user(&state, provider.name(), identity).await {
+        Ok(user) => user,
+        Err(e) => return (jar, Err(e)),
+    };
+    if user.is_locked() {
+        return (jar, Err(AuthAPIError::AccountLocked));
+    }
+    let return_to = login.return_to.unwrap_or_else(|| "/".to_owned());
+
+    // The provider stands in for the password only. Users with 2FA finish on
+    // the login page, which picks the attempt up from the query.
+    if user.requires_2fa {
+        let login_attempt_id =
+            match start_2fa_login(&state, &user, requested_locale(&headers)).await {
+                Ok(login_attempt_id) => login_attempt_id,
+                Err(e) => return (jar, Err(e)),
+            };
+        let two_fa_path = format!(
+            "/?{}",
+            form_urlencoded::Serializer::new(String::new())
+                .append_pair("email", user.email.as_ref())
+                .append_pair("login_attempt_id", login_attempt_id.as_ref())
+                .append_pair("two_fa_method", user.two_fa_method.as_ref())
+                .append_pair("return_to", &return_to)
+                .finish()
+        );
+        return (jar, Ok(Redirect::to(&two_fa_path)));
+    }
+
+    let (auth_cookie, refresh_cookie) = match generate_session_cookies(
+        &user,
+        None,
+        &ClientInfo::new(&headers, addr),
+        state.refresh_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    {
+        Ok(val) => val,
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+    };
+
+    (
+        jar.add(auth_cookie).add(refresh_cookie),
+        Ok(Redirect::to(&return_to)),
+    )
+}
+
+/// Finds the user an identity is linked to. On its first login, the identity
+/// is linked to the user with its email, who is created if there is none.
+async fn resolve_user(
+    state: &AppState,
+    provider: &str,
+    identity: ExternalIdentity,
+) -> Result<User, AuthAPIError> {
+    let linked_email = state
+        .linked_identity_store
+        .read()
+        .await
+        .get_email(provider, &identity.subject)
+        .await;
+    match linked_email {
+        Ok(email) => {
+            return state
+                .user_store
+                .read()
+                .await
+                .get_user(&email)
+                .await
+                .map_err(|e| match e {
+                    UserStoreError::UserNotFound => AuthAPIError::IncorrectCredentials,
+                    _ => AuthAPIError::UnexpectedError,
+                })
+        }
+        Err(LinkedIdentityStoreError::IdentityNotFound) => {}
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    }
+
+    // The email is all that ties the identity to one of our users, so the
+    // provider has to vouch for it.
+    if !identity.email_verified {
+        return Err(AuthAPIError::EmailNotVerified);
+    }
+
+    let existing_user = state
+        .user_store
+        .read()
+        .await
+        .get_user(&identity.email)
+        .await;
+    let user = match existing_user {
+        Ok(user) if user.email_verified => user,
+        // Whoever signed up with the address never proved they own it, and
+        // linking would let them share the account with its real owner.
+        Ok(_) => return Err(AuthAPIError::EmailNotVerified),
+        Err(UserStoreError::UserNotFound) => create_user(state, identity.email).await?,
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    };
+
+    match state
+        .linked_identity_store
+        .write()
+        .await
+        .add_identity(provider, &identity.subject, &user.email)
+        .await
+    {
+        // A concurrent first login got there first.
+        Ok(()) | Err(LinkedIdentityStoreError::IdentityAlreadyLinked) => Ok(user),
+        Err(_) => Err(AuthAPIError::UnexpectedError),
+    }
+}
+
+/// Users created through a provider get a random password they never learn.
+/// A password reset sets one they can log in with.
+async fn create_user(state: &AppState, email: Email) -> Result<User, AuthAPIError> {
+    let password = Password::parse(random_string(32)).map_err(|_| AuthAPIError::UnexpectedError)?;
+    let user = User {
+        email_verified: true,
+        ..User::new(email, password, false)
+    };
+
+    state
+        .user_store
+        .write()
+        .await
+        .add_user(user.clone())
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserAlreadyExists => AuthAPIError::UserAlreadyExists,
+            _ => AuthAPIError::UnexpectedError,
+        })?;
+
+    Ok(user)
+}
+
+fn redirect_uri(provider: &str) -> String {
+    format!("{}/login/{}/callback", *PUBLIC_BASE_URL, provider)
+}
+
+fn cookie_path(provider: &str) -> String {
+    format!("/login/{}", provider)
+}
+
+/// Only paths on this site, so the login can't send users elsewhere.
+fn is_local_path(path: &str) -> bool {
+    path.starts_with('/') && !path[1..].starts_with(['/', '\\'])
+}
+
+fn random_string(length: usize) -> String {
+    thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
+
+/// A login started at a provider, which the user's browser holds on to.
+struct PendingSocialLogin {
+    state: String,
+    code_verifier: String,
+    return_to: Option<String>,
+}
+
+impl PendingSocialLogin {
+    fn parse(value: &str) -> Option<Self> {
+        let mut state = None;
+        let mut code_verifier = None;
+        let mut return_to = None;
+        for (name, value) in form_urlencoded::parse(value.as_bytes()) {
+            match name.as_ref() {
+                "state" => state = Some(value.into_owned()),
+                "code_verifier" => code_verifier = Some(value.into_owned()),
+                "return_to" => return_to = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            state: state?,
+            code_verifier: code_verifier?,
+            return_to: return_to.filter(|path| is_local_path(path)),
+        })
+    }
+
+    /// Scoped to the provider's routes and lax, as the provider redirects
+    /// back to the callback from another site.
+    fn cookie(&self, provider: &str) -> Cookie<'static> {
+        let mut value = form_urlencoded::Serializer::new(String::new());
+        value
+            .append_pair("state", &self.state)
+            .append_pair("code_verifier", &self.code_verifier);
+        if let Some(return_to) = &self.return_to {
+            value.append_pair("return_to", return_to);
+        }
+
+        Cookie::build((SOCIAL_LOGIN_COOKIE_NAME, value.finish()))
+            .path(cookie_path(provider))
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .max_age(time::Duration::seconds(SOCIAL_LOGIN_TTL_SECONDS))
+            .build()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SocialLoginRequest {
+    /// A path on this site to send the user to once they are logged in.
+    pub return_to: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SocialLoginCallback {
+    pub code: Option<String>,
+    pub state: Option<String>,
+}
@@ -0,0 +1,200 @@
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, TotpSecretStoreError, TwoFACode, TwoFAMethod},
+    routes::send_security_alert,
+    utils::{
+        auth::authenticated_email,
+        email_templates::{requested_locale, SecurityEvent},
+        totp::{generate_totp_secret, get_otpauth_uri, verify_totp_code},
+    },
+};
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::extract::CookieJar;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+#[tracing::instrument(name = "Enroll TOTP", skip_all)]
+pub async fn enroll_totp(
+    State(state): State<AppState>,
+    jar: CookieJar,
+) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    let email = match authenticated_email(&state, &jar).await {
+        Ok(val) => val,
+        Err(e) => return (jar, Err(e)),
+    };
+
+    let secret = match generate_totp_secret() {
+        Ok(val) => val,
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+    };
+    let otpauth_uri = match get_otpauth_uri(&secret, &email) {
+        Ok(val) => val,
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+    };
+
+    if state
+        .totp_secret_store
+        .write()
+        .await
+        .set_pending_secret(&email, secret)
+        .await
+        .is_err()
+    {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+
+    let response = Json(EnrollTotpResponse { otpauth_uri });
+
+    (jar, Ok((StatusCode::OK, response)))
+}
+
+#[tracing::instrument(name = "Confirm TOTP enrollment", skip_all)]
+pub async fn confirm_totp(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Json(request): Json<ConfirmTotpRequest>,
+) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    let email = match authenticated_email(&state, &jar).await {
+        Ok(val) => val,
+        Err(e) => return (jar, Err(e)),
+    };
+    let code = match TwoFACode::parse(request.code) {
+        Ok(val) => val,
+        Err(_) => return (jar, Err(AuthAPIError::InvalidCredentials)),
+    };
+
+    let mut totp_secret_store = state.totp_secret_store.write().await;
+
+    let secret = match totp_secret_store.get_pending_secret(&email).await {
+        Ok(val) => val,
+        Err(TotpSecretStoreError::SecretNotFound) => {
+            return (jar, Err(AuthAPIError::TotpNotEnrolled))
+        }
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+    };
+
+    let step = match verify_totp_code(&secret, &email, code.as_ref()) {
+        Ok(Some(step)) => step,
+        Ok(None) => return (jar, Err(AuthAPIError::IncorrectCredentials)),
+        Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+    };
+
+    // The confirming code counts as used, like a code used to log in.
+    if totp_secret_store
+        .confirm_pending_secret(&email)
+        .await
+        .is_err()
+        || totp_secret_store
+            .record_used_step(&email, step)
+            .await
+            .is_err()
+    {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+    drop(totp_secret_store);
+
+    // Confirming an authenticator app turns 2FA on and makes it the default.
+    if state
+        .user_store
+        .write()
+        .await
+        .set_two_fa(&email, true, TwoFAMethod::Totp)
+        .await
+        .is_err()
+    {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+
+    send_security_alert(
+        &state,
+        &email,
+        SecurityEvent::TwoFAChanged,
+        addr.ip(),
+        requested_locale(&headers),
+    )
+    .await;
+
+    (jar, Ok(StatusCode::OK))
+}
+
+#[tracing::instrument(name = "Set 2FA method", skip_all)]
+pub async fn set_two_fa_method(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Json(request): Json<SetTwoFAMethodRequest>,
+) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    let email = match authenticated_email(&state, &jar).await {
+        Ok(val) => val,
+        Err(e) => return (jar, Err(e)),
+    };
+
+    if request.method == TwoFAMethod::Totp {
+        match state
+            .totp_secret_store
+            .read()
+            .await
+            .get_secret(&email)
+            .await
+        {
+            Ok(_) => {}
+            Err(TotpSecretStoreError::SecretNotFound) => {
+                return (jar, Err(AuthAPIError::TotpNotEnrolled))
+            }
+            Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+        }
+    }
+
+    let mut user_store = state.user_store.write().await;
+
+    let user = match user_store.get_user(&email).await {
+        Ok(user) => user,
+        Err(_) => return (jar, Err(AuthAPIError::InvalidToken)),
+    };
+
+    if user_store
+        .set_two_fa(&email, user.requires_2fa, request.method)
+        .await
+        .is_err()
+    {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+    drop(user_store);
+
+    if request.method != user.two_fa_method {
+        send_security_alert(
+            &state,
+            &email,
+            SecurityEvent::TwoFAChanged,
+            addr.ip(),
+            requested_locale(&headers),
+        )
+        .await;
+    }
+
+    (jar, Ok(StatusCode::OK))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnrollTotpResponse {
+    #[serde(rename = "otpauthUri")]
+    pub otpauth_uri: String,
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmTotpRequest {
+    pub code: String,
+}
+
+#[derive(Deserialize)]
+pub struct SetTwoFAMethodRequest {
+    pub method: TwoFAMethod,
+}
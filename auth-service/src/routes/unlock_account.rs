@@ -0,0 +1,138 @@
+use crate::{
+    app_state::AppState,
+    domain::{
+        AccountLock, AuthAPIError, Email, Locale, OneTimeToken, OneTimeTokenStoreError,
+        TokenPurpose, UserStoreError,
+    },
+    routes::{link_result_page, queue_email},
+    utils::{
+        constants::PUBLIC_BASE_URL,
+        email_templates::EmailTemplate,
+        rate_limit::{clear_failures, RateLimitKey, ACCOUNT_LOCK_SECONDS},
+    },
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Locks the account after repeated failed logins and emails the user a link
+/// to unlock it. Unknown accounts are ignored.
+pub async fn lock_account(
+    state: &AppState,
+    email: &Email,
+    requested_locale: Option<Locale>,
+) -> Result<(), String> {
+    let lock = AccountLock {
+        locked_until: Utc::now() + Duration::seconds(ACCOUNT_LOCK_SECONDS),
+        reason: "Too many failed login attempts".to_string(),
+    };
+    match state.user_store.write().await.lock_user(email, lock).await {
+        Ok(_) => {}
+        Err(UserStoreError::UserNotFound) => return Ok(()),
+        Err(e) => return Err(format!("{:?}", e)),
+    }
+
+    let token = OneTimeToken::default();
+    state
+        .one_time_token_store
+        .write()
+        .await
+        .add_token(token.clone(), TokenPurpose::AccountUnlock, email.clone())
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+
+    let link = format!(
+        "{}/unlock-account?token={}",
+        PUBLIC_BASE_URL.as_str(),
+        token.as_ref()
+    );
+    let template = EmailTemplate::AccountLocked {
+        link: &link,
+        expires_in_hours: TokenPurpose::AccountUnlock.ttl_seconds() / 3600,
+    };
+    queue_email(state, email, &template, requested_locale).await
+}
+
+/// Lifts the lock together with the throttling that led to it, so the user
+/// can log in straight away.
+pub async fn lift_account_lock(state: &AppState, email: &Email) -> Result<(), AuthAPIError> {
+    state
+        .user_store
+        .write()
+        .await
+        .unlock_user(email)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::UserNotFound,
+            _ => AuthAPIError::UnexpectedError,
+        })?;
+
+    clear_failures(&state.rate_limit_store, &[RateLimitKey::Account(email)]).await
+}
+
+#[tracing::instrument(name = "Unlock account", skip_all, err(Debug))]
+pub async fn unlock_account(
+    State(state): State<AppState>,
+    Json(request): Json<UnlockAccountRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    consume_unlock_token(&state, request.token).await?;
+
+    let response = Json(UnlockAccountResponse {
+        message: "Account has been unlocked.".to_string(),
+    });
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Target of the link in the account locked email, answered with a page for
+/// the browser instead of JSON.
+#[tracing::instrument(name = "Unlock account link", skip_all)]
+pub async fn unlock_account_link(
+    State(state): State<AppState>,
+    Query(request): Query<UnlockAccountRequest>,
+) -> impl IntoResponse {
+    let result = consume_unlock_token(&state, request.token).await;
+    if let Err(e) = &result {
+        tracing::info!("Failed to unlock account from link: {:?}", e);
+    }
+
+    link_result_page(
+        "Unlock your account",
+        result.map(|_| "Your account has been unlocked. You can now log in."),
+    )
+}
+
+async fn consume_unlock_token(state: &AppState, token: String) -> Result<(), AuthAPIError> {
+    let token = OneTimeToken::parse(token).map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let email = state
+        .one_time_token_store
+        .write()
+        .await
+        .consume_token(&token, TokenPurpose::AccountUnlock)
+        .await
+        .map_err(|e| match e {
+            OneTimeTokenStoreError::TokenNotFound => AuthAPIError::InvalidToken,
+            OneTimeTokenStoreError::UnexpectedError => AuthAPIError::UnexpectedError,
+        })?;
+
+    lift_account_lock(state, &email).await.map_err(|e| match e {
+        AuthAPIError::UserNotFound => AuthAPIError::InvalidToken,
+        e => e,
+    })
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+pub struct UnlockAccountResponse {
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct UnlockAccountRequest {
+    pub token: String,
+}
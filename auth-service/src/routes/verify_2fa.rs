@@ -1,14 +1,34 @@
 use crate::{
     app_state::AppState,
-    domain::{AuthAPIError, Email, LoginAttemptId, TwoFACode},
-    utils::generate_auth_cookie,
+    domain::{
+        AuthAPIError, Email, LoginAttemptId, RecoveryCode, RecoveryCodeStoreError,
+        TotpSecretStoreError, TwoFACode, TwoFAMethod, User,
+    },
+    routes::lock_account,
+    utils::{
+        auth::{generate_session_cookies, ClientInfo},
+        email_templates::requested_locale,
+        rate_limit::{
+            check_rate_limit, clear_failures, record_failure, RateLimitKey, ACCOUNT_LOCK_THRESHOLD,
+            MAX_TWO_FA_CODE_ATTEMPTS,
+        },
+        totp::verify_totp_code,
+    },
+};
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{status::StatusCode, HeaderMap},
+    response::IntoResponse,
+    Json,
 };
-use axum::{extract::State, http::status::StatusCode, response::IntoResponse, Json};
 use axum_extra::extract::CookieJar;
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 
 pub async fn verify_2fa(
     State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     jar: CookieJar,
     Json(request): Json<Verify2FARequest>,
 ) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
@@ -20,35 +40,189 @@ pub async fn verify_2fa(
         Ok(val) => val,
         Err(_) => return (jar, Err(AuthAPIError::InvalidCredentials)),
     };
-    let two_fa_code = match TwoFACode::parse(request.two_fa_code) {
+    let submitted_code = match SubmittedCode::parse(request.two_fa_code) {
         Ok(val) => val,
         Err(_) => return (jar, Err(AuthAPIError::InvalidCredentials)),
     };
+
+    let rate_limit_keys = [RateLimitKey::Account(&email), RateLimitKey::Ip(addr.ip())];
+    if let Err(e) = check_rate_limit(&state.rate_limit_store, &rate_limit_keys).await {
+        return (jar, Err(e));
+    }
+
     let mut two_fa_code_store = state.two_fa_code_store.write().await;
 
-    if (login_attempt_id, two_fa_code)
-        != match two_fa_code_store.get_code(&email).await {
-            Ok(val) => val,
-            Err(_) => return (jar, Err(AuthAPIError::IncorrectCredentials)),
-        }
+    let (expected_login_attempt_id, expected_code) = match two_fa_code_store.get_code(&email).await
     {
-        return (jar, Err(AuthAPIError::IncorrectCredentials));
+        Ok(val) => val,
+        Err(_) => {
+            return match record_failure(&state.rate_limit_store, &rate_limit_keys).await {
+                Ok(_) => (jar, Err(AuthAPIError::IncorrectCredentials)),
+                Err(e) => (jar, Err(e)),
+            }
+        }
+    };
+    if login_attempt_id != expected_login_attempt_id {
+        return match record_failure(&state.rate_limit_store, &rate_limit_keys).await {
+            Ok(_) => (jar, Err(AuthAPIError::IncorrectCredentials)),
+            Err(e) => (jar, Err(e)),
+        };
+    }
+
+    let user = match state.user_store.read().await.get_user(&email).await {
+        Ok(user) => user,
+        Err(_) => return (jar, Err(AuthAPIError::IncorrectCredentials)),
+    };
+
+    match check_submitted_code(&state, &user, expected_code.as_ref(), submitted_code).await {
+        Ok(()) => {}
+        Err(AuthAPIError::IncorrectCredentials) => {
+            let rate_limit_keys = [
+                RateLimitKey::Account(&email),
+                RateLimitKey::Ip(addr.ip()),
+                RateLimitKey::TwoFAAttempt(&login_attempt_id),
+            ];
+            let counts = match record_failure(&state.rate_limit_store, &rate_limit_keys).await {
+                Ok(val) => val,
+                Err(e) => return (jar, Err(e)),
+            };
+
+            let lock = counts[0] >= ACCOUNT_LOCK_THRESHOLD;
+
+            // Too many wrong codes for this login attempt; the password has
+            // to be entered again to get a new one.
+            if (lock || counts[2] >= MAX_TWO_FA_CODE_ATTEMPTS)
+                && two_fa_code_store.remove_code(&email).await.is_err()
+            {
+                return (jar, Err(AuthAPIError::UnexpectedError));
+            }
+
+            if lock {
+                if let Err(e) = lock_account(&state, &email, requested_locale(&headers)).await {
+                    tracing::error!("Failed to lock account: {}", e);
+                    return (jar, Err(AuthAPIError::UnexpectedError));
+                }
+                return (jar, Err(AuthAPIError::AccountLocked));
+            }
+
+            return (jar, Err(AuthAPIError::IncorrectCredentials));
+        }
+        Err(e) => return (jar, Err(e)),
     }
 
-    let auth_cookie = match generate_auth_cookie(&email) {
+    if two_fa_code_store.remove_code(&email).await.is_err() {
+        return (jar, Err(AuthAPIError::UnexpectedError));
+    }
+
+    let (auth_cookie, refresh_cookie) = match generate_session_cookies(
+        &user,
+        None,
+        &ClientInfo::new(&headers, addr),
+        state.refresh_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    {
         Ok(val) => val,
         Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
     };
 
-    if two_fa_code_store.remove_code(&email).await.is_err() {
+    if clear_failures(
+        &state.rate_limit_store,
+        &[
+            RateLimitKey::Account(&email),
+            RateLimitKey::TwoFAAttempt(&login_attempt_id),
+        ],
+    )
+    .await
+    .is_err()
+    {
         return (jar, Err(AuthAPIError::UnexpectedError));
     }
 
-    let updated_jar = jar.add(auth_cookie);
+    let updated_jar = jar.add(auth_cookie).add(refresh_cookie);
 
     (updated_jar, Ok(StatusCode::OK))
 }
 
+async fn check_submitted_code(
+    state: &AppState,
+    user: &User,
+    expected_code: Option<&TwoFACode>,
+    submitted_code: SubmittedCode,
+) -> Result<(), AuthAPIError> {
+    let email = &user.email;
+    match submitted_code {
+        // Only a code from the method the user chose counts.
+        SubmittedCode::TwoFA(two_fa_code) => {
+            let is_valid = match user.two_fa_method {
+                TwoFAMethod::Email => expected_code == Some(&two_fa_code),
+                TwoFAMethod::Totp => is_valid_totp_code(state, email, &two_fa_code).await?,
+            };
+            if is_valid {
+                Ok(())
+            } else {
+                Err(AuthAPIError::IncorrectCredentials)
+            }
+        }
+        SubmittedCode::Recovery(recovery_code) => state
+            .recovery_code_store
+            .write()
+            .await
+            .consume_code(email, &recovery_code)
+            .await
+            .map_err(|e| match e {
+                RecoveryCodeStoreError::CodeNotFound => AuthAPIError::IncorrectCredentials,
+                _ => AuthAPIError::UnexpectedError,
+            }),
+    }
+}
+
+/// The `2FACode` field takes a regular 2FA code or, in its place, one of the
+/// user's recovery codes.
+enum SubmittedCode {
+    TwoFA(TwoFACode),
+    Recovery(RecoveryCode),
+}
+
+impl SubmittedCode {
+    fn parse(code: String) -> Result<Self, String> {
+        match TwoFACode::parse(code.clone()) {
+            Ok(two_fa_code) => Ok(Self::TwoFA(two_fa_code)),
+            Err(_) => RecoveryCode::parse(code).map(Self::Recovery),
+        }
+    }
+}
+
+async fn is_valid_totp_code(
+    state: &AppState,
+    email: &Email,
+    code: &TwoFACode,
+) -> Result<bool, AuthAPIError> {
+    let mut totp_secret_store = state.totp_secret_store.write().await;
+
+    let secret = match totp_secret_store.get_secret(email).await {
+        Ok(secret) => secret,
+        Err(TotpSecretStoreError::SecretNotFound) => return Ok(false),
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    };
+
+    let step = match verify_totp_code(&secret, email, code.as_ref()) {
+        Ok(Some(step)) => step,
+        Ok(None) => return Ok(false),
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    };
+
+    // A code that was already accepted once is as good as a wrong one.
+    match totp_secret_store.record_used_step(email, step).await {
+        Ok(()) => Ok(true),
+        Err(TotpSecretStoreError::CodeAlreadyUsed | TotpSecretStoreError::SecretNotFound) => {
+            Ok(false)
+        }
+        Err(_) => Err(AuthAPIError::UnexpectedError),
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Verify2FAResponse {}
 
@@ -0,0 +1,178 @@
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuthAPIError, Email, Locale, OneTimeToken, OneTimeTokenStoreError, TokenPurpose,
+        UserStoreError,
+    },
+    routes::{link_result_page, queue_email},
+    utils::{
+        constants::PUBLIC_BASE_URL,
+        email_templates::{requested_locale, EmailTemplate},
+    },
+};
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+/// Minimum time between two verification emails to the same address.
+pub const VERIFICATION_EMAIL_RESEND_INTERVAL_SECONDS: i64 = 60;
+
+#[tracing::instrument(name = "Verify email", skip_all, err(Debug))]
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Json(request): Json<VerifyEmailRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    consume_verification_token(&state, request.token).await?;
+
+    let response = Json(VerifyEmailResponse {
+        message: "Email verified.".to_string(),
+    });
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Target of the link in the verification email, answered with a page for the
+/// browser instead of JSON.
+#[tracing::instrument(name = "Verify email link", skip_all)]
+pub async fn verify_email_link(
+    State(state): State<AppState>,
+    Query(request): Query<VerifyEmailRequest>,
+) -> impl IntoResponse {
+    let result = consume_verification_token(&state, request.token).await;
+    if let Err(e) = &result {
+        tracing::info!("Failed to verify email from link: {:?}", e);
+    }
+
+    link_result_page(
+        "Verify your email address",
+        result.map(|_| "Your email address has been verified. You can now log in."),
+    )
+}
+
+async fn consume_verification_token(state: &AppState, token: String) -> Result<(), AuthAPIError> {
+    let token = OneTimeToken::parse(token).map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let email = state
+        .one_time_token_store
+        .write()
+        .await
+        .consume_token(&token, TokenPurpose::EmailVerification)
+        .await
+        .map_err(|e| match e {
+            OneTimeTokenStoreError::TokenNotFound => AuthAPIError::InvalidToken,
+            OneTimeTokenStoreError::UnexpectedError => AuthAPIError::UnexpectedError,
+        })?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .mark_email_verified(&email)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+            _ => AuthAPIError::UnexpectedError,
+        })
+}
+
+#[tracing::instrument(name = "Resend verification email", skip_all, err(Debug))]
+pub async fn resend_verification_email(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ResendVerificationEmailRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let email = Email::parse(request.email).map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    // Like password resets, the response must not reveal whether the account
+    // exists or is already verified, and throttled requests look the same.
+    if let Err(e) = resend(&state, email, requested_locale(&headers)).await {
+        tracing::error!("Failed to resend verification email: {}", e);
+    }
+
+    let response = Json(VerifyEmailResponse {
+        message: "If the account needs verification, a new link has been sent.".to_string(),
+    });
+
+    Ok((StatusCode::OK, response))
+}
+
+async fn resend(
+    state: &AppState,
+    email: Email,
+    requested_locale: Option<Locale>,
+) -> Result<(), String> {
+    match state.user_store.read().await.get_user(&email).await {
+        Ok(user) if user.email_verified => return Ok(()),
+        Ok(_) => {}
+        Err(UserStoreError::UserNotFound) => return Ok(()),
+        Err(e) => return Err(format!("{:?}", e)),
+    }
+
+    let last_issued_at = state
+        .one_time_token_store
+        .read()
+        .await
+        .last_issued_at(&email, TokenPurpose::EmailVerification)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    if let Some(last_issued_at) = last_issued_at {
+        if (Utc::now() - last_issued_at).num_seconds() < VERIFICATION_EMAIL_RESEND_INTERVAL_SECONDS
+        {
+            tracing::info!("Verification email requested too soon, not resending");
+            return Ok(());
+        }
+    }
+
+    send_verification_email(state, email, requested_locale).await
+}
+
+/// Issues a verification token and emails the link to the user.
+pub async fn send_verification_email(
+    state: &AppState,
+    email: Email,
+    requested_locale: Option<Locale>,
+) -> Result<(), String> {
+    let token = OneTimeToken::default();
+    state
+        .one_time_token_store
+        .write()
+        .await
+        .add_token(
+            token.clone(),
+            TokenPurpose::EmailVerification,
+            email.clone(),
+        )
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+
+    let link = format!(
+        "{}/verify-email?token={}",
+        PUBLIC_BASE_URL.as_str(),
+        token.as_ref()
+    );
+    let template = EmailTemplate::EmailVerification {
+        link: &link,
+        expires_in_hours: TokenPurpose::EmailVerification.ttl_seconds() / 3600,
+    };
+    queue_email(state, &email, &template, requested_locale).await
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+pub struct VerifyEmailResponse {
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResendVerificationEmailRequest {
+    pub email: String,
+}
@@ -1,25 +1,95 @@
-use crate::{domain::AuthAPIError, utils::auth::validate_token, AppState};
-use axum::{extract::State, http::status::StatusCode, response::IntoResponse, Json};
+use crate::{
+    domain::{AuthAPIError, VERIFY_TOKEN_SCOPE},
+    routes::touch_session,
+    utils::{
+        auth::{
+            bearer_token, validate_service_token, validate_token, validate_token_for_audiences,
+        },
+        constants::JWT_AUDIENCES,
+    },
+    AppState,
+};
+use axum::{
+    extract::State,
+    http::{status::StatusCode, HeaderMap},
+    response::IntoResponse,
+    Json,
+};
 use axum_extra::extract::CookieJar;
 use serde::Deserialize;
 
 pub async fn verify_token(
     State(state): State<AppState>,
+    headers: HeaderMap,
     jar: CookieJar,
     Json(request): Json<VerifyTokenRequest>,
 ) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    if let Err(e) = authorize_caller(&state, &headers).await {
+        return (jar, Err(e));
+    }
+
     let token = request.token;
-    if validate_token(&token, state.banned_token_store)
+    let result = match request.audience {
+        // Callers may narrow the audiences down, but not widen them.
+        Some(audience) if !JWT_AUDIENCES.contains(&audience) => {
+            return (jar, Err(AuthAPIError::UnknownAudience))
+        }
+        Some(audience) => {
+            validate_token_for_audiences(
+                &token,
+                &[audience],
+                state.banned_token_store.clone(),
+                state.user_store.clone(),
+            )
+            .await
+        }
+        None => {
+            validate_token(
+                &token,
+                state.banned_token_store.clone(),
+                state.user_store.clone(),
+            )
+            .await
+        }
+    };
+
+    let claims = match result {
+        Ok(claims) => claims,
+        Err(_) => return (jar, Err(AuthAPIError::InvalidToken)),
+    };
+
+    // Downstream services verify tokens as they are used, which is as close
+    // as we get to knowing when a session was last active.
+    if let Err(e) = touch_session(&state, &claims.jti).await {
+        return (jar, Err(e));
+    }
+
+    (jar, Ok((StatusCode::OK, Json(claims))))
+}
+
+/// Callers may present a service client's access token, which then has to be
+/// valid. Anonymous callers are turned away if the app requires a client.
+async fn authorize_caller(state: &AppState, headers: &HeaderMap) -> Result<(), AuthAPIError> {
+    let token = match bearer_token(headers) {
+        Some(token) => token,
+        None if state.verify_token_requires_client => return Err(AuthAPIError::InvalidClient),
+        None => return Ok(()),
+    };
+
+    let claims = validate_service_token(token, state.banned_token_store.clone())
         .await
-        .is_err()
-    {
-        return (jar, Err(AuthAPIError::InvalidToken));
+        .map_err(|_| AuthAPIError::InvalidClient)?;
+    if !claims.has_scope(VERIFY_TOKEN_SCOPE) {
+        return Err(AuthAPIError::InsufficientScope);
     }
 
-    (jar, Ok(StatusCode::OK))
+    Ok(())
 }
 
 #[derive(Deserialize)]
 pub struct VerifyTokenRequest {
     pub token: String,
+    /// Only accept the token if it was issued for this audience, which must be
+    /// one of the configured `JWT_AUDIENCES`.
+    pub audience: Option<String>,
 }
@@ -0,0 +1,96 @@
+use crate::domain::{
+    data_stores::{AuthorizationCodeStore, AuthorizationCodeStoreError},
+    AuthorizationCode, AuthorizationGrant, AUTHORIZATION_CODE_TTL_SECONDS,
+};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct HashmapAuthorizationCodeStore {
+    codes: HashMap<AuthorizationCode, (AuthorizationGrant, DateTime<Utc>)>,
+}
+
+impl HashmapAuthorizationCodeStore {
+    pub fn new() -> Self {
+        Self {
+            codes: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl AuthorizationCodeStore for HashmapAuthorizationCodeStore {
+    async fn add_code(
+        &mut self,
+        code: AuthorizationCode,
+        grant: AuthorizationGrant,
+    ) -> Result<(), AuthorizationCodeStoreError> {
+        let expires_at = Utc::now() + Duration::seconds(AUTHORIZATION_CODE_TTL_SECONDS);
+        self.codes.insert(code, (grant, expires_at));
+        Ok(())
+    }
+
+    async fn consume_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> Result<AuthorizationGrant, AuthorizationCodeStoreError> {
+        match self.codes.remove(code) {
+            Some((grant, expires_at)) if expires_at > Utc::now() => Ok(grant),
+            _ => Err(AuthorizationCodeStoreError::CodeNotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{CodeChallenge, Email};
+
+    fn grant() -> AuthorizationGrant {
+        AuthorizationGrant {
+            client_id: "client".to_owned(),
+            redirect_uri: "https://app.example.com/callback".to_owned(),
+            email: Email::parse("valid@mail.com".to_string()).unwrap(),
+            scopes: vec!["email".to_owned()],
+            code_challenge: CodeChallenge::parse(
+                "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM".to_owned(),
+            )
+            .unwrap(),
+            nonce: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_consume_code_only_once() {
+        let mut authorization_code_store = HashmapAuthorizationCodeStore::new();
+        let code = AuthorizationCode::default();
+
+        authorization_code_store
+            .add_code(code.clone(), grant())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            authorization_code_store.consume_code(&code).await,
+            Ok(grant())
+        );
+        assert_eq!(
+            authorization_code_store.consume_code(&code).await,
+            Err(AuthorizationCodeStoreError::CodeNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_consume_expired_code() {
+        let mut authorization_code_store = HashmapAuthorizationCodeStore::new();
+        let code = AuthorizationCode::default();
+        authorization_code_store
+            .codes
+            .insert(code.clone(), (grant(), Utc::now() - Duration::seconds(1)));
+
+        assert_eq!(
+            authorization_code_store.consume_code(&code).await,
+            Err(AuthorizationCodeStoreError::CodeNotFound)
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use crate::domain::{
+    data_stores::{EmailOutboxStats, EmailOutboxStore, EmailOutboxStoreError, QueuedEmail},
+    Email, EmailMessage,
+};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+struct OutboxEntry {
+    email: QueuedEmail,
+    next_attempt_at: DateTime<Utc>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+pub struct HashmapEmailOutboxStore {
+    pending: HashMap<Uuid, OutboxEntry>,
+    dead_letters: HashMap<Uuid, (QueuedEmail, String)>,
+}
+
+impl HashmapEmailOutboxStore {
+    pub fn new() -> Self {
+        Self {
+            pending: HashMap::new(),
+            dead_letters: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailOutboxStore for HashmapEmailOutboxStore {
+    async fn enqueue(
+        &mut self,
+        recipient: &Email,
+        message: &EmailMessage,
+    ) -> Result<(), EmailOutboxStoreError> {
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+        self.pending.insert(
+            id,
+            OutboxEntry {
+                email: QueuedEmail {
+                    id,
+                    recipient: recipient.clone(),
+                    message: message.clone(),
+                    attempts: 0,
+                },
+                next_attempt_at: now,
+                created_at: now,
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn claim_due(
+        &mut self,
+        limit: i64,
+        lease_seconds: i64,
+    ) -> Result<Vec<QueuedEmail>, EmailOutboxStoreError> {
+        let now = Utc::now();
+        let mut due: Vec<&mut OutboxEntry> = self
+            .pending
+            .values_mut()
+            .filter(|entry| entry.next_attempt_at <= now)
+            .collect();
+        due.sort_by_key(|entry| entry.next_attempt_at);
+
+        Ok(due
+            .into_iter()
+            .take(limit.max(0) as usize)
+            .map(|entry| {
+                entry.email.attempts += 1;
+                entry.next_attempt_at = now + Duration::seconds(lease_seconds);
+                entry.email.clone()
+            })
+            .collect())
+    }
+
+    async fn mark_delivered(&mut self, id: Uuid) -> Result<(), EmailOutboxStoreError> {
+        self.pending
+            .remove(&id)
+            .map(|_| ())
+            .ok_or(EmailOutboxStoreError::EmailNotFound)
+    }
+
+    async fn schedule_retry(
+        &mut self,
+        id: Uuid,
+        _error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), EmailOutboxStoreError> {
+        let entry = self
+            .pending
+            .get_mut(&id)
+            .ok_or(EmailOutboxStoreError::EmailNotFound)?;
+        entry.next_attempt_at = next_attempt_at;
+
+        Ok(())
+    }
+
+    async fn dead_letter(&mut self, id: Uuid, error: &str) -> Result<(), EmailOutboxStoreError> {
+        let entry = self
+            .pending
+            .remove(&id)
+            .ok_or(EmailOutboxStoreError::EmailNotFound)?;
+        self.dead_letters
+            .insert(id, (entry.email, error.to_owned()));
+
+        Ok(())
+    }
+
+    async fn get_stats(&self) -> Result<EmailOutboxStats, EmailOutboxStoreError> {
+        Ok(EmailOutboxStats {
+            pending: self.pending.len() as i64,
+            dead_lettered: self.dead_letters.len() as i64,
+            oldest_pending_at: self.pending.values().map(|entry| entry.created_at).min(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message() -> EmailMessage {
+        EmailMessage {
+            subject: "Subject".to_owned(),
+            text_body: "Text".to_owned(),
+            html_body: "<p>HTML</p>".to_owned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_claimed_emails_are_leased() {
+        let mut email_outbox_store = HashmapEmailOutboxStore::new();
+        let email = Email::parse("test@example.com".to_owned()).unwrap();
+        email_outbox_store
+            .enqueue(&email, &message())
+            .await
+            .unwrap();
+
+        let claimed = email_outbox_store.claim_due(10, 60).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].recipient, email);
+        assert_eq!(claimed[0].attempts, 1);
+        assert!(email_outbox_store
+            .claim_due(10, 60)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Due again once the retry time has passed.
+        email_outbox_store
+            .schedule_retry(claimed[0].id, "Unavailable", Utc::now())
+            .await
+            .unwrap();
+        let claimed = email_outbox_store.claim_due(10, 60).await.unwrap();
+        assert_eq!(claimed[0].attempts, 2);
+
+        email_outbox_store
+            .mark_delivered(claimed[0].id)
+            .await
+            .unwrap();
+        assert_eq!(
+            email_outbox_store.get_stats().await.unwrap(),
+            EmailOutboxStats::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter() {
+        let mut email_outbox_store = HashmapEmailOutboxStore::new();
+        let email = Email::parse("test@example.com".to_owned()).unwrap();
+        email_outbox_store
+            .enqueue(&email, &message())
+            .await
+            .unwrap();
+        email_outbox_store
+            .enqueue(&email, &message())
+            .await
+            .unwrap();
+
+        let claimed = email_outbox_store.claim_due(1, 60).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        email_outbox_store
+            .dead_letter(claimed[0].id, "Rejected")
+            .await
+            .unwrap();
+
+        let stats = email_outbox_store.get_stats().await.unwrap();
+        assert_eq!(stats.pending, 1);
+        assert_eq!(stats.dead_lettered, 1);
+        assert!(stats.oldest_pending_at.is_some());
+        assert_eq!(
+            email_outbox_store.mark_delivered(claimed[0].id).await,
+            Err(EmailOutboxStoreError::EmailNotFound)
+        );
+    }
+}
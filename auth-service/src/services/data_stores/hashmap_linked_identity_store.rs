@@ -0,0 +1,79 @@
+use crate::domain::{
+    data_stores::{LinkedIdentityStore, LinkedIdentityStoreError},
+    Email,
+};
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct HashmapLinkedIdentityStore {
+    identities: HashMap<(String, String), Email>,
+}
+
+impl HashmapLinkedIdentityStore {
+    pub fn new() -> Self {
+        Self {
+            identities: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LinkedIdentityStore for HashmapLinkedIdentityStore {
+    async fn add_identity(
+        &mut self,
+        provider: &str,
+        subject: &str,
+        email: &Email,
+    ) -> Result<(), LinkedIdentityStoreError> {
+        let key = (provider.to_owned(), subject.to_owned());
+        if self.identities.contains_key(&key) {
+            return Err(LinkedIdentityStoreError::IdentityAlreadyLinked);
+        }
+
+        self.identities.insert(key, email.clone());
+        Ok(())
+    }
+
+    async fn get_email(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Email, LinkedIdentityStoreError> {
+        self.identities
+            .get(&(provider.to_owned(), subject.to_owned()))
+            .cloned()
+            .ok_or(LinkedIdentityStoreError::IdentityNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_add_and_get_identity() {
+        let mut linked_identity_store = HashmapLinkedIdentityStore::new();
+        let email = Email::parse("test@example.com".to_owned()).unwrap();
+
+        linked_identity_store
+            .add_identity("github", "42", &email)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            linked_identity_store.get_email("github", "42").await,
+            Ok(email.clone())
+        );
+        // Subjects are only unique per provider.
+        assert_eq!(
+            linked_identity_store.get_email("google", "42").await,
+            Err(LinkedIdentityStoreError::IdentityNotFound)
+        );
+        assert_eq!(
+            linked_identity_store
+                .add_identity("github", "42", &email)
+                .await,
+            Err(LinkedIdentityStoreError::IdentityAlreadyLinked)
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use crate::domain::{
+    data_stores::{OAuthClientStore, OAuthClientStoreError},
+    Email, OAuthClient,
+};
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct HashmapOAuthClientStore {
+    clients: HashMap<String, OAuthClient>,
+    consents: HashMap<(String, String), Vec<String>>,
+}
+
+impl HashmapOAuthClientStore {
+    pub fn new() -> Self {
+        Self {
+            clients: HashMap::new(),
+            consents: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl OAuthClientStore for HashmapOAuthClientStore {
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthClientStoreError> {
+        if self.clients.contains_key(&client.client_id) {
+            return Err(OAuthClientStoreError::ClientAlreadyExists);
+        }
+
+        self.clients.insert(client.client_id.clone(), client);
+        Ok(())
+    }
+
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthClientStoreError> {
+        self.clients
+            .get(client_id)
+            .cloned()
+            .ok_or(OAuthClientStoreError::ClientNotFound)
+    }
+
+    async fn get_consent(
+        &self,
+        email: &Email,
+        client_id: &str,
+    ) -> Result<Vec<String>, OAuthClientStoreError> {
+        Ok(self
+            .consents
+            .get(&(email.as_ref().to_owned(), client_id.to_owned()))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn grant_consent(
+        &mut self,
+        email: &Email,
+        client_id: &str,
+        scopes: &[String],
+    ) -> Result<(), OAuthClientStoreError> {
+        if !self.clients.contains_key(client_id) {
+            return Err(OAuthClientStoreError::ClientNotFound);
+        }
+
+        let consent = self
+            .consents
+            .entry((email.as_ref().to_owned(), client_id.to_owned()))
+            .or_default();
+        for scope in scopes {
+            if !consent.contains(scope) {
+                consent.push(scope.clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> OAuthClient {
+        OAuthClient::new(
+            "Wiki".to_owned(),
+            vec!["https://wiki.example.com/callback".to_owned()],
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_add_and_get_client() {
+        let mut oauth_client_store = HashmapOAuthClientStore::new();
+        let client = client();
+
+        oauth_client_store.add_client(client.clone()).await.unwrap();
+
+        assert_eq!(
+            oauth_client_store.get_client(&client.client_id).await,
+            Ok(client.clone())
+        );
+        assert_eq!(
+            oauth_client_store.add_client(client).await,
+            Err(OAuthClientStoreError::ClientAlreadyExists)
+        );
+        assert_eq!(
+            oauth_client_store.get_client("unknown").await,
+            Err(OAuthClientStoreError::ClientNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_grant_consent_adds_scopes() {
+        let mut oauth_client_store = HashmapOAuthClientStore::new();
+        let client = client();
+        let email = Email::parse("valid@mail.com".to_string()).unwrap();
+        oauth_client_store.add_client(client.clone()).await.unwrap();
+
+        assert_eq!(
+            oauth_client_store
+                .get_consent(&email, &client.client_id)
+                .await,
+            Ok(vec![])
+        );
+
+        oauth_client_store
+            .grant_consent(&email, &client.client_id, &["email".to_owned()])
+            .await
+            .unwrap();
+        oauth_client_store
+            .grant_consent(
+                &email,
+                &client.client_id,
+                &["email".to_owned(), "profile".to_owned()],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            oauth_client_store
+                .get_consent(&email, &client.client_id)
+                .await,
+            Ok(vec!["email".to_owned(), "profile".to_owned()])
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use crate::domain::{
+    data_stores::{OneTimeToken, OneTimeTokenStore, OneTimeTokenStoreError, TokenPurpose},
+    Email,
+};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct HashmapOneTimeTokenStore {
+    tokens: HashMap<(TokenPurpose, OneTimeToken), (Email, DateTime<Utc>)>,
+    issued_at: HashMap<(TokenPurpose, String), DateTime<Utc>>,
+}
+
+impl HashmapOneTimeTokenStore {
+    pub fn new() -> Self {
+        Self {
+            tokens: HashMap::new(),
+            issued_at: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl OneTimeTokenStore for HashmapOneTimeTokenStore {
+    async fn add_token(
+        &mut self,
+        token: OneTimeToken,
+        purpose: TokenPurpose,
+        email: Email,
+    ) -> Result<(), OneTimeTokenStoreError> {
+        let now = Utc::now();
+        let expires_at = now + Duration::seconds(purpose.ttl_seconds());
+        self.issued_at
+            .insert((purpose, email.as_ref().to_string()), now);
+        self.tokens.insert((purpose, token), (email, expires_at));
+        Ok(())
+    }
+
+    async fn consume_token(
+        &mut self,
+        token: &OneTimeToken,
+        purpose: TokenPurpose,
+    ) -> Result<Email, OneTimeTokenStoreError> {
+        match self.tokens.remove(&(purpose, token.clone())) {
+            Some((email, expires_at)) if expires_at > Utc::now() => Ok(email),
+            _ => Err(OneTimeTokenStoreError::TokenNotFound),
+        }
+    }
+
+    async fn last_issued_at(
+        &self,
+        email: &Email,
+        purpose: TokenPurpose,
+    ) -> Result<Option<DateTime<Utc>>, OneTimeTokenStoreError> {
+        Ok(self
+            .issued_at
+            .get(&(purpose, email.as_ref().to_string()))
+            .copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email() -> Email {
+        Email::parse("valid@mail.com".to_string()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_consume_token_only_once() {
+        let mut one_time_token_store = HashmapOneTimeTokenStore::new();
+        let token = OneTimeToken::default();
+
+        one_time_token_store
+            .add_token(token.clone(), TokenPurpose::PasswordReset, email())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            one_time_token_store
+                .consume_token(&token, TokenPurpose::PasswordReset)
+                .await,
+            Ok(email())
+        );
+        assert_eq!(
+            one_time_token_store
+                .consume_token(&token, TokenPurpose::PasswordReset)
+                .await,
+            Err(OneTimeTokenStoreError::TokenNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_consume_expired_token() {
+        let mut one_time_token_store = HashmapOneTimeTokenStore::new();
+        let token = OneTimeToken::default();
+        one_time_token_store.tokens.insert(
+            (TokenPurpose::PasswordReset, token.clone()),
+            (email(), Utc::now() - Duration::seconds(1)),
+        );
+
+        assert_eq!(
+            one_time_token_store
+                .consume_token(&token, TokenPurpose::PasswordReset)
+                .await,
+            Err(OneTimeTokenStoreError::TokenNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_last_issued_at() {
+        let mut one_time_token_store = HashmapOneTimeTokenStore::new();
+
+        assert_eq!(
+            one_time_token_store
+                .last_issued_at(&email(), TokenPurpose::EmailVerification)
+                .await,
+            Ok(None)
+        );
+
+        one_time_token_store
+            .add_token(
+                OneTimeToken::default(),
+                TokenPurpose::EmailVerification,
+                email(),
+            )
+            .await
+            .unwrap();
+
+        assert!(one_time_token_store
+            .last_issued_at(&email(), TokenPurpose::EmailVerification)
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            one_time_token_store
+                .last_issued_at(&email(), TokenPurpose::PasswordReset)
+                .await,
+            Ok(None)
+        );
+    }
+}
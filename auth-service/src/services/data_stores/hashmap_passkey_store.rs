@@ -0,0 +1,123 @@
+use crate::domain::{
+    data_stores::{Passkey, PasskeyStore, PasskeyStoreError},
+    Email,
+};
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct HashmapPasskeyStore {
+    passkeys: HashMap<String, Vec<Passkey>>,
+}
+
+impl HashmapPasskeyStore {
+    pub fn new() -> Self {
+        Self {
+            passkeys: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl PasskeyStore for HashmapPasskeyStore {
+    async fn add_passkey(&mut self, passkey: Passkey) -> Result<(), PasskeyStoreError> {
+        if self
+            .passkeys
+            .values()
+            .flatten()
+            .any(|existing| existing.credential_id == passkey.credential_id)
+        {
+            return Err(PasskeyStoreError::PasskeyAlreadyExists);
+        }
+
+        self.passkeys
+            .entry(passkey.email.as_ref().to_string())
+            .or_default()
+            .push(passkey);
+        Ok(())
+    }
+
+    async fn get_passkeys(&self, email: &Email) -> Result<Vec<Passkey>, PasskeyStoreError> {
+        Ok(self
+            .passkeys
+            .get(email.as_ref())
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn update_sign_count(
+        &mut self,
+        credential_id: &[u8],
+        sign_count: u32,
+    ) -> Result<(), PasskeyStoreError> {
+        match self
+            .passkeys
+            .values_mut()
+            .flatten()
+            .find(|passkey| passkey.credential_id == credential_id)
+        {
+            Some(passkey) => {
+                passkey.sign_count = sign_count;
+                Ok(())
+            }
+            None => Err(PasskeyStoreError::PasskeyNotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passkey(credential_id: Vec<u8>) -> Passkey {
+        Passkey {
+            credential_id,
+            email: Email::parse("valid@mail.com".to_string()).unwrap(),
+            public_key: vec![4; 65],
+            sign_count: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_and_get_passkeys() {
+        let mut passkey_store = HashmapPasskeyStore::new();
+
+        passkey_store.add_passkey(passkey(vec![1])).await.unwrap();
+        passkey_store.add_passkey(passkey(vec![2])).await.unwrap();
+
+        let passkeys = passkey_store
+            .get_passkeys(&passkey(vec![]).email)
+            .await
+            .unwrap();
+        assert_eq!(passkeys, vec![passkey(vec![1]), passkey(vec![2])]);
+    }
+
+    #[tokio::test]
+    async fn test_add_passkey_rejects_duplicate_credential_id() {
+        let mut passkey_store = HashmapPasskeyStore::new();
+
+        passkey_store.add_passkey(passkey(vec![1])).await.unwrap();
+
+        assert_eq!(
+            passkey_store.add_passkey(passkey(vec![1])).await,
+            Err(PasskeyStoreError::PasskeyAlreadyExists)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_sign_count() {
+        let mut passkey_store = HashmapPasskeyStore::new();
+
+        passkey_store.add_passkey(passkey(vec![1])).await.unwrap();
+        passkey_store.update_sign_count(&[1], 5).await.unwrap();
+
+        let passkeys = passkey_store
+            .get_passkeys(&passkey(vec![]).email)
+            .await
+            .unwrap();
+        assert_eq!(passkeys[0].sign_count, 5);
+        assert_eq!(
+            passkey_store.update_sign_count(&[2], 5).await,
+            Err(PasskeyStoreError::PasskeyNotFound)
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use crate::domain::data_stores::{FailedAttempts, RateLimitStore, RateLimitStoreError};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct HashmapRateLimitStore {
+    attempts: HashMap<String, (FailedAttempts, DateTime<Utc>)>,
+}
+
+impl HashmapRateLimitStore {
+    pub fn new() -> Self {
+        Self {
+            attempts: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for HashmapRateLimitStore {
+    async fn get_attempts(&self, key: &str) -> Result<FailedAttempts, RateLimitStoreError> {
+        match self.attempts.get(key) {
+            Some((attempts, expires_at)) if *expires_at > Utc::now() => Ok(attempts.clone()),
+            _ => Ok(FailedAttempts::default()),
+        }
+    }
+
+    async fn set_attempts(
+        &mut self,
+        key: &str,
+        attempts: FailedAttempts,
+        ttl_seconds: i64,
+    ) -> Result<(), RateLimitStoreError> {
+        let now = Utc::now();
+
+        // Expired records are only dropped on writes to keep reads cheap.
+        self.attempts.retain(|_, (_, expires_at)| *expires_at > now);
+        self.attempts.insert(
+            key.to_owned(),
+            (attempts, now + Duration::seconds(ttl_seconds)),
+        );
+        Ok(())
+    }
+
+    async fn clear_attempts(&mut self, key: &str) -> Result<(), RateLimitStoreError> {
+        self.attempts.remove(key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_set_and_clear_attempts() {
+        let mut rate_limit_store = HashmapRateLimitStore::new();
+        let attempts = FailedAttempts {
+            count: 3,
+            locked_until: None,
+        };
+
+        rate_limit_store
+            .set_attempts("key", attempts.clone(), 60)
+            .await
+            .unwrap();
+        assert_eq!(rate_limit_store.get_attempts("key").await, Ok(attempts));
+
+        rate_limit_store.clear_attempts("key").await.unwrap();
+        assert_eq!(
+            rate_limit_store.get_attempts("key").await,
+            Ok(FailedAttempts::default())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_attempts_are_ignored() {
+        let mut rate_limit_store = HashmapRateLimitStore::new();
+        let attempts = FailedAttempts {
+            count: 3,
+            locked_until: None,
+        };
+
+        rate_limit_store
+            .set_attempts("key", attempts, 0)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            rate_limit_store.get_attempts("key").await,
+            Ok(FailedAttempts::default())
+        );
+    }
+}
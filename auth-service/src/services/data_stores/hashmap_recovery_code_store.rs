@@ -0,0 +1,102 @@
+use crate::domain::{
+    data_stores::{RecoveryCode, RecoveryCodeStore, RecoveryCodeStoreError},
+    Email,
+};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Default)]
+pub struct HashmapRecoveryCodeStore {
+    codes: HashMap<String, HashSet<RecoveryCode>>,
+}
+
+impl HashmapRecoveryCodeStore {
+    pub fn new() -> Self {
+        Self {
+            codes: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RecoveryCodeStore for HashmapRecoveryCodeStore {
+    async fn replace_codes(
+        &mut self,
+        email: &Email,
+        codes: Vec<RecoveryCode>,
+    ) -> Result<(), RecoveryCodeStoreError> {
+        self.codes
+            .insert(email.as_ref().to_string(), codes.into_iter().collect());
+        Ok(())
+    }
+
+    async fn consume_code(
+        &mut self,
+        email: &Email,
+        code: &RecoveryCode,
+    ) -> Result<(), RecoveryCodeStoreError> {
+        let removed = self
+            .codes
+            .get_mut(email.as_ref())
+            .is_some_and(|codes| codes.remove(code));
+
+        if removed {
+            Ok(())
+        } else {
+            Err(RecoveryCodeStoreError::CodeNotFound)
+        }
+    }
+
+    async fn count_codes(&self, email: &Email) -> Result<usize, RecoveryCodeStoreError> {
+        Ok(self.codes.get(email.as_ref()).map_or(0, HashSet::len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email() -> Email {
+        Email::parse("valid@mail.com".to_string()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_code_can_only_be_consumed_once() {
+        let mut recovery_code_store = HashmapRecoveryCodeStore::new();
+        let code = RecoveryCode::default();
+
+        recovery_code_store
+            .replace_codes(&email(), vec![code.clone(), RecoveryCode::default()])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            recovery_code_store.consume_code(&email(), &code).await,
+            Ok(())
+        );
+        assert_eq!(
+            recovery_code_store.consume_code(&email(), &code).await,
+            Err(RecoveryCodeStoreError::CodeNotFound)
+        );
+        assert_eq!(recovery_code_store.count_codes(&email()).await, Ok(1));
+    }
+
+    #[tokio::test]
+    async fn test_replacing_codes_invalidates_old_batch() {
+        let mut recovery_code_store = HashmapRecoveryCodeStore::new();
+        let old_code = RecoveryCode::default();
+
+        recovery_code_store
+            .replace_codes(&email(), vec![old_code.clone()])
+            .await
+            .unwrap();
+        recovery_code_store
+            .replace_codes(&email(), vec![RecoveryCode::default()])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            recovery_code_store.consume_code(&email(), &old_code).await,
+            Err(RecoveryCodeStoreError::CodeNotFound)
+        );
+    }
+}
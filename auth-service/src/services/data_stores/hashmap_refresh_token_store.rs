@@ -0,0 +1,201 @@
+use crate::{
+    domain::{
+        data_stores::{
+            RefreshToken, RefreshTokenRecord, RefreshTokenStore, RefreshTokenStoreError,
+        },
+        Email,
+    },
+    utils::auth::REFRESH_TOKEN_TTL_SECONDS,
+};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct HashmapRefreshTokenStore {
+    tokens: HashMap<RefreshToken, (RefreshTokenRecord, DateTime<Utc>)>,
+}
+
+impl HashmapRefreshTokenStore {
+    pub fn new() -> Self {
+        Self {
+            tokens: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RefreshTokenStore for HashmapRefreshTokenStore {
+    async fn add_token(
+        &mut self,
+        token: RefreshToken,
+        record: RefreshTokenRecord,
+    ) -> Result<(), RefreshTokenStoreError> {
+        let now = Utc::now();
+        // Expired tokens are dropped here, as nothing else removes them.
+        self.tokens.retain(|_, (_, expires_at)| *expires_at > now);
+        self.tokens.insert(
+            token,
+            (record, now + Duration::seconds(REFRESH_TOKEN_TTL_SECONDS)),
+        );
+        Ok(())
+    }
+
+    async fn get_token(
+        &self,
+        token: &RefreshToken,
+    ) -> Result<RefreshTokenRecord, RefreshTokenStoreError> {
+        match self.tokens.get(token) {
+            Some((record, expires_at)) if *expires_at > Utc::now() => Ok(record.clone()),
+            _ => Err(RefreshTokenStoreError::TokenNotFound),
+        }
+    }
+
+    async fn mark_token_used(
+        &mut self,
+        token: &RefreshToken,
+    ) -> Result<(), RefreshTokenStoreError> {
+        match self.tokens.get_mut(token) {
+            Some((record, expires_at)) if *expires_at > Utc::now() => {
+                record.used = true;
+                Ok(())
+            }
+            _ => Err(RefreshTokenStoreError::TokenNotFound),
+        }
+    }
+
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError> {
+        self.tokens
+            .retain(|_, (record, _)| record.family_id != family_id);
+        Ok(())
+    }
+
+    async fn revoke_user(&mut self, email: &Email) -> Result<(), RefreshTokenStoreError> {
+        self.tokens.retain(|_, (record, _)| &record.email != email);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(family_id: &str) -> RefreshTokenRecord {
+        RefreshTokenRecord::new(
+            &Email::parse("valid@mail.com".to_string()).unwrap(),
+            family_id.to_string(),
+        )
+    }
+
+    fn valid(record: RefreshTokenRecord) -> (RefreshTokenRecord, DateTime<Utc>) {
+        (record, Utc::now() + Duration::hours(1))
+    }
+
+    #[tokio::test]
+    async fn test_add_and_get_token() {
+        let mut refresh_token_store = HashmapRefreshTokenStore::new();
+        let token = RefreshToken::default();
+
+        refresh_token_store
+            .add_token(token.clone(), record("family"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            refresh_token_store.get_token(&token).await,
+            Ok(record("family"))
+        );
+        assert_eq!(
+            refresh_token_store
+                .get_token(&RefreshToken::default())
+                .await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_is_rejected_and_pruned() {
+        let mut refresh_token_store = HashmapRefreshTokenStore::new();
+        let expired = RefreshToken::default();
+        refresh_token_store.tokens.insert(
+            expired.clone(),
+            (record("family"), Utc::now() - Duration::seconds(1)),
+        );
+
+        assert_eq!(
+            refresh_token_store.get_token(&expired).await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+        assert_eq!(
+            refresh_token_store.mark_token_used(&expired).await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+
+        refresh_token_store
+            .add_token(RefreshToken::default(), record("other"))
+            .await
+            .unwrap();
+
+        assert!(!refresh_token_store.tokens.contains_key(&expired));
+    }
+
+    #[tokio::test]
+    async fn test_mark_token_used() {
+        let mut refresh_token_store = HashmapRefreshTokenStore::new();
+        let token = RefreshToken::default();
+        refresh_token_store
+            .tokens
+            .insert(token.clone(), valid(record("family")));
+
+        refresh_token_store.mark_token_used(&token).await.unwrap();
+
+        assert!(refresh_token_store.tokens.get(&token).unwrap().0.used);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_family() {
+        let mut refresh_token_store = HashmapRefreshTokenStore::new();
+        let first = RefreshToken::default();
+        let second = RefreshToken::default();
+        let other = RefreshToken::default();
+        refresh_token_store
+            .tokens
+            .insert(first.clone(), valid(record("family")));
+        refresh_token_store
+            .tokens
+            .insert(second.clone(), valid(record("family")));
+        refresh_token_store
+            .tokens
+            .insert(other.clone(), valid(record("other")));
+
+        refresh_token_store.revoke_family("family").await.unwrap();
+
+        assert!(!refresh_token_store.tokens.contains_key(&first));
+        assert!(!refresh_token_store.tokens.contains_key(&second));
+        assert!(refresh_token_store.tokens.contains_key(&other));
+    }
+
+    #[tokio::test]
+    async fn test_revoke_user() {
+        let mut refresh_token_store = HashmapRefreshTokenStore::new();
+        let token = RefreshToken::default();
+        let other = RefreshToken::default();
+        refresh_token_store
+            .tokens
+            .insert(token.clone(), valid(record("family")));
+        refresh_token_store.tokens.insert(
+            other.clone(),
+            valid(RefreshTokenRecord::new(
+                &Email::parse("other@mail.com".to_string()).unwrap(),
+                "other".to_string(),
+            )),
+        );
+
+        refresh_token_store
+            .revoke_user(&Email::parse("valid@mail.com".to_string()).unwrap())
+            .await
+            .unwrap();
+
+        assert!(!refresh_token_store.tokens.contains_key(&token));
+        assert!(refresh_token_store.tokens.contains_key(&other));
+    }
+}
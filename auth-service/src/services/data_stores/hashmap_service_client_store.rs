@@ -0,0 +1,91 @@
+use crate::domain::{
+    data_stores::{ServiceClientStore, ServiceClientStoreError},
+    ClientSecret, ServiceClient,
+};
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct HashmapServiceClientStore {
+    clients: HashMap<String, (ServiceClient, ClientSecret)>,
+}
+
+impl HashmapServiceClientStore {
+    pub fn new() -> Self {
+        Self {
+            clients: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ServiceClientStore for HashmapServiceClientStore {
+    async fn add_client(
+        &mut self,
+        client: ServiceClient,
+        secret: &ClientSecret,
+    ) -> Result<(), ServiceClientStoreError> {
+        if self.clients.contains_key(&client.client_id) {
+            return Err(ServiceClientStoreError::ClientAlreadyExists);
+        }
+
+        self.clients
+            .insert(client.client_id.clone(), (client, secret.clone()));
+        Ok(())
+    }
+
+    async fn validate_client(
+        &self,
+        client_id: &str,
+        secret: &ClientSecret,
+    ) -> Result<ServiceClient, ServiceClientStoreError> {
+        match self.clients.get(client_id) {
+            Some((client, stored_secret)) if stored_secret == secret => Ok(client.clone()),
+            _ => Err(ServiceClientStoreError::InvalidCredentials),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::VERIFY_TOKEN_SCOPE;
+
+    #[tokio::test]
+    async fn test_validate_client() {
+        let mut service_client_store = HashmapServiceClientStore::new();
+        let client = ServiceClient::new(
+            "app-service".to_owned(),
+            vec![VERIFY_TOKEN_SCOPE.to_owned()],
+        )
+        .unwrap();
+        let secret = ClientSecret::default();
+
+        service_client_store
+            .add_client(client.clone(), &secret)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            service_client_store
+                .validate_client(&client.client_id, &secret)
+                .await,
+            Ok(client.clone())
+        );
+        assert_eq!(
+            service_client_store
+                .validate_client(&client.client_id, &ClientSecret::default())
+                .await,
+            Err(ServiceClientStoreError::InvalidCredentials)
+        );
+        assert_eq!(
+            service_client_store
+                .validate_client("unknown", &secret)
+                .await,
+            Err(ServiceClientStoreError::InvalidCredentials)
+        );
+        assert_eq!(
+            service_client_store.add_client(client, &secret).await,
+            Err(ServiceClientStoreError::ClientAlreadyExists)
+        );
+    }
+}
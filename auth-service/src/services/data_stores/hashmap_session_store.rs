@@ -0,0 +1,126 @@
+use crate::domain::{
+    data_stores::{Session, SessionStore, SessionStoreError},
+    Email,
+};
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct HashmapSessionStore {
+    sessions: HashMap<String, Session>,
+}
+
+impl HashmapSessionStore {
+    pub fn new() -> Self {
+        Self {
+            sessions: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SessionStore for HashmapSessionStore {
+    async fn add_session(&mut self, session: Session) -> Result<(), SessionStoreError> {
+        self.sessions.insert(session.id.clone(), session);
+        Ok(())
+    }
+
+    async fn get_session(&self, id: &str) -> Result<Session, SessionStoreError> {
+        match self.sessions.get(id) {
+            Some(session) => Ok(session.clone()),
+            None => Err(SessionStoreError::SessionNotFound),
+        }
+    }
+
+    async fn get_sessions(&self, email: &Email) -> Result<Vec<Session>, SessionStoreError> {
+        Ok(self
+            .sessions
+            .values()
+            .filter(|session| &session.email == email)
+            .cloned()
+            .collect())
+    }
+
+    async fn remove_session(&mut self, id: &str) -> Result<(), SessionStoreError> {
+        match self.sessions.remove(id) {
+            Some(_) => Ok(()),
+            None => Err(SessionStoreError::SessionNotFound),
+        }
+    }
+
+    async fn remove_user_sessions(&mut self, email: &Email) -> Result<(), SessionStoreError> {
+        self.sessions.retain(|_, session| &session.email != email);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn session(id: &str, email: &str) -> Session {
+        Session {
+            id: id.to_string(),
+            email: Email::parse(email.to_string()).unwrap(),
+            family_id: uuid::Uuid::new_v4().to_string(),
+            user_agent: Some("test-agent".to_string()),
+            ip: "127.0.0.1".parse().unwrap(),
+            created_at: Utc::now(),
+            last_seen_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_and_get_sessions() {
+        let mut session_store = HashmapSessionStore::new();
+        let first = session("first", "valid@mail.com");
+        let second = session("second", "valid@mail.com");
+        session_store.add_session(first.clone()).await.unwrap();
+        session_store.add_session(second).await.unwrap();
+        session_store
+            .add_session(session("other", "other@mail.com"))
+            .await
+            .unwrap();
+
+        assert_eq!(session_store.get_session("first").await, Ok(first.clone()));
+        assert_eq!(
+            session_store
+                .get_sessions(&first.email)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remove_sessions() {
+        let mut session_store = HashmapSessionStore::new();
+        let first = session("first", "valid@mail.com");
+        session_store.add_session(first.clone()).await.unwrap();
+        session_store
+            .add_session(session("second", "valid@mail.com"))
+            .await
+            .unwrap();
+
+        assert_eq!(session_store.remove_session("first").await, Ok(()));
+        assert_eq!(
+            session_store.get_session("first").await,
+            Err(SessionStoreError::SessionNotFound)
+        );
+        assert_eq!(
+            session_store.remove_session("first").await,
+            Err(SessionStoreError::SessionNotFound)
+        );
+
+        session_store
+            .remove_user_sessions(&first.email)
+            .await
+            .unwrap();
+        assert!(session_store
+            .get_sessions(&first.email)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+use crate::domain::{
+    data_stores::{TotpSecretStore, TotpSecretStoreError},
+    Email,
+};
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct HashmapTotpSecretStore {
+    secrets: HashMap<String, Vec<u8>>,
+    pending_secrets: HashMap<String, Vec<u8>>,
+    last_used_steps: HashMap<String, u64>,
+}
+
+impl HashmapTotpSecretStore {
+    pub fn new() -> Self {
+        Self {
+            secrets: HashMap::new(),
+            pending_secrets: HashMap::new(),
+            last_used_steps: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TotpSecretStore for HashmapTotpSecretStore {
+    async fn set_pending_secret(
+        &mut self,
+        email: &Email,
+        secret: Vec<u8>,
+    ) -> Result<(), TotpSecretStoreError> {
+        self.pending_secrets
+            .insert(email.as_ref().to_string(), secret);
+        Ok(())
+    }
+
+    async fn get_pending_secret(&self, email: &Email) -> Result<Vec<u8>, TotpSecretStoreError> {
+        match self.pending_secrets.get(email.as_ref()) {
+            Some(secret) => Ok(secret.clone()),
+            None => Err(TotpSecretStoreError::SecretNotFound),
+        }
+    }
+
+    async fn confirm_pending_secret(&mut self, email: &Email) -> Result<(), TotpSecretStoreError> {
+        match self.pending_secrets.remove(email.as_ref()) {
+            Some(secret) => {
+                self.secrets.insert(email.as_ref().to_string(), secret);
+                self.last_used_steps.remove(email.as_ref());
+                Ok(())
+            }
+            None => Err(TotpSecretStoreError::SecretNotFound),
+        }
+    }
+
+    async fn get_secret(&self, email: &Email) -> Result<Vec<u8>, TotpSecretStoreError> {
+        match self.secrets.get(email.as_ref()) {
+            Some(secret) => Ok(secret.clone()),
+            None => Err(TotpSecretStoreError::SecretNotFound),
+        }
+    }
+
+    async fn record_used_step(
+        &mut self,
+        email: &Email,
+        step: u64,
+    ) -> Result<(), TotpSecretStoreError> {
+        if !self.secrets.contains_key(email.as_ref()) {
+            return Err(TotpSecretStoreError::SecretNotFound);
+        }
+        match self.last_used_steps.get(email.as_ref()) {
+            Some(last_used_step) if *last_used_step >= step => {
+                Err(TotpSecretStoreError::CodeAlreadyUsed)
+            }
+            _ => {
+                self.last_used_steps
+                    .insert(email.as_ref().to_string(), step);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email() -> Email {
+        Email::parse("valid@mail.com".to_string()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_pending_secret_is_not_active_until_confirmed() {
+        let mut totp_secret_store = HashmapTotpSecretStore::new();
+
+        totp_secret_store
+            .set_pending_secret(&email(), vec![1; 20])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            totp_secret_store.get_pending_secret(&email()).await,
+            Ok(vec![1; 20])
+        );
+        assert_eq!(
+            totp_secret_store.get_secret(&email()).await,
+            Err(TotpSecretStoreError::SecretNotFound)
+        );
+
+        totp_secret_store
+            .confirm_pending_secret(&email())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            totp_secret_store.get_secret(&email()).await,
+            Ok(vec![1; 20])
+        );
+        assert_eq!(
+            totp_secret_store.get_pending_secret(&email()).await,
+            Err(TotpSecretStoreError::SecretNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reenrolling_keeps_active_secret() {
+        let mut totp_secret_store = HashmapTotpSecretStore::new();
+        totp_secret_store
+            .secrets
+            .insert(email().as_ref().to_string(), vec![1; 20]);
+
+        totp_secret_store
+            .set_pending_secret(&email(), vec![2; 20])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            totp_secret_store.get_secret(&email()).await,
+            Ok(vec![1; 20])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_used_steps_are_rejected() {
+        let mut totp_secret_store = HashmapTotpSecretStore::new();
+        totp_secret_store
+            .secrets
+            .insert(email().as_ref().to_string(), vec![1; 20]);
+
+        assert_eq!(
+            totp_secret_store.record_used_step(&email(), 10).await,
+            Ok(())
+        );
+        assert_eq!(
+            totp_secret_store.record_used_step(&email(), 10).await,
+            Err(TotpSecretStoreError::CodeAlreadyUsed)
+        );
+        assert_eq!(
+            totp_secret_store.record_used_step(&email(), 9).await,
+            Err(TotpSecretStoreError::CodeAlreadyUsed)
+        );
+        assert_eq!(
+            totp_secret_store.record_used_step(&email(), 11).await,
+            Ok(())
+        );
+    }
+}
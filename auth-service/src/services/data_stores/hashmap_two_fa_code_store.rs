@@ -6,7 +6,7 @@ use std::collections::HashMap;
 
 #[derive(Default)]
 pub struct HashmapTwoFACodeStore {
-    codes: HashMap<String, (LoginAttemptId, TwoFACode)>,
+    codes: HashMap<String, (LoginAttemptId, Option<TwoFACode>)>,
 }
 
 impl HashmapTwoFACodeStore {
@@ -23,7 +23,7 @@ impl TwoFACodeStore for HashmapTwoFACodeStore {
         &mut self,
         email: Email,
         login_attempt_id: LoginAttemptId,
-        code: TwoFACode,
+        code: Option<TwoFACode>,
     ) -> Result<(), TwoFACodeStoreError> {
         if self.codes.contains_key(email.as_ref()) && self.remove_code(&email).await.is_err() {
             return Err(TwoFACodeStoreError::UnexpectedError);
@@ -36,7 +36,7 @@ impl TwoFACodeStore for HashmapTwoFACodeStore {
     async fn get_code(
         &self,
         email: &Email,
-    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+    ) -> Result<(LoginAttemptId, Option<TwoFACode>), TwoFACodeStoreError> {
         match self.codes.get(email.as_ref()) {
             Some(val) => Ok(val.clone()),
             None => Err(TwoFACodeStoreError::LoginAttemptIdNotFound),
@@ -65,7 +65,7 @@ mod tests {
             .add_code(
                 email.clone(),
                 login_attempt_id,
-                TwoFACode::parse("123456".to_string()).unwrap(),
+                Some(TwoFACode::parse("123456".to_string()).unwrap()),
             )
             .await;
 
@@ -83,7 +83,7 @@ mod tests {
             email.as_ref().to_string(),
             (
                 login_attempt_id,
-                TwoFACode::parse("123456".to_string()).unwrap(),
+                Some(TwoFACode::parse("123456".to_string()).unwrap()),
             ),
         );
 
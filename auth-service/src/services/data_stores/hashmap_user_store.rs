@@ -1,4 +1,4 @@
-use crate::domain::{data_stores::UserStore, Email, Password};
+use crate::domain::{data_stores::UserStore, AccountLock, Email, Password, TwoFAMethod};
 use std::collections::HashMap;
 
 use crate::domain::{User, UserStoreError};
@@ -42,11 +42,100 @@ impl UserStore for HashmapUserStore {
         if user.is_none() {
             return Err(UserStoreError::UserNotFound);
         };
+        if user.unwrap().is_locked() {
+            return Err(UserStoreError::AccountLocked);
+        };
         if &user.unwrap().password != password {
             return Err(UserStoreError::InvalidCredentials);
         };
         Ok(())
     }
+
+    async fn update_password(
+        &mut self,
+        email: &Email,
+        password: Password,
+    ) -> Result<(), UserStoreError> {
+        match self.users.get_mut(email.as_ref()) {
+            Some(user) => {
+                user.password = password;
+                Ok(())
+            }
+            None => Err(UserStoreError::UserNotFound),
+        }
+    }
+
+    async fn mark_email_verified(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        match self.users.get_mut(email.as_ref()) {
+            Some(user) => {
+                user.email_verified = true;
+                Ok(())
+            }
+            None => Err(UserStoreError::UserNotFound),
+        }
+    }
+
+    async fn delete_user(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        match self.users.remove(email.as_ref()) {
+            Some(_) => Ok(()),
+            None => Err(UserStoreError::UserNotFound),
+        }
+    }
+
+    async fn set_two_fa(
+        &mut self,
+        email: &Email,
+        requires_2fa: bool,
+        method: TwoFAMethod,
+    ) -> Result<(), UserStoreError> {
+        match self.users.get_mut(email.as_ref()) {
+            Some(user) => {
+                user.requires_2fa = requires_2fa;
+                user.two_fa_method = method;
+                Ok(())
+            }
+            None => Err(UserStoreError::UserNotFound),
+        }
+    }
+
+    async fn increment_token_version(&mut self, email: &Email) -> Result<i32, UserStoreError> {
+        match self.users.get_mut(email.as_ref()) {
+            Some(user) => {
+                user.token_version += 1;
+                Ok(user.token_version)
+            }
+            None => Err(UserStoreError::UserNotFound),
+        }
+    }
+
+    async fn lock_user(&mut self, email: &Email, lock: AccountLock) -> Result<(), UserStoreError> {
+        match self.users.get_mut(email.as_ref()) {
+            Some(user) => {
+                user.lock = Some(lock);
+                Ok(())
+            }
+            None => Err(UserStoreError::UserNotFound),
+        }
+    }
+
+    async fn unlock_user(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        match self.users.get_mut(email.as_ref()) {
+            Some(user) => {
+                user.lock = None;
+                Ok(())
+            }
+            None => Err(UserStoreError::UserNotFound),
+        }
+    }
+
+    async fn get_locked_users(&self) -> Result<Vec<User>, UserStoreError> {
+        Ok(self
+            .users
+            .values()
+            .filter(|user| user.is_locked())
+            .cloned()
+            .collect())
+    }
 }
 
 #[cfg(test)]
@@ -66,16 +155,11 @@ mod tests {
         );
         test_user_store
             .users
-            .insert(user.email.as_ref().to_string(), user);
+            .insert(user.email.as_ref().to_string(), user.clone());
 
         let mut user_store = HashmapUserStore {
             users: HashMap::new(),
         };
-        let user = User::new(
-            Email::parse("asdf@asdf.com".to_string()).unwrap(),
-            Password::parse("password123".to_string()).unwrap(),
-            true,
-        );
         user_store.add_user(user).await.unwrap();
 
         assert_eq!(user_store, test_user_store, "Failed");
@@ -93,17 +177,12 @@ mod tests {
         );
         user_store
             .users
-            .insert(user.email.as_ref().to_string(), user);
+            .insert(user.email.as_ref().to_string(), user.clone());
 
-        let user = user_store
+        let test_user = user_store
             .get_user(&Email::parse("asdf@asdf.com".to_string()).unwrap())
             .await
             .unwrap();
-        let test_user = User::new(
-            Email::parse("asdf@asdf.com".to_string()).unwrap(),
-            Password::parse("password123".to_string()).unwrap(),
-            true,
-        );
         assert_eq!(user, test_user, "Failed to get valid user");
     }
 
@@ -142,4 +221,171 @@ mod tests {
             validate
         );
     }
+
+    #[tokio::test]
+    async fn test_update_password() {
+        let mut user_store = HashmapUserStore::new();
+        let email = Email::parse("asdf@asdf.com".to_string()).unwrap();
+        user_store
+            .add_user(User::new(
+                email.clone(),
+                Password::parse("password123".to_string()).unwrap(),
+                false,
+            ))
+            .await
+            .unwrap();
+
+        user_store
+            .update_password(&email, Password::parse("newpassword".to_string()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            user_store
+                .validate_user(&email, &Password::parse("newpassword".to_string()).unwrap())
+                .await,
+            Ok(())
+        );
+        assert_eq!(
+            user_store
+                .update_password(
+                    &Email::parse("missing@asdf.com".to_string()).unwrap(),
+                    Password::parse("newpassword".to_string()).unwrap(),
+                )
+                .await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mark_email_verified() {
+        let mut user_store = HashmapUserStore::new();
+        let email = Email::parse("asdf@asdf.com".to_string()).unwrap();
+        user_store
+            .add_user(User::new(
+                email.clone(),
+                Password::parse("password123".to_string()).unwrap(),
+                false,
+            ))
+            .await
+            .unwrap();
+        assert!(!user_store.get_user(&email).await.unwrap().email_verified);
+
+        user_store.mark_email_verified(&email).await.unwrap();
+
+        assert!(user_store.get_user(&email).await.unwrap().email_verified);
+    }
+
+    #[tokio::test]
+    async fn test_delete_user() {
+        let mut user_store = HashmapUserStore::new();
+        let email = Email::parse("asdf@asdf.com".to_string()).unwrap();
+        user_store
+            .add_user(User::new(
+                email.clone(),
+                Password::parse("password123".to_string()).unwrap(),
+                false,
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(user_store.delete_user(&email).await, Ok(()));
+        assert_eq!(
+            user_store.get_user(&email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+        assert_eq!(
+            user_store.delete_user(&email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_two_fa() {
+        let mut user_store = HashmapUserStore::new();
+        let email = Email::parse("asdf@asdf.com".to_string()).unwrap();
+        user_store
+            .add_user(User::new(
+                email.clone(),
+                Password::parse("password123".to_string()).unwrap(),
+                false,
+            ))
+            .await
+            .unwrap();
+
+        user_store
+            .set_two_fa(&email, true, TwoFAMethod::Totp)
+            .await
+            .unwrap();
+
+        let user = user_store.get_user(&email).await.unwrap();
+        assert!(user.requires_2fa);
+        assert_eq!(user.two_fa_method, TwoFAMethod::Totp);
+    }
+
+    #[tokio::test]
+    async fn test_increment_token_version() {
+        let mut user_store = HashmapUserStore::new();
+        let email = Email::parse("asdf@asdf.com".to_string()).unwrap();
+        user_store
+            .add_user(User::new(
+                email.clone(),
+                Password::parse("password123".to_string()).unwrap(),
+                false,
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(user_store.increment_token_version(&email).await, Ok(1));
+        assert_eq!(user_store.increment_token_version(&email).await, Ok(2));
+        assert_eq!(user_store.get_user(&email).await.unwrap().token_version, 2);
+    }
+
+    #[tokio::test]
+    async fn test_lock_user() {
+        let mut user_store = HashmapUserStore::new();
+        let email = Email::parse("asdf@asdf.com".to_string()).unwrap();
+        let password = Password::parse("password123".to_string()).unwrap();
+        user_store
+            .add_user(User::new(email.clone(), password.clone(), false))
+            .await
+            .unwrap();
+
+        let lock = AccountLock {
+            locked_until: chrono::Utc::now() + chrono::Duration::hours(1),
+            reason: "Too many failed login attempts".to_string(),
+        };
+        user_store.lock_user(&email, lock).await.unwrap();
+
+        assert_eq!(
+            user_store.validate_user(&email, &password).await,
+            Err(UserStoreError::AccountLocked)
+        );
+        assert_eq!(user_store.get_locked_users().await.unwrap().len(), 1);
+
+        user_store.unlock_user(&email).await.unwrap();
+
+        assert_eq!(user_store.validate_user(&email, &password).await, Ok(()));
+        assert!(user_store.get_locked_users().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_expired_lock_is_ignored() {
+        let mut user_store = HashmapUserStore::new();
+        let email = Email::parse("asdf@asdf.com".to_string()).unwrap();
+        let password = Password::parse("password123".to_string()).unwrap();
+        user_store
+            .add_user(User::new(email.clone(), password.clone(), false))
+            .await
+            .unwrap();
+
+        let lock = AccountLock {
+            locked_until: chrono::Utc::now() - chrono::Duration::seconds(1),
+            reason: "Too many failed login attempts".to_string(),
+        };
+        user_store.lock_user(&email, lock).await.unwrap();
+
+        assert_eq!(user_store.validate_user(&email, &password).await, Ok(()));
+        assert!(user_store.get_locked_users().await.unwrap().is_empty());
+    }
 }
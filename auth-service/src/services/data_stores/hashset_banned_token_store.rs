@@ -16,12 +16,12 @@ impl HashsetBannedTokenStore {
 
 #[async_trait::async_trait]
 impl BannedTokenStore for HashsetBannedTokenStore {
-    async fn add_token(&mut self, token: String) -> Result<(), BannedTokenStoreError> {
-        self.tokens.insert(token);
+    async fn add_token(&mut self, jti: String) -> Result<(), BannedTokenStoreError> {
+        self.tokens.insert(jti);
         Ok(())
     }
-    async fn contains_token(&self, token: String) -> Result<bool, BannedTokenStoreError> {
-        Ok(self.tokens.contains(&token))
+    async fn contains_token(&self, jti: String) -> Result<bool, BannedTokenStoreError> {
+        Ok(self.tokens.contains(&jti))
     }
 }
 
@@ -42,7 +42,7 @@ mod tests {
             false,
         );
 
-        let token = generate_auth_cookie(&user.email)
+        let token = generate_auth_cookie(&user)
             .expect("Failed to generate cookie")
             .value()
             .to_string();
@@ -64,7 +64,7 @@ mod tests {
             false,
         );
 
-        let token = generate_auth_cookie(&user.email)
+        let token = generate_auth_cookie(&user)
             .expect("Failed to generate cookie")
             .value()
             .to_string();
@@ -1,15 +1,63 @@
+pub mod hashmap_authorization_code_store;
+pub mod hashmap_email_outbox_store;
+pub mod hashmap_linked_identity_store;
+pub mod hashmap_oauth_client_store;
+pub mod hashmap_one_time_token_store;
+pub mod hashmap_passkey_store;
+pub mod hashmap_rate_limit_store;
+pub mod hashmap_recovery_code_store;
+pub mod hashmap_refresh_token_store;
+pub mod hashmap_service_client_store;
+pub mod hashmap_session_store;
+pub mod hashmap_totp_secret_store;
 pub mod hashmap_two_fa_code_store;
 pub mod hashmap_user_store;
 pub mod hashset_banned_token_store;
-pub mod mock_email_client;
+pub mod postgres_email_outbox_store;
+pub mod postgres_linked_identity_store;
+pub mod postgres_oauth_client_store;
+pub mod postgres_passkey_store;
+pub mod postgres_recovery_code_store;
+pub mod postgres_refresh_token_store;
+pub mod postgres_service_client_store;
+pub mod postgres_totp_secret_store;
 pub mod postgres_user_store;
+pub mod redis_authorization_code_store;
 pub mod redis_banned_token_store;
+pub mod redis_one_time_token_store;
+pub mod redis_rate_limit_store;
+pub mod redis_refresh_token_store;
+pub mod redis_session_store;
 pub mod redis_two_fa_code_store;
 
+pub use hashmap_authorization_code_store::HashmapAuthorizationCodeStore;
+pub use hashmap_email_outbox_store::HashmapEmailOutboxStore;
+pub use hashmap_linked_identity_store::HashmapLinkedIdentityStore;
+pub use hashmap_oauth_client_store::HashmapOAuthClientStore;
+pub use hashmap_one_time_token_store::HashmapOneTimeTokenStore;
+pub use hashmap_passkey_store::HashmapPasskeyStore;
+pub use hashmap_rate_limit_store::HashmapRateLimitStore;
+pub use hashmap_recovery_code_store::HashmapRecoveryCodeStore;
+pub use hashmap_refresh_token_store::HashmapRefreshTokenStore;
+pub use hashmap_service_client_store::HashmapServiceClientStore;
+pub use hashmap_session_store::HashmapSessionStore;
+pub use hashmap_totp_secret_store::HashmapTotpSecretStore;
 pub use hashmap_two_fa_code_store::HashmapTwoFACodeStore;
 pub use hashmap_user_store::HashmapUserStore;
 pub use hashset_banned_token_store::HashsetBannedTokenStore;
-pub use mock_email_client::MockEmailClient;
+pub use postgres_email_outbox_store::PostgresEmailOutboxStore;
+pub use postgres_linked_identity_store::PostgresLinkedIdentityStore;
+pub use postgres_oauth_client_store::PostgresOAuthClientStore;
+pub use postgres_passkey_store::PostgresPasskeyStore;
+pub use postgres_recovery_code_store::PostgresRecoveryCodeStore;
+pub use postgres_refresh_token_store::PostgresRefreshTokenStore;
+pub use postgres_service_client_store::PostgresServiceClientStore;
+pub use postgres_totp_secret_store::PostgresTotpSecretStore;
 pub use postgres_user_store::PostgresUserStore;
+pub use redis_authorization_code_store::RedisAuthorizationCodeStore;
 pub use redis_banned_token_store::RedisBannedTokenStore;
+pub use redis_one_time_token_store::RedisOneTimeTokenStore;
+pub use redis_rate_limit_store::RedisRateLimitStore;
+pub use redis_refresh_token_store::RedisRefreshTokenStore;
+pub use redis_session_store::RedisSessionStore;
 pub use redis_two_fa_code_store::RedisTwoFACodeStore;
@@ -0,0 +1,187 @@
+use crate::domain::{
+    data_stores::{EmailOutboxStats, EmailOutboxStore, EmailOutboxStoreError, QueuedEmail},
+    Email, EmailMessage,
+};
+use chrono::{DateTime, Utc};
+use sqlx::{query, PgPool};
+use uuid::Uuid;
+
+pub struct PostgresEmailOutboxStore {
+    pool: PgPool,
+}
+
+impl PostgresEmailOutboxStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailOutboxStore for PostgresEmailOutboxStore {
+    #[tracing::instrument(name = "Adding email to PostgreSQL outbox", skip_all)]
+    async fn enqueue(
+        &mut self,
+        recipient: &Email,
+        message: &EmailMessage,
+    ) -> Result<(), EmailOutboxStoreError> {
+        query!(
+            r#"
+            INSERT INTO email_outbox (id, recipient, subject, text_body, html_body)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            Uuid::new_v4(),
+            recipient.as_ref(),
+            message.subject,
+            message.text_body,
+            message.html_body
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| EmailOutboxStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Claiming due emails from PostgreSQL outbox", skip_all)]
+    async fn claim_due(
+        &mut self,
+        limit: i64,
+        lease_seconds: i64,
+    ) -> Result<Vec<QueuedEmail>, EmailOutboxStoreError> {
+        // SKIP LOCKED lets several workers claim batches at the same time
+        // without waiting on, or sending, each other's emails.
+        query!(
+            r#"
+            UPDATE email_outbox
+            SET attempts = attempts + 1,
+                next_attempt_at = NOW() + make_interval(secs => $2)
+            WHERE id IN (
+                SELECT id
+                FROM email_outbox
+                WHERE next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, recipient, subject, text_body, html_body, attempts
+            "#,
+            limit,
+            lease_seconds as f64
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_| EmailOutboxStoreError::UnexpectedError)?
+        .into_iter()
+        .map(|row| {
+            Ok(QueuedEmail {
+                id: row.id,
+                recipient: Email::parse(row.recipient)
+                    .map_err(|_| EmailOutboxStoreError::UnexpectedError)?,
+                message: EmailMessage {
+                    subject: row.subject,
+                    text_body: row.text_body,
+                    html_body: row.html_body,
+                },
+                attempts: row.attempts,
+            })
+        })
+        .collect()
+    }
+
+    #[tracing::instrument(name = "Removing delivered email from PostgreSQL outbox", skip_all)]
+    async fn mark_delivered(&mut self, id: Uuid) -> Result<(), EmailOutboxStoreError> {
+        let result = query!(
+            r#"
+            DELETE FROM email_outbox
+            WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| EmailOutboxStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(EmailOutboxStoreError::EmailNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Scheduling email retry in PostgreSQL outbox", skip_all)]
+    async fn schedule_retry(
+        &mut self,
+        id: Uuid,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), EmailOutboxStoreError> {
+        let result = query!(
+            r#"
+            UPDATE email_outbox
+            SET next_attempt_at = $2, last_error = $3
+            WHERE id = $1
+            "#,
+            id,
+            next_attempt_at,
+            error
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| EmailOutboxStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(EmailOutboxStoreError::EmailNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Dead-lettering email in PostgreSQL outbox", skip_all)]
+    async fn dead_letter(&mut self, id: Uuid, error: &str) -> Result<(), EmailOutboxStoreError> {
+        let result = query!(
+            r#"
+            WITH failed AS (
+                DELETE FROM email_outbox
+                WHERE id = $1
+                RETURNING id, recipient, subject, text_body, html_body, attempts, created_at
+            )
+            INSERT INTO email_dead_letters
+                (id, recipient, subject, text_body, html_body, attempts, last_error, created_at)
+            SELECT id, recipient, subject, text_body, html_body, attempts, $2, created_at
+            FROM failed
+            "#,
+            id,
+            error
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| EmailOutboxStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(EmailOutboxStoreError::EmailNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving PostgreSQL outbox stats", skip_all)]
+    async fn get_stats(&self) -> Result<EmailOutboxStats, EmailOutboxStoreError> {
+        let row = query!(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM email_outbox) AS "pending!",
+                (SELECT COUNT(*) FROM email_dead_letters) AS "dead_lettered!",
+                (SELECT MIN(created_at) FROM email_outbox) AS oldest_pending_at
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|_| EmailOutboxStoreError::UnexpectedError)?;
+
+        Ok(EmailOutboxStats {
+            pending: row.pending,
+            dead_lettered: row.dead_lettered,
+            oldest_pending_at: row.oldest_pending_at,
+        })
+    }
+}
@@ -0,0 +1,69 @@
+use crate::domain::{
+    data_stores::{LinkedIdentityStore, LinkedIdentityStoreError},
+    Email,
+};
+use sqlx::{query, PgPool};
+
+pub struct PostgresLinkedIdentityStore {
+    pool: PgPool,
+}
+
+impl PostgresLinkedIdentityStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl LinkedIdentityStore for PostgresLinkedIdentityStore {
+    #[tracing::instrument(name = "Adding linked identity to PostgreSQL", skip_all)]
+    async fn add_identity(
+        &mut self,
+        provider: &str,
+        subject: &str,
+        email: &Email,
+    ) -> Result<(), LinkedIdentityStoreError> {
+        let result = query!(
+            r#"
+            INSERT INTO linked_identities (provider, subject, email)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (provider, subject) DO NOTHING
+            "#,
+            provider,
+            subject,
+            email.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| LinkedIdentityStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(LinkedIdentityStoreError::IdentityAlreadyLinked);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving linked identity from PostgreSQL", skip_all)]
+    async fn get_email(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Email, LinkedIdentityStoreError> {
+        let row = query!(
+            r#"
+            SELECT email
+            FROM linked_identities
+            WHERE provider = $1 AND subject = $2
+            "#,
+            provider,
+            subject
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| LinkedIdentityStoreError::UnexpectedError)?
+        .ok_or(LinkedIdentityStoreError::IdentityNotFound)?;
+
+        Email::parse(row.email).map_err(|_| LinkedIdentityStoreError::UnexpectedError)
+    }
+}
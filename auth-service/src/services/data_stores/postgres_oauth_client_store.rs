@@ -0,0 +1,116 @@
+use crate::domain::{
+    data_stores::{OAuthClientStore, OAuthClientStoreError},
+    Email, OAuthClient,
+};
+use sqlx::{query, PgPool};
+
+pub struct PostgresOAuthClientStore {
+    pool: PgPool,
+}
+
+impl PostgresOAuthClientStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl OAuthClientStore for PostgresOAuthClientStore {
+    #[tracing::instrument(name = "Adding OAuth client to PostgreSQL", skip_all)]
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthClientStoreError> {
+        let result = query!(
+            r#"
+            INSERT INTO oauth_clients (client_id, name, redirect_uris)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (client_id) DO NOTHING
+            "#,
+            client.client_id,
+            client.name,
+            &client.redirect_uris
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| OAuthClientStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(OAuthClientStoreError::ClientAlreadyExists);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving OAuth client from PostgreSQL", skip_all)]
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthClientStoreError> {
+        query!(
+            r#"
+            SELECT client_id, name, redirect_uris
+            FROM oauth_clients
+            WHERE client_id = $1
+            "#,
+            client_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| OAuthClientStoreError::UnexpectedError)?
+        .map(|row| OAuthClient {
+            client_id: row.client_id,
+            name: row.name,
+            redirect_uris: row.redirect_uris,
+        })
+        .ok_or(OAuthClientStoreError::ClientNotFound)
+    }
+
+    #[tracing::instrument(name = "Retrieving OAuth consent from PostgreSQL", skip_all)]
+    async fn get_consent(
+        &self,
+        email: &Email,
+        client_id: &str,
+    ) -> Result<Vec<String>, OAuthClientStoreError> {
+        let consent = query!(
+            r#"
+            SELECT scopes
+            FROM oauth_consents
+            WHERE email = $1 AND client_id = $2
+            "#,
+            email.as_ref(),
+            client_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| OAuthClientStoreError::UnexpectedError)?;
+
+        Ok(consent.map(|row| row.scopes).unwrap_or_default())
+    }
+
+    #[tracing::instrument(name = "Granting OAuth consent in PostgreSQL", skip_all)]
+    async fn grant_consent(
+        &mut self,
+        email: &Email,
+        client_id: &str,
+        scopes: &[String],
+    ) -> Result<(), OAuthClientStoreError> {
+        query!(
+            r#"
+            INSERT INTO oauth_consents (email, client_id, scopes)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (email, client_id) DO UPDATE
+            SET scopes = ARRAY(
+                SELECT DISTINCT unnest(oauth_consents.scopes || EXCLUDED.scopes)
+            )
+            "#,
+            email.as_ref(),
+            client_id,
+            scopes
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(e) if e.is_foreign_key_violation() => {
+                OAuthClientStoreError::ClientNotFound
+            }
+            _ => OAuthClientStoreError::UnexpectedError,
+        })?;
+
+        Ok(())
+    }
+}
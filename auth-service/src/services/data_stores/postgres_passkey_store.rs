@@ -0,0 +1,97 @@
+use crate::domain::{
+    data_stores::{Passkey, PasskeyStore, PasskeyStoreError},
+    Email,
+};
+use sqlx::{query, PgPool};
+
+pub struct PostgresPasskeyStore {
+    pool: PgPool,
+}
+
+impl PostgresPasskeyStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl PasskeyStore for PostgresPasskeyStore {
+    #[tracing::instrument(name = "Adding passkey to PostgreSQL", skip_all)]
+    async fn add_passkey(&mut self, passkey: Passkey) -> Result<(), PasskeyStoreError> {
+        let result = query!(
+            r#"
+            INSERT INTO passkeys (credential_id, email, public_key, sign_count)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (credential_id) DO NOTHING
+            "#,
+            &passkey.credential_id,
+            passkey.email.as_ref(),
+            &passkey.public_key,
+            i64::from(passkey.sign_count)
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| PasskeyStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(PasskeyStoreError::PasskeyAlreadyExists);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving passkeys from PostgreSQL", skip_all)]
+    async fn get_passkeys(&self, email: &Email) -> Result<Vec<Passkey>, PasskeyStoreError> {
+        query!(
+            r#"
+            SELECT credential_id, public_key, sign_count
+            FROM passkeys
+            WHERE email = $1
+            ORDER BY created_at
+            "#,
+            email.as_ref()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_| PasskeyStoreError::UnexpectedError)?
+        .into_iter()
+        .map(|row| {
+            Ok(Passkey {
+                credential_id: row.credential_id,
+                email: email.clone(),
+                public_key: row.public_key,
+                sign_count: row
+                    .sign_count
+                    .try_into()
+                    .map_err(|_| PasskeyStoreError::UnexpectedError)?,
+            })
+        })
+        .collect()
+    }
+
+    #[tracing::instrument(name = "Updating passkey sign count in PostgreSQL", skip_all)]
+    async fn update_sign_count(
+        &mut self,
+        credential_id: &[u8],
+        sign_count: u32,
+    ) -> Result<(), PasskeyStoreError> {
+        let result = query!(
+            r#"
+            UPDATE passkeys
+            SET sign_count = $2
+            WHERE credential_id = $1
+            "#,
+            credential_id,
+            i64::from(sign_count)
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| PasskeyStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(PasskeyStoreError::PasskeyNotFound);
+        }
+
+        Ok(())
+    }
+}
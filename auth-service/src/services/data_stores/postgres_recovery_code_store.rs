@@ -0,0 +1,114 @@
+use crate::domain::{
+    data_stores::{RecoveryCode, RecoveryCodeStore, RecoveryCodeStoreError},
+    Email,
+};
+use sha2::{Digest, Sha256};
+use sqlx::{query, PgPool};
+
+/// Stores only SHA-256 hashes of recovery codes. The codes are random enough
+/// that a fast hash suffices, and it keeps lookups a single indexed query.
+pub struct PostgresRecoveryCodeStore {
+    pool: PgPool,
+}
+
+impl PostgresRecoveryCodeStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl RecoveryCodeStore for PostgresRecoveryCodeStore {
+    #[tracing::instrument(name = "Replacing recovery codes in PostgreSQL", skip_all)]
+    async fn replace_codes(
+        &mut self,
+        email: &Email,
+        codes: Vec<RecoveryCode>,
+    ) -> Result<(), RecoveryCodeStoreError> {
+        let code_hashes: Vec<String> = codes.iter().map(hash_code).collect();
+
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?;
+
+        query!(
+            r#"
+            DELETE FROM recovery_codes
+            WHERE email = $1
+            "#,
+            email.as_ref()
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?;
+
+        query!(
+            r#"
+            INSERT INTO recovery_codes (email, code_hash)
+            SELECT $1, code_hash FROM UNNEST($2::TEXT[]) AS code_hash
+            "#,
+            email.as_ref(),
+            &code_hashes
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?;
+
+        transaction
+            .commit()
+            .await
+            .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Consuming recovery code in PostgreSQL", skip_all)]
+    async fn consume_code(
+        &mut self,
+        email: &Email,
+        code: &RecoveryCode,
+    ) -> Result<(), RecoveryCodeStoreError> {
+        let result = query!(
+            r#"
+            DELETE FROM recovery_codes
+            WHERE email = $1 AND code_hash = $2
+            "#,
+            email.as_ref(),
+            hash_code(code)
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(RecoveryCodeStoreError::CodeNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Counting recovery codes in PostgreSQL", skip_all)]
+    async fn count_codes(&self, email: &Email) -> Result<usize, RecoveryCodeStoreError> {
+        let row = query!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM recovery_codes
+            WHERE email = $1
+            "#,
+            email.as_ref()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?;
+
+        row.count
+            .try_into()
+            .map_err(|_| RecoveryCodeStoreError::UnexpectedError)
+    }
+}
+
+fn hash_code(code: &RecoveryCode) -> String {
+    hex::encode(Sha256::digest(code.as_ref().as_bytes()))
+}
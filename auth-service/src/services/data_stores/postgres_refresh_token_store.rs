@@ -0,0 +1,130 @@
+use crate::{
+    domain::{
+        data_stores::{
+            RefreshToken, RefreshTokenRecord, RefreshTokenStore, RefreshTokenStoreError,
+        },
+        Email,
+    },
+    utils::auth::REFRESH_TOKEN_TTL_SECONDS,
+};
+use sqlx::{query, PgPool};
+
+pub struct PostgresRefreshTokenStore {
+    pool: PgPool,
+}
+
+impl PostgresRefreshTokenStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl RefreshTokenStore for PostgresRefreshTokenStore {
+    #[tracing::instrument(name = "Adding refresh token to PostgreSQL", skip_all)]
+    async fn add_token(
+        &mut self,
+        token: RefreshToken,
+        record: RefreshTokenRecord,
+    ) -> Result<(), RefreshTokenStoreError> {
+        query!(
+            r#"
+            INSERT INTO refresh_tokens (token, email, family_id, used, expires_at)
+            VALUES ($1, $2, $3, $4, NOW() + make_interval(secs => $5))
+            "#,
+            token.as_ref(),
+            record.email.as_ref(),
+            &record.family_id,
+            record.used,
+            REFRESH_TOKEN_TTL_SECONDS as f64
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving refresh token from PostgreSQL", skip_all)]
+    async fn get_token(
+        &self,
+        token: &RefreshToken,
+    ) -> Result<RefreshTokenRecord, RefreshTokenStoreError> {
+        query!(
+            r#"
+            SELECT email, family_id, used
+            FROM refresh_tokens
+            WHERE token = $1 AND expires_at > NOW()
+            "#,
+            token.as_ref()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| RefreshTokenStoreError::UnexpectedError)?
+        .map(|row| {
+            Ok(RefreshTokenRecord {
+                email: Email::parse(row.email)
+                    .map_err(|_| RefreshTokenStoreError::UnexpectedError)?,
+                family_id: row.family_id,
+                used: row.used,
+            })
+        })
+        .ok_or(RefreshTokenStoreError::TokenNotFound)?
+    }
+
+    #[tracing::instrument(name = "Marking refresh token as used in PostgreSQL", skip_all)]
+    async fn mark_token_used(
+        &mut self,
+        token: &RefreshToken,
+    ) -> Result<(), RefreshTokenStoreError> {
+        let result = query!(
+            r#"
+            UPDATE refresh_tokens
+            SET used = TRUE
+            WHERE token = $1
+            "#,
+            token.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(RefreshTokenStoreError::TokenNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Revoking refresh token family in PostgreSQL", skip_all)]
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError> {
+        query!(
+            r#"
+            DELETE FROM refresh_tokens
+            WHERE family_id = $1
+            "#,
+            family_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Revoking refresh tokens of user in PostgreSQL", skip_all)]
+    async fn revoke_user(&mut self, email: &Email) -> Result<(), RefreshTokenStoreError> {
+        query!(
+            r#"
+            DELETE FROM refresh_tokens
+            WHERE email = $1
+            "#,
+            email.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use crate::domain::{
+    data_stores::{ServiceClientStore, ServiceClientStoreError},
+    ClientSecret, ServiceClient,
+};
+use sha2::{Digest, Sha256};
+use sqlx::{query, PgPool};
+
+/// Stores only SHA-256 hashes of client secrets. Like recovery codes, the
+/// secrets are generated by us and random enough that a fast hash suffices.
+pub struct PostgresServiceClientStore {
+    pool: PgPool,
+}
+
+impl PostgresServiceClientStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ServiceClientStore for PostgresServiceClientStore {
+    #[tracing::instrument(name = "Adding service client to PostgreSQL", skip_all)]
+    async fn add_client(
+        &mut self,
+        client: ServiceClient,
+        secret: &ClientSecret,
+    ) -> Result<(), ServiceClientStoreError> {
+        let result = query!(
+            r#"
+            INSERT INTO service_clients (client_id, name, secret_hash, scopes)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (client_id) DO NOTHING
+            "#,
+            client.client_id,
+            client.name,
+            hash_secret(secret),
+            &client.scopes
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| ServiceClientStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(ServiceClientStoreError::ClientAlreadyExists);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Validating service client in PostgreSQL", skip_all)]
+    async fn validate_client(
+        &self,
+        client_id: &str,
+        secret: &ClientSecret,
+    ) -> Result<ServiceClient, ServiceClientStoreError> {
+        query!(
+            r#"
+            SELECT client_id, name, scopes
+            FROM service_clients
+            WHERE client_id = $1 AND secret_hash = $2
+            "#,
+            client_id,
+            hash_secret(secret)
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| ServiceClientStoreError::UnexpectedError)?
+        .map(|row| ServiceClient {
+            client_id: row.client_id,
+            name: row.name,
+            scopes: row.scopes,
+        })
+        .ok_or(ServiceClientStoreError::InvalidCredentials)
+    }
+}
+
+fn hash_secret(secret: &ClientSecret) -> String {
+    hex::encode(Sha256::digest(secret.as_ref().as_bytes()))
+}
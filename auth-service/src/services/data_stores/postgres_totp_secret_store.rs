@@ -0,0 +1,147 @@
+use crate::{
+    domain::{
+        data_stores::{TotpSecretStore, TotpSecretStoreError},
+        Email,
+    },
+    utils::totp::SecretCipher,
+};
+use sqlx::{query, PgPool};
+
+/// Stores TOTP secrets encrypted with a key that never reaches the database.
+pub struct PostgresTotpSecretStore {
+    pool: PgPool,
+    cipher: SecretCipher,
+}
+
+impl PostgresTotpSecretStore {
+    pub fn new(pool: PgPool, cipher: SecretCipher) -> Self {
+        Self { pool, cipher }
+    }
+
+    fn decrypt(&self, secret: Option<Vec<u8>>) -> Result<Vec<u8>, TotpSecretStoreError> {
+        self.cipher
+            .decrypt(&secret.ok_or(TotpSecretStoreError::SecretNotFound)?)
+            .map_err(|_| TotpSecretStoreError::UnexpectedError)
+    }
+}
+
+#[async_trait::async_trait]
+impl TotpSecretStore for PostgresTotpSecretStore {
+    #[tracing::instrument(name = "Storing pending TOTP secret in PostgreSQL", skip_all)]
+    async fn set_pending_secret(
+        &mut self,
+        email: &Email,
+        secret: Vec<u8>,
+    ) -> Result<(), TotpSecretStoreError> {
+        let encrypted = self
+            .cipher
+            .encrypt(&secret)
+            .map_err(|_| TotpSecretStoreError::UnexpectedError)?;
+
+        query!(
+            r#"
+            INSERT INTO totp_secrets (email, pending_secret)
+            VALUES ($1, $2)
+            ON CONFLICT (email) DO UPDATE SET pending_secret = EXCLUDED.pending_secret
+            "#,
+            email.as_ref(),
+            &encrypted
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| TotpSecretStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving pending TOTP secret from PostgreSQL", skip_all)]
+    async fn get_pending_secret(&self, email: &Email) -> Result<Vec<u8>, TotpSecretStoreError> {
+        let row = query!(
+            r#"
+            SELECT pending_secret
+            FROM totp_secrets
+            WHERE email = $1
+            "#,
+            email.as_ref()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| TotpSecretStoreError::UnexpectedError)?
+        .ok_or(TotpSecretStoreError::SecretNotFound)?;
+
+        self.decrypt(row.pending_secret)
+    }
+
+    #[tracing::instrument(name = "Confirming TOTP secret in PostgreSQL", skip_all)]
+    async fn confirm_pending_secret(&mut self, email: &Email) -> Result<(), TotpSecretStoreError> {
+        let result = query!(
+            r#"
+            UPDATE totp_secrets
+            SET secret = pending_secret, pending_secret = NULL, last_used_step = NULL
+            WHERE email = $1 AND pending_secret IS NOT NULL
+            "#,
+            email.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| TotpSecretStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(TotpSecretStoreError::SecretNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving TOTP secret from PostgreSQL", skip_all)]
+    async fn get_secret(&self, email: &Email) -> Result<Vec<u8>, TotpSecretStoreError> {
+        let row = query!(
+            r#"
+            SELECT secret
+            FROM totp_secrets
+            WHERE email = $1
+            "#,
+            email.as_ref()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| TotpSecretStoreError::UnexpectedError)?
+        .ok_or(TotpSecretStoreError::SecretNotFound)?;
+
+        self.decrypt(row.secret)
+    }
+
+    #[tracing::instrument(name = "Recording used TOTP step in PostgreSQL", skip_all)]
+    async fn record_used_step(
+        &mut self,
+        email: &Email,
+        step: u64,
+    ) -> Result<(), TotpSecretStoreError> {
+        let step = i64::try_from(step).map_err(|_| TotpSecretStoreError::UnexpectedError)?;
+
+        // Compared and updated in one statement, so concurrent logins can't
+        // both use the same code.
+        let result = query!(
+            r#"
+            UPDATE totp_secrets
+            SET last_used_step = $2
+            WHERE email = $1 AND secret IS NOT NULL
+                AND (last_used_step IS NULL OR last_used_step < $2)
+            "#,
+            email.as_ref(),
+            step
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| TotpSecretStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return match self.get_secret(email).await {
+                Ok(_) => Err(TotpSecretStoreError::CodeAlreadyUsed),
+                Err(e) => Err(e),
+            };
+        }
+
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use crate::domain::{
     data_stores::{UserStore, UserStoreError},
-    Email, Password, User,
+    AccountLock, Email, Locale, Password, TwoFAMethod, User,
 };
 use argon2::{
     password_hash::SaltString, Algorithm, Argon2, Params, PasswordHash, PasswordHasher,
@@ -29,12 +29,17 @@ impl UserStore for PostgresUserStore {
 
         query!(
             r#"
-            INSERT INTO users (email, password_hash, requires_2fa)
-            VALUES ($1, $2, $3)
+            INSERT INTO users
+                (id, email, password_hash, requires_2fa, email_verified, two_fa_method, locale)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
             "#,
+            user.id,
             user.email.as_ref(),
             &password_hash,
-            user.requires_2fa
+            user.requires_2fa,
+            user.email_verified,
+            user.two_fa_method.as_ref(),
+            user.locale.as_ref().map(AsRef::as_ref)
         )
         .execute(&self.pool)
         .await
@@ -47,7 +52,8 @@ impl UserStore for PostgresUserStore {
     async fn get_user(&self, email: &Email) -> Result<User, UserStoreError> {
         query!(
             r#"
-            SELECT email, password_hash, requires_2fa
+            SELECT id, email, password_hash, requires_2fa, email_verified, two_fa_method,
+                locked_until, lock_reason, token_version, locale
             FROM users
             WHERE email = $1
             "#,
@@ -58,10 +64,24 @@ impl UserStore for PostgresUserStore {
         .map_err(|_| UserStoreError::UnexpectedError)?
         .map(|row| {
             Ok(User {
+                id: row.id,
                 email: Email::parse(row.email).map_err(|_| UserStoreError::UnexpectedError)?,
                 password: Password::parse(row.password_hash)
                     .map_err(|_| UserStoreError::UnexpectedError)?,
                 requires_2fa: row.requires_2fa,
+                email_verified: row.email_verified,
+                two_fa_method: TwoFAMethod::parse(&row.two_fa_method)
+                    .map_err(|_| UserStoreError::UnexpectedError)?,
+                lock: row.locked_until.map(|locked_until| AccountLock {
+                    locked_until,
+                    reason: row.lock_reason.unwrap_or_default(),
+                }),
+                token_version: row.token_version,
+                locale: row
+                    .locale
+                    .map(|locale| Locale::parse(&locale))
+                    .transpose()
+                    .map_err(|_| UserStoreError::UnexpectedError)?,
             })
         })
         .ok_or(UserStoreError::UserNotFound)?
@@ -75,6 +95,10 @@ impl UserStore for PostgresUserStore {
     ) -> Result<(), UserStoreError> {
         let user = self.get_user(email).await?;
 
+        if user.is_locked() {
+            return Err(UserStoreError::AccountLocked);
+        }
+
         verify_password_hash(
             user.password.as_ref().to_owned(),
             password.as_ref().to_owned(),
@@ -82,6 +106,207 @@ impl UserStore for PostgresUserStore {
         .await
         .map_err(|_| UserStoreError::InvalidCredentials)
     }
+
+    #[tracing::instrument(name = "Updating user password in PostgreSQL", skip_all)]
+    async fn update_password(
+        &mut self,
+        email: &Email,
+        password: Password,
+    ) -> Result<(), UserStoreError> {
+        let password_hash = compute_password_hash(password.as_ref().to_owned())
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+
+        let result = query!(
+            r#"
+            UPDATE users
+            SET password_hash = $2
+            WHERE email = $1
+            "#,
+            email.as_ref(),
+            &password_hash
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Marking user email as verified in PostgreSQL", skip_all)]
+    async fn mark_email_verified(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        let result = query!(
+            r#"
+            UPDATE users
+            SET email_verified = TRUE
+            WHERE email = $1
+            "#,
+            email.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Deleting user from PostgreSQL", skip_all)]
+    async fn delete_user(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        let result = query!(
+            r#"
+            DELETE FROM users
+            WHERE email = $1
+            "#,
+            email.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Updating user 2FA settings in PostgreSQL", skip_all)]
+    async fn set_two_fa(
+        &mut self,
+        email: &Email,
+        requires_2fa: bool,
+        method: TwoFAMethod,
+    ) -> Result<(), UserStoreError> {
+        let result = query!(
+            r#"
+            UPDATE users
+            SET requires_2fa = $2, two_fa_method = $3
+            WHERE email = $1
+            "#,
+            email.as_ref(),
+            requires_2fa,
+            method.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Incrementing user token version in PostgreSQL", skip_all)]
+    async fn increment_token_version(&mut self, email: &Email) -> Result<i32, UserStoreError> {
+        query!(
+            r#"
+            UPDATE users
+            SET token_version = token_version + 1
+            WHERE email = $1
+            RETURNING token_version
+            "#,
+            email.as_ref()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?
+        .map(|row| row.token_version)
+        .ok_or(UserStoreError::UserNotFound)
+    }
+
+    #[tracing::instrument(name = "Locking user in PostgreSQL", skip_all)]
+    async fn lock_user(&mut self, email: &Email, lock: AccountLock) -> Result<(), UserStoreError> {
+        let result = query!(
+            r#"
+            UPDATE users
+            SET locked_until = $2, lock_reason = $3
+            WHERE email = $1
+            "#,
+            email.as_ref(),
+            lock.locked_until,
+            lock.reason
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Unlocking user in PostgreSQL", skip_all)]
+    async fn unlock_user(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        let result = query!(
+            r#"
+            UPDATE users
+            SET locked_until = NULL, lock_reason = NULL
+            WHERE email = $1
+            "#,
+            email.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving locked users from PostgreSQL", skip_all)]
+    async fn get_locked_users(&self) -> Result<Vec<User>, UserStoreError> {
+        query!(
+            r#"
+            SELECT id, email, password_hash, requires_2fa, email_verified, two_fa_method,
+                locked_until, lock_reason, token_version, locale
+            FROM users
+            WHERE locked_until > NOW()
+            ORDER BY locked_until
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?
+        .into_iter()
+        .map(|row| {
+            Ok(User {
+                id: row.id,
+                email: Email::parse(row.email).map_err(|_| UserStoreError::UnexpectedError)?,
+                password: Password::parse(row.password_hash)
+                    .map_err(|_| UserStoreError::UnexpectedError)?,
+                requires_2fa: row.requires_2fa,
+                email_verified: row.email_verified,
+                two_fa_method: TwoFAMethod::parse(&row.two_fa_method)
+                    .map_err(|_| UserStoreError::UnexpectedError)?,
+                lock: row.locked_until.map(|locked_until| AccountLock {
+                    locked_until,
+                    reason: row.lock_reason.unwrap_or_default(),
+                }),
+                token_version: row.token_version,
+                locale: row
+                    .locale
+                    .map(|locale| Locale::parse(&locale))
+                    .transpose()
+                    .map_err(|_| UserStoreError::UnexpectedError)?,
+            })
+        })
+        .collect()
+    }
 }
 
 #[tracing::instrument(name = "Verify password hash", skip_all)]
@@ -0,0 +1,110 @@
+use crate::domain::{
+    data_stores::{AuthorizationCodeStore, AuthorizationCodeStoreError},
+    AuthorizationCode, AuthorizationGrant, CodeChallenge, Email, AUTHORIZATION_CODE_TTL_SECONDS,
+};
+use redis::{Commands, Connection};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub struct RedisAuthorizationCodeStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisAuthorizationCodeStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl AuthorizationCodeStore for RedisAuthorizationCodeStore {
+    async fn add_code(
+        &mut self,
+        code: AuthorizationCode,
+        grant: AuthorizationGrant,
+    ) -> Result<(), AuthorizationCodeStoreError> {
+        let value = serde_json::to_string(&AuthorizationGrantValue::from(&grant))
+            .map_err(|_| AuthorizationCodeStoreError::UnexpectedError)?;
+        let ttl: u64 = AUTHORIZATION_CODE_TTL_SECONDS
+            .try_into()
+            .map_err(|_| AuthorizationCodeStoreError::UnexpectedError)?;
+
+        let _: () = self
+            .conn
+            .write()
+            .await
+            .set_ex(get_key(&code), value, ttl)
+            .map_err(|_| AuthorizationCodeStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn consume_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> Result<AuthorizationGrant, AuthorizationCodeStoreError> {
+        let key = get_key(code);
+        let mut conn = self.conn.write().await;
+
+        let value: Option<String> = conn
+            .get(&key)
+            .map_err(|_| AuthorizationCodeStoreError::UnexpectedError)?;
+        let value = value.ok_or(AuthorizationCodeStoreError::CodeNotFound)?;
+
+        let _: () = conn
+            .del(&key)
+            .map_err(|_| AuthorizationCodeStoreError::UnexpectedError)?;
+
+        let value: AuthorizationGrantValue = serde_json::from_str(&value)
+            .map_err(|_| AuthorizationCodeStoreError::UnexpectedError)?;
+
+        value.try_into()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct AuthorizationGrantValue {
+    client_id: String,
+    redirect_uri: String,
+    email: String,
+    scopes: Vec<String>,
+    code_challenge: String,
+    nonce: Option<String>,
+}
+
+impl From<&AuthorizationGrant> for AuthorizationGrantValue {
+    fn from(grant: &AuthorizationGrant) -> Self {
+        Self {
+            client_id: grant.client_id.clone(),
+            redirect_uri: grant.redirect_uri.clone(),
+            email: grant.email.as_ref().to_owned(),
+            scopes: grant.scopes.clone(),
+            code_challenge: grant.code_challenge.as_ref().to_owned(),
+            nonce: grant.nonce.clone(),
+        }
+    }
+}
+
+impl TryFrom<AuthorizationGrantValue> for AuthorizationGrant {
+    type Error = AuthorizationCodeStoreError;
+
+    fn try_from(value: AuthorizationGrantValue) -> Result<Self, Self::Error> {
+        Ok(Self {
+            client_id: value.client_id,
+            redirect_uri: value.redirect_uri,
+            email: Email::parse(value.email)
+                .map_err(|_| AuthorizationCodeStoreError::UnexpectedError)?,
+            scopes: value.scopes,
+            code_challenge: CodeChallenge::parse(value.code_challenge)
+                .map_err(|_| AuthorizationCodeStoreError::UnexpectedError)?,
+            nonce: value.nonce,
+        })
+    }
+}
+
+const AUTHORIZATION_CODE_PREFIX: &str = "authorization_code:";
+
+fn get_key(code: &AuthorizationCode) -> String {
+    format!("{}{}", AUTHORIZATION_CODE_PREFIX, code.as_ref())
+}
@@ -18,8 +18,8 @@ impl RedisBannedTokenStore {
 
 #[async_trait::async_trait]
 impl BannedTokenStore for RedisBannedTokenStore {
-    async fn add_token(&mut self, token: String) -> Result<(), BannedTokenStoreError> {
-        let token_key = get_key(token.as_str());
+    async fn add_token(&mut self, jti: String) -> Result<(), BannedTokenStoreError> {
+        let token_key = get_key(jti.as_str());
         let value = true;
         let ttl: u64 = TOKEN_TTL_SECONDS
             .try_into()
@@ -35,8 +35,8 @@ impl BannedTokenStore for RedisBannedTokenStore {
         Ok(())
     }
 
-    async fn contains_token(&self, token: String) -> Result<bool, BannedTokenStoreError> {
-        let token_key = get_key(token.as_str());
+    async fn contains_token(&self, jti: String) -> Result<bool, BannedTokenStoreError> {
+        let token_key = get_key(jti.as_str());
 
         let is_banned: bool = self
             .conn
@@ -0,0 +1,109 @@
+use crate::domain::{
+    data_stores::{OneTimeToken, OneTimeTokenStore, OneTimeTokenStoreError, TokenPurpose},
+    Email,
+};
+use chrono::{DateTime, Utc};
+use redis::{Commands, Connection};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub struct RedisOneTimeTokenStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisOneTimeTokenStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl OneTimeTokenStore for RedisOneTimeTokenStore {
+    async fn add_token(
+        &mut self,
+        token: OneTimeToken,
+        purpose: TokenPurpose,
+        email: Email,
+    ) -> Result<(), OneTimeTokenStoreError> {
+        let ttl: u64 = purpose
+            .ttl_seconds()
+            .try_into()
+            .map_err(|_| OneTimeTokenStoreError::UnexpectedError)?;
+
+        let mut conn = self.conn.write().await;
+
+        let _: () = conn
+            .set_ex(get_key(&token, purpose), email.as_ref(), ttl)
+            .map_err(|_| OneTimeTokenStoreError::UnexpectedError)?;
+        let _: () = conn
+            .set_ex(
+                get_issued_at_key(&email, purpose),
+                Utc::now().timestamp(),
+                ttl,
+            )
+            .map_err(|_| OneTimeTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn consume_token(
+        &mut self,
+        token: &OneTimeToken,
+        purpose: TokenPurpose,
+    ) -> Result<Email, OneTimeTokenStoreError> {
+        let key = get_key(token, purpose);
+        let mut conn = self.conn.write().await;
+
+        let email: Option<String> = conn
+            .get(&key)
+            .map_err(|_| OneTimeTokenStoreError::UnexpectedError)?;
+        let email = email.ok_or(OneTimeTokenStoreError::TokenNotFound)?;
+
+        let _: () = conn
+            .del(&key)
+            .map_err(|_| OneTimeTokenStoreError::UnexpectedError)?;
+
+        Email::parse(email).map_err(|_| OneTimeTokenStoreError::UnexpectedError)
+    }
+
+    async fn last_issued_at(
+        &self,
+        email: &Email,
+        purpose: TokenPurpose,
+    ) -> Result<Option<DateTime<Utc>>, OneTimeTokenStoreError> {
+        let timestamp: Option<i64> = self
+            .conn
+            .write()
+            .await
+            .get(get_issued_at_key(email, purpose))
+            .map_err(|_| OneTimeTokenStoreError::UnexpectedError)?;
+
+        timestamp
+            .map(|timestamp| {
+                DateTime::from_timestamp(timestamp, 0)
+                    .ok_or(OneTimeTokenStoreError::UnexpectedError)
+            })
+            .transpose()
+    }
+}
+
+const ONE_TIME_TOKEN_KEY_PREFIX: &str = "one_time_token:";
+const ONE_TIME_TOKEN_ISSUED_AT_KEY_PREFIX: &str = "one_time_token_issued_at:";
+
+fn get_key(token: &OneTimeToken, purpose: TokenPurpose) -> String {
+    format!(
+        "{}{}:{}",
+        ONE_TIME_TOKEN_KEY_PREFIX,
+        purpose.as_ref(),
+        token.as_ref()
+    )
+}
+
+fn get_issued_at_key(email: &Email, purpose: TokenPurpose) -> String {
+    format!(
+        "{}{}:{}",
+        ONE_TIME_TOKEN_ISSUED_AT_KEY_PREFIX,
+        purpose.as_ref(),
+        email.as_ref()
+    )
+}
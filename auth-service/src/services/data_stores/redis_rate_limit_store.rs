@@ -0,0 +1,113 @@
+use super::HashmapRateLimitStore;
+use crate::domain::data_stores::{FailedAttempts, RateLimitStore, RateLimitStoreError};
+use chrono::DateTime;
+use redis::{Commands, Connection, RedisError};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Keeps attempt counters in Redis. While Redis can't be reached, counters
+/// are kept in memory instead, so an outage never switches throttling off.
+/// In-memory counters are per instance and are not carried over to Redis.
+pub struct RedisRateLimitStore {
+    conn: Arc<RwLock<Connection>>,
+    fallback: HashmapRateLimitStore,
+}
+
+impl RedisRateLimitStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self {
+            conn,
+            fallback: HashmapRateLimitStore::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+    async fn get_attempts(&self, key: &str) -> Result<FailedAttempts, RateLimitStoreError> {
+        let value: Result<Option<String>, RedisError> = self.conn.write().await.get(get_key(key));
+
+        match value {
+            Ok(Some(value)) => serde_json::from_str::<FailedAttemptsValue>(&value)
+                .map_err(|_| RateLimitStoreError::UnexpectedError)?
+                .try_into(),
+            Ok(None) => self.fallback.get_attempts(key).await,
+            Err(e) => {
+                tracing::warn!("Falling back to in-memory rate limiting: {}", e);
+                self.fallback.get_attempts(key).await
+            }
+        }
+    }
+
+    async fn set_attempts(
+        &mut self,
+        key: &str,
+        attempts: FailedAttempts,
+        ttl_seconds: i64,
+    ) -> Result<(), RateLimitStoreError> {
+        let value = serde_json::to_string(&FailedAttemptsValue::from(&attempts))
+            .map_err(|_| RateLimitStoreError::UnexpectedError)?;
+        let ttl: u64 = ttl_seconds.max(1).unsigned_abs();
+
+        let result: Result<(), RedisError> =
+            self.conn.write().await.set_ex(get_key(key), value, ttl);
+
+        match result {
+            Ok(()) => self.fallback.clear_attempts(key).await,
+            Err(e) => {
+                tracing::warn!("Falling back to in-memory rate limiting: {}", e);
+                self.fallback.set_attempts(key, attempts, ttl_seconds).await
+            }
+        }
+    }
+
+    async fn clear_attempts(&mut self, key: &str) -> Result<(), RateLimitStoreError> {
+        self.fallback.clear_attempts(key).await?;
+
+        let result: Result<(), RedisError> = self.conn.write().await.del(get_key(key));
+        if let Err(e) = result {
+            tracing::warn!("Failed to clear rate limit in Redis: {}", e);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct FailedAttemptsValue {
+    count: u32,
+    locked_until: Option<i64>,
+}
+
+impl From<&FailedAttempts> for FailedAttemptsValue {
+    fn from(attempts: &FailedAttempts) -> Self {
+        Self {
+            count: attempts.count,
+            locked_until: attempts.locked_until.map(|time| time.timestamp()),
+        }
+    }
+}
+
+impl TryFrom<FailedAttemptsValue> for FailedAttempts {
+    type Error = RateLimitStoreError;
+
+    fn try_from(value: FailedAttemptsValue) -> Result<Self, Self::Error> {
+        Ok(Self {
+            count: value.count,
+            locked_until: value
+                .locked_until
+                .map(|timestamp| {
+                    DateTime::from_timestamp(timestamp, 0)
+                        .ok_or(RateLimitStoreError::UnexpectedError)
+                })
+                .transpose()?,
+        })
+    }
+}
+
+const RATE_LIMIT_PREFIX: &str = "rate_limit:";
+
+fn get_key(key: &str) -> String {
+    format!("{}{}", RATE_LIMIT_PREFIX, key)
+}
@@ -0,0 +1,194 @@
+use crate::{
+    domain::{
+        data_stores::{
+            RefreshToken, RefreshTokenRecord, RefreshTokenStore, RefreshTokenStoreError,
+        },
+        Email,
+    },
+    utils::auth::REFRESH_TOKEN_TTL_SECONDS,
+};
+use redis::{Commands, Connection, SetExpiry, SetOptions};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub struct RedisRefreshTokenStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisRefreshTokenStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl RefreshTokenStore for RedisRefreshTokenStore {
+    async fn add_token(
+        &mut self,
+        token: RefreshToken,
+        record: RefreshTokenRecord,
+    ) -> Result<(), RefreshTokenStoreError> {
+        let token_key = get_token_key(&token);
+        let family_key = get_family_key(&record.family_id);
+        let user_key = get_user_key(&record.email);
+        let value = serde_json::to_string(&RefreshTokenValue::from(&record))
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+        let ttl: u64 = REFRESH_TOKEN_TTL_SECONDS
+            .try_into()
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        let mut conn = self.conn.write().await;
+
+        let _: () = conn
+            .set_ex(&token_key, value, ttl)
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+        let _: () = conn
+            .sadd(&family_key, token.as_ref())
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+        let _: () = conn
+            .expire(&family_key, ttl as i64)
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+        let _: () = conn
+            .sadd(&user_key, &record.family_id)
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+        let _: () = conn
+            .expire(&user_key, ttl as i64)
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn get_token(
+        &self,
+        token: &RefreshToken,
+    ) -> Result<RefreshTokenRecord, RefreshTokenStoreError> {
+        let value: Option<String> = self
+            .conn
+            .write()
+            .await
+            .get(get_token_key(token))
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        let value: RefreshTokenValue =
+            serde_json::from_str(&value.ok_or(RefreshTokenStoreError::TokenNotFound)?)
+                .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        value.try_into()
+    }
+
+    async fn mark_token_used(
+        &mut self,
+        token: &RefreshToken,
+    ) -> Result<(), RefreshTokenStoreError> {
+        let mut record = self.get_token(token).await?;
+        record.used = true;
+
+        let value = serde_json::to_string(&RefreshTokenValue::from(&record))
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        let _: () = self
+            .conn
+            .write()
+            .await
+            .set_options(
+                get_token_key(token),
+                value,
+                SetOptions::default().with_expiration(SetExpiry::KEEPTTL),
+            )
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError> {
+        let family_key = get_family_key(family_id);
+        let mut conn = self.conn.write().await;
+
+        let tokens: Vec<String> = conn
+            .smembers(&family_key)
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        for token in tokens {
+            let _: () = conn
+                .del(format!("{}{}", REFRESH_TOKEN_PREFIX, token))
+                .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+        }
+
+        let _: () = conn
+            .del(&family_key)
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn revoke_user(&mut self, email: &Email) -> Result<(), RefreshTokenStoreError> {
+        let user_key = get_user_key(email);
+
+        let families: Vec<String> = self
+            .conn
+            .write()
+            .await
+            .smembers(&user_key)
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        for family_id in families {
+            self.revoke_family(&family_id).await?;
+        }
+
+        let _: () = self
+            .conn
+            .write()
+            .await
+            .del(&user_key)
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct RefreshTokenValue {
+    email: String,
+    family_id: String,
+    used: bool,
+}
+
+impl From<&RefreshTokenRecord> for RefreshTokenValue {
+    fn from(record: &RefreshTokenRecord) -> Self {
+        Self {
+            email: record.email.as_ref().to_owned(),
+            family_id: record.family_id.clone(),
+            used: record.used,
+        }
+    }
+}
+
+impl TryFrom<RefreshTokenValue> for RefreshTokenRecord {
+    type Error = RefreshTokenStoreError;
+
+    fn try_from(value: RefreshTokenValue) -> Result<Self, Self::Error> {
+        Ok(Self {
+            email: Email::parse(value.email)
+                .map_err(|_| RefreshTokenStoreError::UnexpectedError)?,
+            family_id: value.family_id,
+            used: value.used,
+        })
+    }
+}
+
+const REFRESH_TOKEN_PREFIX: &str = "refresh_token:";
+const REFRESH_TOKEN_FAMILY_PREFIX: &str = "refresh_token_family:";
+const REFRESH_TOKEN_USER_PREFIX: &str = "refresh_token_user:";
+
+fn get_token_key(token: &RefreshToken) -> String {
+    format!("{}{}", REFRESH_TOKEN_PREFIX, token.as_ref())
+}
+
+fn get_family_key(family_id: &str) -> String {
+    format!("{}{}", REFRESH_TOKEN_FAMILY_PREFIX, family_id)
+}
+
+fn get_user_key(email: &Email) -> String {
+    format!("{}{}", REFRESH_TOKEN_USER_PREFIX, email.as_ref())
+}
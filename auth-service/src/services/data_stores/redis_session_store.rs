@@ -0,0 +1,175 @@
+use crate::{
+    domain::{
+        data_stores::{Session, SessionStore, SessionStoreError},
+        Email,
+    },
+    utils::auth::REFRESH_TOKEN_TTL_SECONDS,
+};
+use chrono::{DateTime, Utc};
+use redis::{Commands, Connection};
+use serde::{Deserialize, Serialize};
+use std::{net::IpAddr, sync::Arc};
+use tokio::sync::RwLock;
+
+pub struct RedisSessionStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisSessionStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl SessionStore for RedisSessionStore {
+    async fn add_session(&mut self, session: Session) -> Result<(), SessionStoreError> {
+        let session_key = get_session_key(&session.id);
+        let user_key = get_user_key(&session.email);
+        let value = serde_json::to_string(&SessionValue::from(&session))
+            .map_err(|_| SessionStoreError::UnexpectedError)?;
+        // A session lives as long as the refresh token that keeps it going.
+        let ttl: u64 = REFRESH_TOKEN_TTL_SECONDS
+            .try_into()
+            .map_err(|_| SessionStoreError::UnexpectedError)?;
+
+        let mut conn = self.conn.write().await;
+
+        let _: () = conn
+            .set_ex(&session_key, value, ttl)
+            .map_err(|_| SessionStoreError::UnexpectedError)?;
+        let _: () = conn
+            .sadd(&user_key, &session.id)
+            .map_err(|_| SessionStoreError::UnexpectedError)?;
+        let _: () = conn
+            .expire(&user_key, ttl as i64)
+            .map_err(|_| SessionStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn get_session(&self, id: &str) -> Result<Session, SessionStoreError> {
+        let value: Option<String> = self
+            .conn
+            .write()
+            .await
+            .get(get_session_key(id))
+            .map_err(|_| SessionStoreError::UnexpectedError)?;
+
+        let value: SessionValue =
+            serde_json::from_str(&value.ok_or(SessionStoreError::SessionNotFound)?)
+                .map_err(|_| SessionStoreError::UnexpectedError)?;
+
+        value.try_into()
+    }
+
+    async fn get_sessions(&self, email: &Email) -> Result<Vec<Session>, SessionStoreError> {
+        let ids: Vec<String> = self
+            .conn
+            .write()
+            .await
+            .smembers(get_user_key(email))
+            .map_err(|_| SessionStoreError::UnexpectedError)?;
+
+        let mut sessions = Vec::with_capacity(ids.len());
+        for id in ids {
+            // The index outlives sessions that expired on their own.
+            match self.get_session(&id).await {
+                Ok(session) => sessions.push(session),
+                Err(SessionStoreError::SessionNotFound) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(sessions)
+    }
+
+    async fn remove_session(&mut self, id: &str) -> Result<(), SessionStoreError> {
+        let session = self.get_session(id).await?;
+        let mut conn = self.conn.write().await;
+
+        let _: () = conn
+            .del(get_session_key(id))
+            .map_err(|_| SessionStoreError::UnexpectedError)?;
+        let _: () = conn
+            .srem(get_user_key(&session.email), id)
+            .map_err(|_| SessionStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn remove_user_sessions(&mut self, email: &Email) -> Result<(), SessionStoreError> {
+        let user_key = get_user_key(email);
+        let mut conn = self.conn.write().await;
+
+        let ids: Vec<String> = conn
+            .smembers(&user_key)
+            .map_err(|_| SessionStoreError::UnexpectedError)?;
+
+        for id in ids {
+            let _: () = conn
+                .del(get_session_key(&id))
+                .map_err(|_| SessionStoreError::UnexpectedError)?;
+        }
+
+        let _: () = conn
+            .del(&user_key)
+            .map_err(|_| SessionStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SessionValue {
+    id: String,
+    email: String,
+    family_id: String,
+    user_agent: Option<String>,
+    ip: IpAddr,
+    created_at: i64,
+    last_seen_at: i64,
+}
+
+impl From<&Session> for SessionValue {
+    fn from(session: &Session) -> Self {
+        Self {
+            id: session.id.clone(),
+            email: session.email.as_ref().to_owned(),
+            family_id: session.family_id.clone(),
+            user_agent: session.user_agent.clone(),
+            ip: session.ip,
+            created_at: session.created_at.timestamp(),
+            last_seen_at: session.last_seen_at.timestamp(),
+        }
+    }
+}
+
+impl TryFrom<SessionValue> for Session {
+    type Error = SessionStoreError;
+
+    fn try_from(value: SessionValue) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.id,
+            email: Email::parse(value.email).map_err(|_| SessionStoreError::UnexpectedError)?,
+            family_id: value.family_id,
+            user_agent: value.user_agent,
+            ip: value.ip,
+            created_at: DateTime::<Utc>::from_timestamp(value.created_at, 0)
+                .ok_or(SessionStoreError::UnexpectedError)?,
+            last_seen_at: DateTime::<Utc>::from_timestamp(value.last_seen_at, 0)
+                .ok_or(SessionStoreError::UnexpectedError)?,
+        })
+    }
+}
+
+const SESSION_PREFIX: &str = "session:";
+const SESSION_USER_PREFIX: &str = "session_user:";
+
+fn get_session_key(id: &str) -> String {
+    format!("{}{}", SESSION_PREFIX, id)
+}
+
+fn get_user_key(email: &Email) -> String {
+    format!("{}{}", SESSION_USER_PREFIX, email.as_ref())
+}
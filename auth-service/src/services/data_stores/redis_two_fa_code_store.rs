@@ -23,12 +23,12 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
         &mut self,
         email: Email,
         login_attempt_id: LoginAttemptId,
-        code: TwoFACode,
+        code: Option<TwoFACode>,
     ) -> Result<(), TwoFACodeStoreError> {
         let key = get_key(&email);
         let value = TwoFATuple(
             login_attempt_id.as_ref().to_string(),
-            code.as_ref().to_string(),
+            code.map(|code| code.as_ref().to_string()),
         );
         let value =
             serde_json::to_string(&value).map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
@@ -46,7 +46,7 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
     async fn get_code(
         &self,
         email: &Email,
-    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+    ) -> Result<(LoginAttemptId, Option<TwoFACode>), TwoFACodeStoreError> {
         let key = get_key(email);
 
         let value: String = self
@@ -61,8 +61,11 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
 
         let login_attempt_id =
             LoginAttemptId::parse(value.0).map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
-        let two_fa_code =
-            TwoFACode::parse(value.1).map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        let two_fa_code = value
+            .1
+            .map(TwoFACode::parse)
+            .transpose()
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
 
         Ok((login_attempt_id, two_fa_code))
     }
@@ -82,7 +85,7 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
 }
 
 #[derive(Serialize, Deserialize)]
-struct TwoFATuple(pub String, pub String);
+struct TwoFATuple(pub String, pub Option<String>);
 
 const TEN_MINUTES_IN_SECONDS: u64 = 600;
 const TWO_FA_CODE_PREFIX: &str = "two_fa_code:";
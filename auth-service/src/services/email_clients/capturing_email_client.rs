@@ -0,0 +1,131 @@
+use crate::domain::{Email, EmailClient, EmailClientError, EmailMessage};
+use chrono::{DateTime, Utc};
+use std::sync::{Arc, Mutex};
+
+/// An email the capturing client was asked to send.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SentEmail {
+    pub recipient: Email,
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: String,
+    pub sent_at: DateTime<Utc>,
+}
+
+/// Keeps every email in memory instead of sending it, so tests can read
+/// them the way users would. Clones share the same emails, so one can be
+/// handed to the app and another kept to inspect them.
+#[derive(Clone, Default)]
+pub struct CapturingEmailClient {
+    sent_emails: Arc<Mutex<Vec<SentEmail>>>,
+}
+
+impl CapturingEmailClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every email sent so far, oldest first.
+    pub fn sent_emails(&self) -> Vec<SentEmail> {
+        self.sent_emails.lock().unwrap().clone()
+    }
+
+    /// The emails sent to `recipient`, oldest first.
+    pub fn emails_to(&self, recipient: &Email) -> Vec<SentEmail> {
+        self.sent_emails
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|email| &email.recipient == recipient)
+            .cloned()
+            .collect()
+    }
+
+    pub fn last_email_to(&self, recipient: &Email) -> Option<SentEmail> {
+        self.sent_emails
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|email| &email.recipient == recipient)
+            .cloned()
+    }
+
+    /// The latest email to `recipient` with the given subject, to skip the
+    /// other emails a flow sends along the way.
+    pub fn last_email_with_subject(&self, recipient: &Email, subject: &str) -> Option<SentEmail> {
+        self.sent_emails
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|email| &email.recipient == recipient && email.subject == subject)
+            .cloned()
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailClient for CapturingEmailClient {
+    async fn send_email(
+        &self,
+        recipient: &Email,
+        message: &EmailMessage,
+    ) -> Result<(), EmailClientError> {
+        self.sent_emails.lock().unwrap().push(SentEmail {
+            recipient: recipient.clone(),
+            subject: message.subject.clone(),
+            text_body: message.text_body.clone(),
+            html_body: message.html_body.clone(),
+            sent_at: Utc::now(),
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(subject: &str) -> EmailMessage {
+        EmailMessage {
+            subject: subject.to_owned(),
+            text_body: format!("{} text", subject),
+            html_body: format!("<p>{}</p>", subject),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_records_emails_per_recipient() {
+        let client = CapturingEmailClient::new();
+        let shared = client.clone();
+        let alice = Email::parse("alice@example.com".to_owned()).unwrap();
+        let bob = Email::parse("bob@example.com".to_owned()).unwrap();
+
+        client.send_email(&alice, &message("First")).await.unwrap();
+        client.send_email(&bob, &message("Second")).await.unwrap();
+        client.send_email(&alice, &message("Third")).await.unwrap();
+
+        assert_eq!(shared.sent_emails().len(), 3);
+        let subjects: Vec<String> = shared
+            .emails_to(&alice)
+            .into_iter()
+            .map(|email| email.subject)
+            .collect();
+        assert_eq!(subjects, ["First", "Third"]);
+
+        let last = shared.last_email_to(&alice).unwrap();
+        assert_eq!(last.subject, "Third");
+        assert_eq!(last.text_body, "Third text");
+        assert!(last.sent_at >= shared.emails_to(&alice)[0].sent_at);
+
+        assert_eq!(
+            shared
+                .last_email_with_subject(&alice, "First")
+                .unwrap()
+                .text_body,
+            "First text"
+        );
+        assert_eq!(shared.last_email_with_subject(&bob, "First"), None);
+    }
+}
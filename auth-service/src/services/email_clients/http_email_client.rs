@@ -0,0 +1,185 @@
+use crate::domain::{Email, EmailClient, EmailClientError, EmailMessage};
+use reqwest::{header::ACCEPT, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use url::Url;
+
+#[derive(Clone, Debug)]
+pub struct HttpEmailConfig {
+    /// Where the API lives, like `https://api.postmarkapp.com`.
+    pub base_url: String,
+    pub api_token: String,
+    /// The `From` address, optionally with a name, like
+    /// `Auth Service <no-reply@example.com>`.
+    pub sender: String,
+    /// Limits each attempt, so retries get the full time again.
+    pub timeout: Duration,
+    /// How often a failed request is repeated before giving up.
+    pub max_retries: u32,
+    /// The wait before the first retry, doubled for each one after it.
+    pub retry_delay: Duration,
+}
+
+/// Delivers emails through a transactional email HTTP API, in Postmark's
+/// format. Requests the provider failed on its side are retried with
+/// exponential backoff.
+pub struct HttpEmailClient {
+    config: HttpEmailConfig,
+    endpoint: Url,
+    http_client: reqwest::Client,
+}
+
+impl HttpEmailClient {
+    pub fn new(config: HttpEmailConfig) -> Result<Self, String> {
+        let endpoint = Url::parse(&config.base_url)
+            .and_then(|base_url| base_url.join("email"))
+            .map_err(|_| "Invalid email API URL".to_string())?;
+        let http_client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            config,
+            endpoint,
+            http_client,
+        })
+    }
+
+    async fn post_email(&self, request: &SendEmailRequest<'_>) -> Result<(), EmailClientError> {
+        let response = self
+            .http_client
+            .post(self.endpoint.clone())
+            .header(ACCEPT, "application/json")
+            .header("X-Postmark-Server-Token", &self.config.api_token)
+            .json(request)
+            .send()
+            .await
+            .map_err(request_error)?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        // The provider explains refusals in the body, but its failures may
+        // come from a proxy in front of it, without a JSON body.
+        let message = response
+            .json::<ErrorResponse>()
+            .await
+            .map(|error| format!("{}: {}", status, error.message))
+            .unwrap_or_else(|_| status.to_string());
+        if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+            Err(EmailClientError::Unavailable(message))
+        } else {
+            Err(EmailClientError::Rejected(message))
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailClient for HttpEmailClient {
+    #[tracing::instrument(name = "Sending email over HTTP", skip_all)]
+    async fn send_email(
+        &self,
+        recipient: &Email,
+        message: &EmailMessage,
+    ) -> Result<(), EmailClientError> {
+        let request = SendEmailRequest {
+            from: &self.config.sender,
+            to: recipient.as_ref(),
+            subject: &message.subject,
+            text_body: &message.text_body,
+            html_body: &message.html_body,
+        };
+
+        let mut retries = 0;
+        loop {
+            match self.post_email(&request).await {
+                Err(e @ (EmailClientError::Timeout | EmailClientError::Unavailable(_)))
+                    if retries < self.config.max_retries =>
+                {
+                    let delay = self.config.retry_delay * 2u32.pow(retries);
+                    tracing::warn!("Failed to send email, retrying in {:?}: {:?}", delay, e);
+                    tokio::time::sleep(delay).await;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+fn request_error(e: reqwest::Error) -> EmailClientError {
+    if e.is_timeout() {
+        EmailClientError::Timeout
+    } else if e.is_connect() || e.is_request() {
+        EmailClientError::Unavailable(e.to_string())
+    } else {
+        EmailClientError::UnexpectedError(e.to_string())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct SendEmailRequest<'a> {
+    from: &'a str,
+    to: &'a str,
+    subject: &'a str,
+    text_body: &'a str,
+    html_body: &'a str,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ErrorResponse {
+    message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(base_url: &str) -> HttpEmailConfig {
+        HttpEmailConfig {
+            base_url: base_url.to_owned(),
+            api_token: "token".to_owned(),
+            sender: "no-reply@example.com".to_owned(),
+            timeout: Duration::from_secs(10),
+            max_retries: 3,
+            retry_delay: Duration::from_millis(100),
+        }
+    }
+
+    #[test]
+    fn test_endpoint() {
+        let client = HttpEmailClient::new(config("https://api.postmarkapp.com")).unwrap();
+        assert_eq!(
+            client.endpoint.as_str(),
+            "https://api.postmarkapp.com/email"
+        );
+        assert!(HttpEmailClient::new(config("not a url")).is_err());
+    }
+
+    #[test]
+    fn test_request_uses_provider_field_names() {
+        let request = SendEmailRequest {
+            from: "no-reply@example.com",
+            to: "user@example.com",
+            subject: "Subject",
+            text_body: "Text",
+            html_body: "<p>HTML</p>",
+        };
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "From": "no-reply@example.com",
+                "To": "user@example.com",
+                "Subject": "Subject",
+                "TextBody": "Text",
+                "HtmlBody": "<p>HTML</p>",
+            })
+        );
+    }
+}
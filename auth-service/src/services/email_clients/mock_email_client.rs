@@ -1,4 +1,4 @@
-use crate::domain::{Email, EmailClient};
+use crate::domain::{Email, EmailClient, EmailClientError, EmailMessage};
 
 pub struct MockEmailClient;
 
@@ -7,14 +7,13 @@ impl EmailClient for MockEmailClient {
     async fn send_email(
         &self,
         recipient: &Email,
-        subject: &str,
-        content: &str,
-    ) -> Result<(), String> {
+        message: &EmailMessage,
+    ) -> Result<(), EmailClientError> {
         println!(
             "Sending email to {} with subject: {} and content: {}",
             recipient.as_ref(),
-            subject,
-            content,
+            message.subject,
+            message.text_body,
         );
 
         Ok(())
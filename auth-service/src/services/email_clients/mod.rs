@@ -0,0 +1,9 @@
+pub mod capturing_email_client;
+pub mod http_email_client;
+pub mod mock_email_client;
+pub mod smtp_email_client;
+
+pub use capturing_email_client::{CapturingEmailClient, SentEmail};
+pub use http_email_client::{HttpEmailClient, HttpEmailConfig};
+pub use mock_email_client::MockEmailClient;
+pub use smtp_email_client::{SmtpConfig, SmtpEmailClient, SmtpTls};
@@ -0,0 +1,151 @@
+use crate::domain::{Email, EmailClient, EmailClientError, EmailMessage};
+use lettre::{
+    message::{Mailbox, MultiPart},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use std::time::Duration;
+
+/// How the connection to the SMTP server is secured.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SmtpTls {
+    /// Plain text, only for servers on the same host or a private network.
+    None,
+    /// Upgraded with STARTTLS, by default on port 587.
+    StartTls,
+    /// Implicit TLS, by default on port 465.
+    Tls,
+}
+
+impl SmtpTls {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "starttls" => Ok(Self::StartTls),
+            "tls" => Ok(Self::Tls),
+            _ => Err(format!("Unknown SMTP TLS mode: {}", value)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SmtpConfig {
+    pub host: String,
+    /// Defaults to the port of the TLS mode, or 25 without TLS.
+    pub port: Option<u16>,
+    pub tls: SmtpTls,
+    /// Username and password, for servers that require authentication.
+    pub credentials: Option<(String, String)>,
+    /// The `From` address, optionally with a name, like
+    /// `Auth Service <no-reply@example.com>`.
+    pub sender: String,
+    /// Limits each command sent to the server.
+    pub timeout: Duration,
+}
+
+/// Delivers emails through an SMTP server, as multipart messages with both
+/// the text and the HTML body.
+pub struct SmtpEmailClient {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    sender: Mailbox,
+}
+
+impl SmtpEmailClient {
+    pub fn new(config: SmtpConfig) -> Result<Self, String> {
+        let sender = config
+            .sender
+            .parse::<Mailbox>()
+            .map_err(|e| format!("Invalid sender address: {}", e))?;
+
+        let mut builder = match config.tls {
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+                .map_err(|e| e.to_string())?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)
+                .map_err(|e| e.to_string())?,
+        }
+        .timeout(Some(config.timeout));
+        if let Some(port) = config.port {
+            builder = builder.port(port);
+        }
+        if let Some((username, password)) = config.credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            sender,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailClient for SmtpEmailClient {
+    #[tracing::instrument(name = "Sending email over SMTP", skip_all)]
+    async fn send_email(
+        &self,
+        recipient: &Email,
+        message: &EmailMessage,
+    ) -> Result<(), EmailClientError> {
+        let recipient = recipient
+            .as_ref()
+            .parse::<Mailbox>()
+            .map_err(|e| EmailClientError::Rejected(format!("Invalid recipient address: {}", e)))?;
+        let email = Message::builder()
+            .from(self.sender.clone())
+            .to(recipient)
+            .subject(&message.subject)
+            .multipart(MultiPart::alternative_plain_html(
+                message.text_body.clone(),
+                message.html_body.clone(),
+            ))
+            .map_err(|e| EmailClientError::UnexpectedError(e.to_string()))?;
+
+        self.transport.send(email).await.map_err(send_error)?;
+
+        Ok(())
+    }
+}
+
+/// Permanent replies, in the 5xx range, mean the server won't take the
+/// email. Anything else may pass on another attempt.
+fn send_error(e: lettre::transport::smtp::Error) -> EmailClientError {
+    if e.is_timeout() {
+        EmailClientError::Timeout
+    } else if e.is_permanent() {
+        EmailClientError::Rejected(e.to_string())
+    } else {
+        EmailClientError::Unavailable(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(sender: &str) -> SmtpConfig {
+        SmtpConfig {
+            host: "localhost".to_owned(),
+            port: None,
+            tls: SmtpTls::StartTls,
+            credentials: None,
+            sender: sender.to_owned(),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    #[test]
+    fn test_parse_tls_mode() {
+        assert_eq!(SmtpTls::parse("STARTTLS"), Ok(SmtpTls::StartTls));
+        assert_eq!(SmtpTls::parse("tls"), Ok(SmtpTls::Tls));
+        assert_eq!(SmtpTls::parse("none"), Ok(SmtpTls::None));
+        assert!(SmtpTls::parse("ssl").is_err());
+    }
+
+    // The connection pool is started on the runtime.
+    #[tokio::test]
+    async fn test_rejects_invalid_sender() {
+        assert!(SmtpEmailClient::new(config("Auth Service <no-reply@example.com>")).is_ok());
+        assert!(SmtpEmailClient::new(config("not an address")).is_err());
+    }
+}
@@ -0,0 +1,258 @@
+use crate::{
+    app_state::{EmailClientType, EmailOutboxStoreType},
+    domain::{EmailClientError, EmailOutboxStoreError, QueuedEmail},
+    utils::constants::{
+        EMAIL_OUTBOX_BATCH_SIZE, EMAIL_OUTBOX_LEASE_SECONDS, EMAIL_OUTBOX_MAX_ATTEMPTS,
+        EMAIL_OUTBOX_MAX_RETRY_DELAY_SECONDS, EMAIL_OUTBOX_POLL_INTERVAL_MILLIS,
+        EMAIL_OUTBOX_RETRY_DELAY_SECONDS,
+    },
+};
+use chrono::Utc;
+use std::time::Duration;
+
+#[derive(Clone, Debug)]
+pub struct EmailOutboxConfig {
+    /// How long the worker sleeps when no email is due.
+    pub poll_interval: Duration,
+    pub batch_size: i64,
+    /// Attempts after which an email is moved to the dead letters.
+    pub max_attempts: i32,
+    /// The wait before the first retry, doubled for each one after it.
+    pub retry_delay: chrono::Duration,
+    pub max_retry_delay: chrono::Duration,
+}
+
+impl Default for EmailOutboxConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(EMAIL_OUTBOX_POLL_INTERVAL_MILLIS),
+            batch_size: EMAIL_OUTBOX_BATCH_SIZE,
+            max_attempts: EMAIL_OUTBOX_MAX_ATTEMPTS,
+            retry_delay: chrono::Duration::seconds(EMAIL_OUTBOX_RETRY_DELAY_SECONDS),
+            max_retry_delay: chrono::Duration::seconds(EMAIL_OUTBOX_MAX_RETRY_DELAY_SECONDS),
+        }
+    }
+}
+
+/// Delivers the emails queued in the outbox, so requests never wait on the
+/// email provider or fail with it.
+#[derive(Clone)]
+pub struct EmailOutboxWorker {
+    email_outbox_store: EmailOutboxStoreType,
+    email_client: EmailClientType,
+    config: EmailOutboxConfig,
+}
+
+impl EmailOutboxWorker {
+    pub fn new(email_outbox_store: EmailOutboxStoreType, email_client: EmailClientType) -> Self {
+        Self {
+            email_outbox_store,
+            email_client,
+            config: EmailOutboxConfig::default(),
+        }
+    }
+
+    pub fn with_config(mut self, config: EmailOutboxConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Delivers due emails until the app stops.
+    pub async fn run(self) {
+        loop {
+            match self.deliver_due().await {
+                // A full batch suggests more are waiting.
+                Ok(delivered) if delivered as i64 == self.config.batch_size => continue,
+                Ok(_) => {}
+                Err(e) => tracing::error!("Failed to read the email outbox: {:?}", e),
+            }
+            tokio::time::sleep(self.config.poll_interval).await;
+        }
+    }
+
+    /// Attempts one batch of due emails and returns how many were claimed.
+    #[tracing::instrument(name = "Delivering queued emails", skip_all)]
+    pub async fn deliver_due(&self) -> Result<usize, EmailOutboxStoreError> {
+        let emails = self
+            .email_outbox_store
+            .write()
+            .await
+            .claim_due(self.config.batch_size, EMAIL_OUTBOX_LEASE_SECONDS)
+            .await?;
+
+        for email in &emails {
+            let result = self
+                .email_client
+                .read()
+                .await
+                .send_email(&email.recipient, &email.message)
+                .await;
+            self.record_result(email, result).await?;
+        }
+
+        Ok(emails.len())
+    }
+
+    async fn record_result(
+        &self,
+        email: &QueuedEmail,
+        result: Result<(), EmailClientError>,
+    ) -> Result<(), EmailOutboxStoreError> {
+        let mut email_outbox_store = self.email_outbox_store.write().await;
+        let error = match result {
+            Ok(()) => return email_outbox_store.mark_delivered(email.id).await,
+            Err(e) => e,
+        };
+
+        // Rejected emails would be rejected again, so they aren't retried.
+        let retry = !matches!(error, EmailClientError::Rejected(_))
+            && email.attempts < self.config.max_attempts;
+        let error = format!("{:?}", error);
+        if !retry {
+            tracing::error!(
+                "Giving up on email after {} attempts: {}",
+                email.attempts,
+                error
+            );
+            return email_outbox_store.dead_letter(email.id, &error).await;
+        }
+
+        let delay = self.retry_delay(email.attempts);
+        tracing::warn!("Failed to deliver email, retrying in {}: {}", delay, error);
+        email_outbox_store
+            .schedule_retry(email.id, &error, Utc::now() + delay)
+            .await
+    }
+
+    fn retry_delay(&self, attempts: i32) -> chrono::Duration {
+        let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+        (self.config.retry_delay * 2i32.pow(exponent)).min(self.config.max_retry_delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{Email, EmailClient, EmailMessage, EmailOutboxStats},
+        services::HashmapEmailOutboxStore,
+    };
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::RwLock;
+
+    /// Fails with the scripted errors in turn, then delivers.
+    struct FlakyEmailClient {
+        errors: Mutex<Vec<EmailClientError>>,
+    }
+
+    #[async_trait::async_trait]
+    impl EmailClient for FlakyEmailClient {
+        async fn send_email(
+            &self,
+            _recipient: &Email,
+            _message: &EmailMessage,
+        ) -> Result<(), EmailClientError> {
+            match self.errors.lock().unwrap().pop() {
+                Some(error) => Err(error),
+                None => Ok(()),
+            }
+        }
+    }
+
+    async fn worker(errors: Vec<EmailClientError>) -> (EmailOutboxWorker, EmailOutboxStoreType) {
+        let email_outbox_store: EmailOutboxStoreType =
+            Arc::new(RwLock::new(HashmapEmailOutboxStore::new()));
+        email_outbox_store
+            .write()
+            .await
+            .enqueue(
+                &Email::parse("test@example.com".to_owned()).unwrap(),
+                &EmailMessage {
+                    subject: "Subject".to_owned(),
+                    text_body: "Text".to_owned(),
+                    html_body: "<p>HTML</p>".to_owned(),
+                },
+            )
+            .await
+            .unwrap();
+        let email_client = Arc::new(RwLock::new(FlakyEmailClient {
+            errors: Mutex::new(errors),
+        }));
+
+        let worker = EmailOutboxWorker::new(email_outbox_store.clone(), email_client).with_config(
+            EmailOutboxConfig {
+                max_attempts: 3,
+                retry_delay: chrono::Duration::zero(),
+                ..EmailOutboxConfig::default()
+            },
+        );
+        (worker, email_outbox_store)
+    }
+
+    async fn stats(email_outbox_store: &EmailOutboxStoreType) -> EmailOutboxStats {
+        email_outbox_store.read().await.get_stats().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_retries_until_delivered() {
+        let (worker, email_outbox_store) = worker(vec![
+            EmailClientError::Timeout,
+            EmailClientError::Unavailable("503".to_owned()),
+        ])
+        .await;
+
+        for _ in 0..2 {
+            assert_eq!(worker.deliver_due().await, Ok(1));
+            assert_eq!(stats(&email_outbox_store).await.pending, 1);
+        }
+        assert_eq!(worker.deliver_due().await, Ok(1));
+        assert_eq!(
+            stats(&email_outbox_store).await,
+            EmailOutboxStats::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_after_max_attempts() {
+        let (worker, email_outbox_store) = worker(vec![
+            EmailClientError::Timeout,
+            EmailClientError::Timeout,
+            EmailClientError::Timeout,
+        ])
+        .await;
+
+        for _ in 0..3 {
+            assert_eq!(worker.deliver_due().await, Ok(1));
+        }
+        let stats = stats(&email_outbox_store).await;
+        assert_eq!(stats.pending, 0);
+        assert_eq!(stats.dead_lettered, 1);
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_rejected_email_right_away() {
+        let (worker, email_outbox_store) =
+            worker(vec![EmailClientError::Rejected("422".to_owned())]).await;
+
+        assert_eq!(worker.deliver_due().await, Ok(1));
+        assert_eq!(stats(&email_outbox_store).await.dead_lettered, 1);
+    }
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_maximum() {
+        let worker = EmailOutboxWorker::new(
+            Arc::new(RwLock::new(HashmapEmailOutboxStore::new())),
+            Arc::new(RwLock::new(crate::services::MockEmailClient)),
+        )
+        .with_config(EmailOutboxConfig {
+            retry_delay: chrono::Duration::seconds(30),
+            max_retry_delay: chrono::Duration::seconds(100),
+            ..EmailOutboxConfig::default()
+        });
+
+        assert_eq!(worker.retry_delay(1), chrono::Duration::seconds(30));
+        assert_eq!(worker.retry_delay(2), chrono::Duration::seconds(60));
+        assert_eq!(worker.retry_delay(3), chrono::Duration::seconds(100));
+        assert_eq!(worker.retry_delay(100), chrono::Duration::seconds(100));
+    }
+}
@@ -0,0 +1,5 @@
+pub mod mock_identity_provider;
+pub mod oauth2_identity_provider;
+
+pub use mock_identity_provider::MockIdentityProvider;
+pub use oauth2_identity_provider::{OAuth2IdentityProvider, OAuth2ProviderConfig};
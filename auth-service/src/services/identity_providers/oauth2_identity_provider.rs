@@ -0,0 +1,278 @@
+use crate::domain::{
+    CodeChallenge, Email, ExternalIdentity, IdentityProvider, IdentityProviderError,
+};
+use reqwest::header::ACCEPT;
+use serde::Deserialize;
+use std::time::Duration;
+use url::Url;
+
+/// Where an OAuth2 or OpenID Connect provider lives, and our client's
+/// registration with it.
+#[derive(Clone, Debug)]
+pub struct OAuth2ProviderConfig {
+    pub name: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    /// Returns the user as JSON, with a `sub` or `id` and usually an
+    /// `email` and `email_verified`.
+    pub userinfo_endpoint: String,
+    /// Lists the user's addresses with whether they are verified, for
+    /// providers whose userinfo doesn't say, like GitHub.
+    pub emails_endpoint: Option<String>,
+    pub scopes: Vec<String>,
+}
+
+impl OAuth2ProviderConfig {
+    pub fn google(client_id: String, client_secret: String) -> Self {
+        Self {
+            name: "google".to_owned(),
+            client_id,
+            client_secret,
+            authorization_endpoint: "https://accounts.google.com/o/oauth2/v2/auth".to_owned(),
+            token_endpoint: "https://oauth2.googleapis.com/token".to_owned(),
+            userinfo_endpoint: "https://openidconnect.googleapis.com/v1/userinfo".to_owned(),
+            emails_endpoint: None,
+            scopes: vec!["openid".to_owned(), "email".to_owned()],
+        }
+    }
+
+    pub fn github(client_id: String, client_secret: String) -> Self {
+        Self {
+            name: "github".to_owned(),
+            client_id,
+            client_secret,
+            authorization_endpoint: "https://github.com/login/oauth/authorize".to_owned(),
+            token_endpoint: "https://github.com/login/oauth/access_token".to_owned(),
+            userinfo_endpoint: "https://api.github.com/user".to_owned(),
+            emails_endpoint: Some("https://api.github.com/user/emails".to_owned()),
+            scopes: vec!["read:user".to_owned(), "user:email".to_owned()],
+        }
+    }
+}
+
+/// Logs users in with any provider that implements the authorization code
+/// flow and has a userinfo endpoint.
+pub struct OAuth2IdentityProvider {
+    config: OAuth2ProviderConfig,
+    authorization_endpoint: Url,
+    http_client: reqwest::Client,
+}
+
+impl OAuth2IdentityProvider {
+    pub fn new(config: OAuth2ProviderConfig) -> Result<Self, String> {
+        let authorization_endpoint = Url::parse(&config.authorization_endpoint)
+            .map_err(|_| "Invalid authorization endpoint".to_string())?;
+        let http_client = reqwest::Client::builder()
+            // GitHub's API refuses requests without one.
+            .user_agent("auth-service")
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            config,
+            authorization_endpoint,
+            http_client,
+        })
+    }
+
+    async fn request_access_token(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        redirect_uri: &str,
+    ) -> Result<String, IdentityProviderError> {
+        let response = self
+            .http_client
+            .post(&self.config.token_endpoint)
+            // GitHub answers with a form unless asked for JSON.
+            .header(ACCEPT, "application/json")
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", redirect_uri),
+                ("client_id", &self.config.client_id),
+                ("client_secret", &self.config.client_secret),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await
+            .map_err(unexpected_error)?;
+        if response.status().is_server_error() {
+            return Err(IdentityProviderError::UnexpectedError(format!(
+                "Token endpoint responded with {}",
+                response.status()
+            )));
+        }
+
+        // Failed exchanges are reported in the body, with a 400 or, by
+        // GitHub, a 200.
+        let status = response.status();
+        let token = response
+            .json::<TokenResponse>()
+            .await
+            .map_err(unexpected_error)?;
+        match token.access_token {
+            Some(access_token) if status.is_success() => Ok(access_token),
+            _ => Err(IdentityProviderError::InvalidCode),
+        }
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        access_token: &str,
+    ) -> Result<T, IdentityProviderError> {
+        self.http_client
+            .get(url)
+            .bearer_auth(access_token)
+            .header(ACCEPT, "application/json")
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(unexpected_error)?
+            .json::<T>()
+            .await
+            .map_err(unexpected_error)
+    }
+}
+
+#[async_trait::async_trait]
+impl IdentityProvider for OAuth2IdentityProvider {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn authorization_url(
+        &self,
+        redirect_uri: &str,
+        state: &str,
+        code_challenge: &CodeChallenge,
+    ) -> String {
+        let mut url = self.authorization_endpoint.clone();
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("scope", &self.config.scopes.join(" "))
+            .append_pair("state", state)
+            .append_pair("code_challenge", code_challenge.as_ref())
+            .append_pair("code_challenge_method", "S256");
+
+        url.into()
+    }
+
+    async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        redirect_uri: &str,
+    ) -> Result<ExternalIdentity, IdentityProviderError> {
+        let access_token = self
+            .request_access_token(code, code_verifier, redirect_uri)
+            .await?;
+        let user_info: UserInfo = self
+            .get_json(&self.config.userinfo_endpoint, &access_token)
+            .await?;
+
+        let subject = match (user_info.sub, user_info.id) {
+            (Some(sub), _) => sub,
+            (None, Some(serde_json::Value::String(id))) => id,
+            (None, Some(id)) => id.to_string(),
+            (None, None) => {
+                return Err(IdentityProviderError::UnexpectedError(
+                    "Userinfo has no subject".to_string(),
+                ))
+            }
+        };
+
+        let (email, email_verified) = match &self.config.emails_endpoint {
+            Some(emails_endpoint) => {
+                let emails: Vec<ProviderEmail> =
+                    self.get_json(emails_endpoint, &access_token).await?;
+                let primary = emails
+                    .into_iter()
+                    .find(|email| email.primary)
+                    .ok_or(IdentityProviderError::MissingEmail)?;
+                (primary.email, primary.verified)
+            }
+            None => (
+                user_info.email.ok_or(IdentityProviderError::MissingEmail)?,
+                user_info.email_verified.unwrap_or(false),
+            ),
+        };
+
+        Ok(ExternalIdentity {
+            subject,
+            email: Email::parse(email).map_err(|_| IdentityProviderError::MissingEmail)?,
+            email_verified,
+        })
+    }
+}
+
+fn unexpected_error(e: reqwest::Error) -> IdentityProviderError {
+    IdentityProviderError::UnexpectedError(e.to_string())
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UserInfo {
+    /// OpenID Connect providers identify users by `sub`, others mostly by a
+    /// numeric `id`.
+    sub: Option<String>,
+    id: Option<serde_json::Value>,
+    email: Option<String>,
+    email_verified: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct ProviderEmail {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authorization_url() {
+        let provider = OAuth2IdentityProvider::new(OAuth2ProviderConfig::github(
+            "client-id".to_owned(),
+            "client-secret".to_owned(),
+        ))
+        .unwrap();
+        let code_challenge = CodeChallenge::from_verifier("verifier");
+
+        let url = Url::parse(&provider.authorization_url(
+            "http://localhost:3000/login/github/callback",
+            "some-state",
+            &code_challenge,
+        ))
+        .unwrap();
+        assert_eq!(url.host_str(), Some("github.com"));
+        let params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        assert!(params.contains(&("client_id".to_owned(), "client-id".to_owned())));
+        assert!(params.contains(&("scope".to_owned(), "read:user user:email".to_owned())));
+        assert!(params.contains(&("state".to_owned(), "some-state".to_owned())));
+        assert!(params.contains(&(
+            "code_challenge".to_owned(),
+            code_challenge.as_ref().to_owned()
+        )));
+        assert!(!params.iter().any(|(name, _)| name == "client_secret"));
+    }
+
+    #[test]
+    fn test_rejects_invalid_endpoint() {
+        let mut config = OAuth2ProviderConfig::google("id".to_owned(), "secret".to_owned());
+        config.authorization_endpoint = "not a url".to_owned();
+        assert!(OAuth2IdentityProvider::new(config).is_err());
+    }
+}
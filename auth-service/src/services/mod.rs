@@ -1,3 +1,9 @@
 pub mod data_stores;
+pub mod email_clients;
+pub mod email_outbox_worker;
+pub mod identity_providers;
 
 pub use data_stores::*;
+pub use email_clients::*;
+pub use email_outbox_worker::*;
+pub use identity_providers::*;
@@ -1,12 +1,136 @@
-use super::constants::{JWT_COOKIE_NAME, JWT_SECRET};
-use crate::{app_state::BannedTokenStoreType, domain::email::Email};
-use axum_extra::extract::cookie::{Cookie, SameSite};
-use chrono::Utc;
-use jsonwebtoken::{decode, encode, errors::Error, DecodingKey, EncodingKey, Validation};
-use serde::{Deserialize, Serialize};
-
-pub fn generate_auth_cookie(email: &Email) -> Result<Cookie<'static>, GenerateTokenError> {
-    let token = generate_auth_token(email)?;
+use super::{
+    constants::{
+        JWT_AUDIENCES, JWT_COOKIE_NAME, JWT_ISSUER, PUBLIC_BASE_URL, REFRESH_TOKEN_COOKIE_NAME,
+    },
+    keys::{KeyError, SigningKey, VerificationKey},
+};
+use crate::{
+    app_state::{
+        AppState, BannedTokenStoreType, RefreshTokenStoreType, SessionStoreType, UserStoreType,
+    },
+    domain::{
+        email::Email, AuthAPIError, RefreshToken, RefreshTokenRecord, ServiceClient, Session, User,
+    },
+};
+use axum::http::{header, HeaderMap};
+use axum_extra::extract::{
+    cookie::{Cookie, SameSite},
+    CookieJar,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{
+    decode, decode_header, encode,
+    errors::{Error, ErrorKind},
+    jwk::JwkSet,
+    Algorithm, Header, Validation,
+};
+use lazy_static::lazy_static;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::{RwLock, RwLockReadGuard},
+};
+use url::form_urlencoded;
+
+lazy_static! {
+    pub static ref KEY_RING: RwLock<KeyRing> =
+        RwLock::new(KeyRing::from_config().expect("Failed to load JWT signing keys."));
+}
+
+/// The key new tokens are signed with, plus every key that may still have
+/// signed a live token. Keys are looked up by the `kid` token header.
+pub struct KeyRing {
+    current: SigningKey,
+    configured: Vec<VerificationKey>,
+    retired: Vec<(VerificationKey, DateTime<Utc>)>,
+}
+
+impl KeyRing {
+    pub fn new(current: SigningKey, configured: Vec<VerificationKey>) -> Self {
+        Self {
+            current,
+            configured,
+            retired: Vec::new(),
+        }
+    }
+
+    pub fn from_config() -> Result<Self, KeyError> {
+        dotenvy::dotenv().ok();
+        Ok(Self::new(
+            SigningKey::from_config()?,
+            VerificationKey::list_from_config()?,
+        ))
+    }
+
+    pub fn current(&self) -> &SigningKey {
+        &self.current
+    }
+
+    pub fn find(&self, kid: &str) -> Option<&VerificationKey> {
+        std::iter::once(self.current.verification_key())
+            .chain(self.configured.iter())
+            .chain(self.active_retired())
+            .find(|key| key.kid == kid)
+    }
+
+    /// Swaps in a new signing key. If its `kid` changed, the old key keeps
+    /// verifying until every token it signed has expired.
+    pub fn rotate(&mut self, current: SigningKey, configured: Vec<VerificationKey>) {
+        let previous = std::mem::replace(&mut self.current, current);
+        if previous.kid != self.current.kid {
+            self.retired
+                .push((previous.verification_key().clone(), Utc::now()));
+        }
+        self.configured = configured;
+
+        let kid = self.current.kid.clone();
+        self.retired
+            .retain(|(key, retired_at)| key.kid != kid && !is_expired(retired_at));
+    }
+
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: std::iter::once(self.current.verification_key())
+                .chain(self.configured.iter())
+                .chain(self.active_retired())
+                .filter_map(|key| key.jwk().cloned())
+                .collect(),
+        }
+    }
+
+    fn active_retired(&self) -> impl Iterator<Item = &VerificationKey> {
+        self.retired
+            .iter()
+            .filter(|(_, retired_at)| !is_expired(retired_at))
+            .map(|(key, _)| key)
+    }
+}
+
+fn is_expired(retired_at: &DateTime<Utc>) -> bool {
+    Utc::now().signed_duration_since(retired_at).num_seconds() > TOKEN_TTL_SECONDS
+}
+
+/// Re-reads `.env` and the key files, e.g. after a SIGHUP.
+pub fn reload_key_ring() -> Result<(), KeyError> {
+    dotenvy::dotenv_override().ok();
+    let current = SigningKey::from_config()?;
+    let configured = VerificationKey::list_from_config()?;
+
+    KEY_RING
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .rotate(current, configured);
+
+    Ok(())
+}
+
+fn key_ring() -> RwLockReadGuard<'static, KeyRing> {
+    KEY_RING.read().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn generate_auth_cookie(user: &User) -> Result<Cookie<'static>, GenerateTokenError> {
+    let token = generate_auth_token(user)?;
     Ok(create_auth_cookie(token))
 }
 
@@ -20,6 +144,107 @@ pub fn create_auth_cookie(token: String) -> Cookie<'static> {
     cookie
 }
 
+/// Issues a new refresh token and stores it under `family_id`, or under a new
+/// family when this is the first token of a session.
+pub async fn generate_refresh_cookie(
+    email: &Email,
+    family_id: Option<String>,
+    refresh_token_store: RefreshTokenStoreType,
+) -> Result<Cookie<'static>, GenerateTokenError> {
+    let token = RefreshToken::default();
+    let family_id = family_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    refresh_token_store
+        .write()
+        .await
+        .add_token(token.clone(), RefreshTokenRecord::new(email, family_id))
+        .await
+        .map_err(|_| GenerateTokenError::UnexpectedError)?;
+
+    Ok(create_refresh_cookie(token.as_ref().to_owned()))
+}
+
+pub fn create_refresh_cookie(token: String) -> Cookie<'static> {
+    let cookie = Cookie::build((REFRESH_TOKEN_COOKIE_NAME, token))
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .max_age(time::Duration::seconds(REFRESH_TOKEN_TTL_SECONDS))
+        .build();
+
+    cookie
+}
+
+/// Where a request came from, recorded with the session it starts.
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip: IpAddr,
+}
+
+impl ClientInfo {
+    pub fn new(headers: &HeaderMap, addr: SocketAddr) -> Self {
+        Self {
+            user_agent: headers
+                .get(header::USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned),
+            ip: addr.ip(),
+        }
+    }
+}
+
+/// Issues an access and a refresh token and records the access token as a
+/// session. Passing the `family_id` of a refreshed token continues that
+/// family's session, which keeps its creation time but moves to the new `jti`.
+pub async fn generate_session_cookies(
+    user: &User,
+    family_id: Option<String>,
+    client: &ClientInfo,
+    refresh_token_store: RefreshTokenStoreType,
+    session_store: SessionStoreType,
+) -> Result<(Cookie<'static>, Cookie<'static>), GenerateTokenError> {
+    let mut session_store = session_store.write().await;
+
+    let previous = match &family_id {
+        Some(family_id) => session_store
+            .get_sessions(&user.email)
+            .await
+            .map_err(|_| GenerateTokenError::UnexpectedError)?
+            .into_iter()
+            .find(|session| &session.family_id == family_id),
+        None => None,
+    };
+    let family_id = family_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let claims = generate_auth_claims(user)?;
+    let token = create_token(&claims).map_err(GenerateTokenError::TokenError)?;
+    let refresh_cookie =
+        generate_refresh_cookie(&user.email, Some(family_id.clone()), refresh_token_store).await?;
+
+    let now = Utc::now();
+    if let Some(previous) = &previous {
+        session_store
+            .remove_session(&previous.id)
+            .await
+            .map_err(|_| GenerateTokenError::UnexpectedError)?;
+    }
+    session_store
+        .add_session(Session {
+            id: claims.jti,
+            email: user.email.clone(),
+            family_id,
+            user_agent: client.user_agent.clone(),
+            ip: client.ip,
+            created_at: previous.map_or(now, |session| session.created_at),
+            last_seen_at: now,
+        })
+        .await
+        .map_err(|_| GenerateTokenError::UnexpectedError)?;
+
+    Ok((create_auth_cookie(token), refresh_cookie))
+}
+
 #[derive(Debug)]
 pub enum GenerateTokenError {
     TokenError(Error),
@@ -27,12 +252,104 @@ pub enum GenerateTokenError {
 }
 
 pub const TOKEN_TTL_SECONDS: i64 = 600; // 10 minutes
+pub const REFRESH_TOKEN_TTL_SECONDS: i64 = 60 * 60 * 24 * 30; // 30 days
+pub const SERVICE_TOKEN_TTL_SECONDS: i64 = 300; // 5 minutes
+
+fn generate_auth_token(user: &User) -> Result<String, GenerateTokenError> {
+    let claims = generate_auth_claims(user)?;
+
+    create_token(&claims).map_err(GenerateTokenError::TokenError)
+}
+
+/// Issues an access token to an OAuth2 client acting on the user's behalf.
+/// The token is meant for the client only, not our downstream services.
+pub fn generate_client_access_token(
+    user: &User,
+    client_id: &str,
+    scopes: &[String],
+) -> Result<String, GenerateTokenError> {
+    let claims = Claims {
+        aud: vec![client_id.to_owned()],
+        scope: Some(scopes.join(" ")),
+        ..generate_auth_claims(user)?
+    };
+
+    create_token(&claims).map_err(GenerateTokenError::TokenError)
+}
+
+/// Issues an OpenID Connect ID token, telling the client who logged in. Its
+/// issuer is `PUBLIC_BASE_URL`, where the discovery document is served.
+pub fn generate_id_token(
+    user: &User,
+    client_id: &str,
+    nonce: Option<String>,
+    scopes: &[String],
+) -> Result<String, GenerateTokenError> {
+    let claims = generate_auth_claims(user)?;
+    let with_email = scopes.iter().any(|scope| scope == "email");
+
+    let id_token_claims = IdTokenClaims {
+        iss: PUBLIC_BASE_URL.clone(),
+        sub: claims.sub,
+        aud: client_id.to_owned(),
+        exp: claims.exp,
+        iat: claims.iat,
+        nonce,
+        email: with_email.then(|| user.email.as_ref().to_owned()),
+        email_verified: with_email.then_some(user.email_verified),
+    };
+
+    create_token(&id_token_claims).map_err(GenerateTokenError::TokenError)
+}
+
+/// Issues an access token to a service client through the client credentials
+/// grant. The token is meant for us, so its audience is our own issuer.
+pub fn generate_service_token(
+    client: &ServiceClient,
+    scopes: &[String],
+) -> Result<String, GenerateTokenError> {
+    let (iat, exp) = token_lifetime(SERVICE_TOKEN_TTL_SECONDS)?;
+
+    let claims = ServiceClaims {
+        sub: client.client_id.clone(),
+        iss: JWT_ISSUER.to_owned(),
+        aud: vec![JWT_ISSUER.to_owned()],
+        exp,
+        iat,
+        nbf: iat,
+        jti: uuid::Uuid::new_v4().to_string(),
+        scope: scopes.join(" "),
+    };
 
-fn generate_auth_token(email: &Email) -> Result<String, GenerateTokenError> {
-    let delta = chrono::Duration::try_seconds(TOKEN_TTL_SECONDS)
-        .ok_or(GenerateTokenError::UnexpectedError)?;
+    create_token(&claims).map_err(GenerateTokenError::TokenError)
+}
+
+fn generate_auth_claims(user: &User) -> Result<Claims, GenerateTokenError> {
+    let (iat, exp) = token_lifetime(TOKEN_TTL_SECONDS)?;
+
+    let claims = Claims {
+        sub: user.id.to_string(),
+        email: user.email.as_ref().to_owned(),
+        iss: JWT_ISSUER.to_owned(),
+        aud: JWT_AUDIENCES.clone(),
+        exp,
+        iat,
+        nbf: iat,
+        jti: uuid::Uuid::new_v4().to_string(),
+        ver: user.token_version,
+        scope: None,
+    };
 
-    let exp = Utc::now()
+    Ok(claims)
+}
+
+/// `iat` and `exp` of a token issued now.
+fn token_lifetime(ttl_seconds: i64) -> Result<(usize, usize), GenerateTokenError> {
+    let delta =
+        chrono::Duration::try_seconds(ttl_seconds).ok_or(GenerateTokenError::UnexpectedError)?;
+
+    let now = Utc::now();
+    let exp = now
         .checked_add_signed(delta)
         .ok_or(GenerateTokenError::UnexpectedError)?
         .timestamp();
@@ -40,63 +357,365 @@ fn generate_auth_token(email: &Email) -> Result<String, GenerateTokenError> {
     let exp: usize = exp
         .try_into()
         .map_err(|_| GenerateTokenError::UnexpectedError)?;
+    let iat: usize = now
+        .timestamp()
+        .try_into()
+        .map_err(|_| GenerateTokenError::UnexpectedError)?;
 
-    let sub = email.as_ref().to_owned();
+    Ok((iat, exp))
+}
 
-    let claims = Claims { sub, exp };
+/// Validates the access token in the JWT cookie of a request.
+pub async fn authenticated_claims(
+    state: &AppState,
+    jar: &CookieJar,
+) -> Result<Claims, AuthAPIError> {
+    let token = jar
+        .get(JWT_COOKIE_NAME)
+        .ok_or(AuthAPIError::MissingToken)?
+        .value()
+        .to_owned();
 
-    create_token(&claims).map_err(GenerateTokenError::TokenError)
+    validate_token(
+        &token,
+        state.banned_token_store.clone(),
+        state.user_store.clone(),
+    )
+    .await
+    .map_err(|_| AuthAPIError::InvalidToken)
 }
 
+/// The email of the user the request's access token was issued to.
+pub async fn authenticated_email(state: &AppState, jar: &CookieJar) -> Result<Email, AuthAPIError> {
+    let claims = authenticated_claims(state, jar).await?;
+
+    Email::parse(claims.email).map_err(|_| AuthAPIError::InvalidToken)
+}
+
+/// The user the request's access token was issued to.
+pub async fn authenticated_user(state: &AppState, jar: &CookieJar) -> Result<User, AuthAPIError> {
+    let email = authenticated_email(state, jar).await?;
+
+    state
+        .user_store
+        .read()
+        .await
+        .get_user(&email)
+        .await
+        .map_err(|_| AuthAPIError::InvalidToken)
+}
+
+/// Validates a token issued for any of our configured audiences.
 pub async fn validate_token(
     token: &str,
     banned_token_store: BannedTokenStoreType,
+    user_store: UserStoreType,
 ) -> Result<Claims, jsonwebtoken::errors::Error> {
-    println!("{token}");
+    validate_token_for_audiences(token, &JWT_AUDIENCES, banned_token_store, user_store).await
+}
+
+/// Validates a token and checks that it was issued for at least one of
+/// `audiences`.
+pub async fn validate_token_for_audiences(
+    token: &str,
+    audiences: &[String],
+    banned_token_store: BannedTokenStoreType,
+    user_store: UserStoreType,
+) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let claims: Claims = decode_token(token, Some(audiences))?;
+
+    check_token_revoked(claims, banned_token_store, user_store).await
+}
+
+/// Validates an access token issued to any OAuth2 client, e.g. when a client
+/// presents it to `/userinfo`. Our own access tokens are not accepted.
+pub async fn validate_client_access_token(
+    token: &str,
+    banned_token_store: BannedTokenStoreType,
+    user_store: UserStoreType,
+) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let claims: Claims = decode_token(token, None)?;
+    if claims.scope.is_none() {
+        return Err(Error::from(ErrorKind::InvalidToken));
+    }
+
+    check_token_revoked(claims, banned_token_store, user_store).await
+}
+
+/// Validates an access token issued to a service client.
+pub async fn validate_service_token(
+    token: &str,
+    banned_token_store: BannedTokenStoreType,
+) -> Result<ServiceClaims, jsonwebtoken::errors::Error> {
+    let claims: ServiceClaims = decode_token(token, Some(&[JWT_ISSUER.to_owned()]))?;
+    check_token_banned(&claims.jti, banned_token_store).await?;
+
+    Ok(claims)
+}
+
+/// Validates any access token we issued, whoever it was issued to.
+pub async fn validate_access_token(
+    token: &str,
+    banned_token_store: BannedTokenStoreType,
+    user_store: UserStoreType,
+) -> Result<AccessTokenClaims, jsonwebtoken::errors::Error> {
+    match decode_access_token(token)? {
+        AccessTokenClaims::User(claims) => {
+            check_token_revoked(claims, banned_token_store, user_store)
+                .await
+                .map(AccessTokenClaims::User)
+        }
+        AccessTokenClaims::Service(claims) => {
+            check_token_banned(&claims.jti, banned_token_store).await?;
+            Ok(AccessTokenClaims::Service(claims))
+        }
+    }
+}
+
+/// Decodes any access token we issued, without checking whether it was
+/// revoked.
+pub fn decode_access_token(token: &str) -> Result<AccessTokenClaims, Error> {
+    match decode_token::<Claims>(token, None) {
+        Ok(claims) => Ok(AccessTokenClaims::User(claims)),
+        Err(_) => decode_token::<ServiceClaims>(token, Some(&[JWT_ISSUER.to_owned()]))
+            .map(AccessTokenClaims::Service),
+    }
+}
+
+async fn check_token_revoked(
+    claims: Claims,
+    banned_token_store: BannedTokenStoreType,
+    user_store: UserStoreType,
+) -> Result<Claims, jsonwebtoken::errors::Error> {
+    check_token_banned(&claims.jti, banned_token_store).await?;
+
+    // Tokens of deleted users are rejected, and so are those of an earlier
+    // account under the same email, which had another id. Tokens issued
+    // before the user's last logout everywhere are rejected as well.
+    let email = Email::parse(claims.email.clone()).map_err(|_| ErrorKind::InvalidToken)?;
+    let user = user_store
+        .read()
+        .await
+        .get_user(&email)
+        .await
+        .map_err(|_| ErrorKind::InvalidToken)?;
+    if claims.sub != user.id.to_string() || claims.ver < user.token_version {
+        return Err(Error::from(ErrorKind::InvalidToken));
+    }
+
+    Ok(claims)
+}
+
+async fn check_token_banned(
+    jti: &str,
+    banned_token_store: BannedTokenStoreType,
+) -> Result<(), jsonwebtoken::errors::Error> {
     if banned_token_store
         .read()
         .await
-        .contains_token(token.to_string())
+        .contains_token(jti.to_owned())
         .await
-        .map_err(|_| jsonwebtoken::errors::ErrorKind::InvalidToken)?
+        .map_err(|_| ErrorKind::InvalidToken)?
     {
-        return Err(jsonwebtoken::errors::Error::from(
-            jsonwebtoken::errors::ErrorKind::InvalidToken,
-        ));
+        return Err(Error::from(ErrorKind::InvalidToken));
     }
-    decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(JWT_SECRET.as_bytes()),
-        &Validation::default(),
-    )
-    .map(|data| data.claims)
+
+    Ok(())
 }
 
-fn create_token(claims: &Claims) -> Result<String, Error> {
-    encode(
-        &jsonwebtoken::Header::default(),
-        &claims,
-        &EncodingKey::from_secret(JWT_SECRET.as_bytes()),
-    )
+/// Tokens are decoded for any audience if `audiences` is `None`.
+fn decode_token<T: DeserializeOwned>(
+    token: &str,
+    audiences: Option<&[String]>,
+) -> Result<T, Error> {
+    let header = decode_header(token)?;
+    let key_ring = key_ring();
+    let key = match header.kid {
+        Some(kid) => key_ring
+            .find(&kid)
+            .ok_or(Error::from(ErrorKind::InvalidSignature))?,
+        None => key_ring.current().verification_key(),
+    };
+
+    let mut validation = Validation::new(key.algorithm);
+    validation.set_issuer(&[JWT_ISSUER.as_str()]);
+    match audiences {
+        Some(audiences) => validation.set_audience(audiences),
+        None => validation.validate_aud = false,
+    }
+    validation.set_required_spec_claims(&["exp", "nbf", "sub", "iss", "aud"]);
+    validation.validate_nbf = true;
+
+    decode::<T>(token, key.decoding_key(), &validation).map(|data| data.claims)
+}
+
+fn create_token<T: Serialize>(claims: &T) -> Result<String, Error> {
+    let key_ring = key_ring();
+    let key = key_ring.current();
+    let mut header = Header::new(key.algorithm);
+    header.kid = Some(key.kid.clone());
+
+    encode(&header, &claims, key.encoding_key())
+}
+
+/// The token of an `Authorization: Bearer` header.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+/// The client id and secret of an `Authorization: Basic` header. Both are
+/// form-urlencoded before being joined, as required by RFC 6749.
+pub fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let credentials = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Basic "))
+        .and_then(|value| STANDARD.decode(value).ok())
+        .and_then(|value| String::from_utf8(value).ok())?;
+    let (client_id, secret) = credentials.split_once(':')?;
+
+    let decode = |value: &str| {
+        form_urlencoded::parse(value.as_bytes())
+            .next()
+            .map_or(String::new(), |(value, _)| value.into_owned())
+    };
+    Some((decode(client_id), decode(secret)))
+}
+
+/// The algorithm new tokens are signed with.
+pub fn signing_algorithm() -> Algorithm {
+    key_ring().current().algorithm
+}
+
+/// Public keys consumers can use to verify our tokens without calling
+/// `/verify-token`. Empty when tokens are signed with a shared secret.
+pub fn get_jwks() -> JwkSet {
+    key_ring().jwks()
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
+    /// The user's id. Unlike the email it never changes.
     pub sub: String,
+    pub email: String,
+    pub iss: String,
+    pub aud: Vec<String>,
     pub exp: usize,
+    pub iat: usize,
+    pub nbf: usize,
+    /// Unique per token, used to ban a token before it expires.
+    pub jti: String,
+    /// The user's token version at the time the token was issued. Missing in
+    /// tokens issued before versions were introduced.
+    #[serde(default)]
+    pub ver: i32,
+    /// Space-delimited scopes of tokens issued to OAuth2 clients.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+/// Claims of any of our access tokens, told apart by whether they were issued
+/// for a user or a service client.
+#[derive(Debug)]
+pub enum AccessTokenClaims {
+    User(Claims),
+    Service(ServiceClaims),
+}
+
+impl AccessTokenClaims {
+    pub fn jti(&self) -> &str {
+        match self {
+            AccessTokenClaims::User(claims) => &claims.jti,
+            AccessTokenClaims::Service(claims) => &claims.jti,
+        }
+    }
+}
+
+/// Claims of a service client's access token. There is no user, so they
+/// don't pass for `Claims`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServiceClaims {
+    /// The client id.
+    pub sub: String,
+    pub iss: String,
+    pub aud: Vec<String>,
+    pub exp: usize,
+    pub iat: usize,
+    pub nbf: usize,
+    pub jti: String,
+    /// Space-delimited scopes.
+    pub scope: String,
+}
+
+impl ServiceClaims {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scope
+            .split_whitespace()
+            .any(|granted| granted == scope)
+    }
+}
+
+/// Claims of an OpenID Connect ID token. The email claims are only included
+/// if the client was granted the `email` scope.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    /// The client id.
+    pub aud: String,
+    pub exp: usize,
+    pub iat: usize,
+    /// Echoed from the authorization request, so the client can tie the token
+    /// to it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::services::hashset_banned_token_store::HashsetBannedTokenStore;
+    use crate::domain::RefreshTokenStore;
+    use crate::domain::{BannedTokenStore, Password, VERIFY_TOKEN_SCOPE};
+    use crate::services::{
+        hashmap_refresh_token_store::HashmapRefreshTokenStore,
+        hashmap_user_store::HashmapUserStore, hashset_banned_token_store::HashsetBannedTokenStore,
+    };
     use std::sync::Arc;
     use tokio::sync::RwLock;
 
+    fn user() -> User {
+        User::new(
+            Email::parse("test@example.com".to_owned()).unwrap(),
+            Password::parse("password123".to_owned()).unwrap(),
+            false,
+        )
+    }
+
+    fn user_store() -> UserStoreType {
+        Arc::new(RwLock::new(HashmapUserStore::new()))
+    }
+
+    async fn user_store_with(user: &User) -> UserStoreType {
+        let user_store = user_store();
+        user_store
+            .write()
+            .await
+            .add_user(user.clone())
+            .await
+            .unwrap();
+        user_store
+    }
+
     #[tokio::test]
     async fn test_generate_auth_cookie() {
-        let email = Email::parse("test@example.com".to_owned()).unwrap();
-        let cookie = generate_auth_cookie(&email).unwrap();
+        let cookie = generate_auth_cookie(&user()).unwrap();
         assert_eq!(cookie.name(), JWT_COOKIE_NAME);
         assert_eq!(cookie.value().split('.').count(), 3);
         assert_eq!(cookie.path(), Some("/"));
@@ -116,19 +735,51 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_generate_auth_token() {
+    async fn test_generate_refresh_cookie() {
+        let refresh_token_store = Arc::new(RwLock::new(HashmapRefreshTokenStore::new()));
         let email = Email::parse("test@example.com".to_owned()).unwrap();
-        let result = generate_auth_token(&email).unwrap();
+        let cookie = generate_refresh_cookie(
+            &email,
+            Some("family".to_owned()),
+            refresh_token_store.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(cookie.name(), REFRESH_TOKEN_COOKIE_NAME);
+        assert_eq!(cookie.http_only(), Some(true));
+        assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+
+        let token = RefreshToken::parse(cookie.value().to_owned()).unwrap();
+        let record = refresh_token_store
+            .read()
+            .await
+            .get_token(&token)
+            .await
+            .unwrap();
+        assert_eq!(record.email, email);
+        assert_eq!(record.family_id, "family");
+        assert!(!record.used);
+    }
+
+    #[tokio::test]
+    async fn test_generate_auth_token() {
+        let result = generate_auth_token(&user()).unwrap();
         assert_eq!(result.split('.').count(), 3);
     }
 
     #[tokio::test]
     async fn test_validate_token_with_valid_token() {
         let banned_token_store = Arc::new(RwLock::new(HashsetBannedTokenStore::new()));
-        let email = Email::parse("test@example.com".to_owned()).unwrap();
-        let token = generate_auth_token(&email).unwrap();
-        let result = validate_token(&token, banned_token_store).await.unwrap();
-        assert_eq!(result.sub, "test@example.com");
+        let user = user();
+        let token = generate_auth_token(&user).unwrap();
+        let result = validate_token(&token, banned_token_store, user_store_with(&user).await)
+            .await
+            .unwrap();
+        assert_eq!(result.sub, user.id.to_string());
+        assert_eq!(result.email, "test@example.com");
+        assert_eq!(result.iss, *JWT_ISSUER);
+        assert_eq!(result.aud, *JWT_AUDIENCES);
+        assert!(result.nbf <= result.iat);
 
         let exp = Utc::now()
             .checked_add_signed(chrono::Duration::try_minutes(9).expect("valid duration"))
@@ -138,11 +789,234 @@ mod tests {
         assert!(result.exp > exp as usize);
     }
 
+    #[tokio::test]
+    async fn test_validate_token_with_banned_jti() {
+        let banned_token_store = Arc::new(RwLock::new(HashsetBannedTokenStore::new()));
+        let user = user();
+        let user_store = user_store_with(&user).await;
+        let token = generate_auth_token(&user).unwrap();
+        let claims = validate_token(&token, banned_token_store.clone(), user_store.clone())
+            .await
+            .unwrap();
+
+        banned_token_store
+            .write()
+            .await
+            .add_token(claims.jti)
+            .await
+            .unwrap();
+
+        assert!(validate_token(&token, banned_token_store, user_store)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_with_outdated_version() {
+        let banned_token_store = Arc::new(RwLock::new(HashsetBannedTokenStore::new()));
+        let user = user();
+        let user_store = user_store_with(&user).await;
+
+        let token = generate_auth_token(&user).unwrap();
+        assert!(
+            validate_token(&token, banned_token_store.clone(), user_store.clone())
+                .await
+                .is_ok()
+        );
+
+        user_store
+            .write()
+            .await
+            .increment_token_version(&user.email)
+            .await
+            .unwrap();
+
+        assert!(validate_token(&token, banned_token_store, user_store)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_of_deleted_user() {
+        let banned_token_store = Arc::new(RwLock::new(HashsetBannedTokenStore::new()));
+        let user = user();
+        let user_store = user_store_with(&user).await;
+        let token = generate_auth_token(&user).unwrap();
+
+        user_store
+            .write()
+            .await
+            .delete_user(&user.email)
+            .await
+            .unwrap();
+        assert!(
+            validate_token(&token, banned_token_store.clone(), user_store.clone())
+                .await
+                .is_err()
+        );
+
+        // A new account under the same email starts at the same version, but
+        // has another id.
+        let new_user = User::new(user.email.clone(), user.password.clone(), false);
+        user_store.write().await.add_user(new_user).await.unwrap();
+        assert!(validate_token(&token, banned_token_store, user_store)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_with_wrong_audience() {
+        let banned_token_store = Arc::new(RwLock::new(HashsetBannedTokenStore::new()));
+        let token = generate_auth_token(&user()).unwrap();
+        let result = validate_token_for_audiences(
+            &token,
+            &["another-service".to_owned()],
+            banned_token_store,
+            user_store(),
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_client_access_token() {
+        let banned_token_store = Arc::new(RwLock::new(HashsetBannedTokenStore::new()));
+        let user = user();
+
+        let user_store = user_store_with(&user).await;
+
+        let token = generate_client_access_token(&user, "client", &["openid".to_owned()]).unwrap();
+        let claims =
+            validate_client_access_token(&token, banned_token_store.clone(), user_store.clone())
+                .await
+                .unwrap();
+        assert_eq!(claims.aud, vec!["client".to_owned()]);
+        assert_eq!(claims.scope.as_deref(), Some("openid"));
+
+        // Our own tokens don't carry scopes.
+        let token = generate_auth_token(&user).unwrap();
+        assert!(
+            validate_client_access_token(&token, banned_token_store, user_store)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_service_token() {
+        let banned_token_store = Arc::new(RwLock::new(HashsetBannedTokenStore::new()));
+        let client = ServiceClient::new(
+            "app-service".to_owned(),
+            vec![VERIFY_TOKEN_SCOPE.to_owned()],
+        )
+        .unwrap();
+
+        let token = generate_service_token(&client, &client.scopes).unwrap();
+        let claims = validate_service_token(&token, banned_token_store.clone())
+            .await
+            .unwrap();
+        assert_eq!(claims.sub, client.client_id);
+        assert!(claims.has_scope(VERIFY_TOKEN_SCOPE));
+
+        // Service tokens aren't user tokens, and the other way around.
+        assert!(
+            validate_token(&token, banned_token_store.clone(), user_store())
+                .await
+                .is_err()
+        );
+        let token = generate_auth_token(&user()).unwrap();
+        assert!(validate_service_token(&token, banned_token_store)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_basic_credentials() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            format!("Basic {}", STANDARD.encode("client%3A1:s3cret"))
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            basic_credentials(&headers),
+            Some(("client:1".to_owned(), "s3cret".to_owned()))
+        );
+
+        headers.insert(header::AUTHORIZATION, "Bearer token".parse().unwrap());
+        assert_eq!(basic_credentials(&headers), None);
+    }
+
     #[tokio::test]
     async fn test_validate_token_with_invalid_token() {
         let banned_token_store = Arc::new(RwLock::new(HashsetBannedTokenStore::new()));
         let token = "invalid_token".to_owned();
-        let result = validate_token(&token, banned_token_store).await;
+        let result = validate_token(&token, banned_token_store, user_store()).await;
         assert!(result.is_err());
     }
+
+    fn pem_key(kid: &str) -> SigningKey {
+        SigningKey::from_pem(
+            kid,
+            jsonwebtoken::Algorithm::ES256,
+            include_bytes!("../../tests/fixtures/keys/es256_private.pem"),
+            include_bytes!("../../tests/fixtures/keys/es256_public.pem"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_key_ring_keeps_retired_key_for_verification() {
+        let mut key_ring = KeyRing::new(SigningKey::from_secret("old", b"old-secret"), vec![]);
+
+        key_ring.rotate(SigningKey::from_secret("new", b"new-secret"), vec![]);
+
+        assert_eq!(key_ring.current().kid, "new");
+        assert!(key_ring.find("new").is_some());
+        assert!(key_ring.find("old").is_some());
+        assert!(key_ring.find("unknown").is_none());
+    }
+
+    #[test]
+    fn test_key_ring_drops_retired_key_after_token_ttl() {
+        let mut key_ring = KeyRing::new(SigningKey::from_secret("new", b"new-secret"), vec![]);
+        let retired_at = Utc::now() - chrono::Duration::seconds(TOKEN_TTL_SECONDS + 1);
+        key_ring.retired.push((
+            SigningKey::from_secret("old", b"old-secret")
+                .verification_key()
+                .clone(),
+            retired_at,
+        ));
+
+        assert!(key_ring.find("old").is_none());
+
+        key_ring.rotate(SigningKey::from_secret("new", b"new-secret"), vec![]);
+        assert!(key_ring.retired.is_empty());
+    }
+
+    #[test]
+    fn test_key_ring_rotation_without_new_kid_does_not_retire() {
+        let mut key_ring = KeyRing::new(SigningKey::from_secret("key", b"old-secret"), vec![]);
+
+        key_ring.rotate(SigningKey::from_secret("key", b"new-secret"), vec![]);
+
+        assert!(key_ring.retired.is_empty());
+    }
+
+    #[test]
+    fn test_key_ring_publishes_current_configured_and_retired_keys() {
+        let mut key_ring = KeyRing::new(pem_key("first"), vec![]);
+        let configured = pem_key("configured").verification_key().clone();
+
+        key_ring.rotate(pem_key("second"), vec![configured]);
+
+        let kids: Vec<_> = key_ring
+            .jwks()
+            .keys
+            .into_iter()
+            .filter_map(|jwk| jwk.common.key_id)
+            .collect();
+        assert_eq!(kids, ["second", "configured", "first"]);
+    }
 }
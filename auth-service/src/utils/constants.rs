@@ -3,18 +3,37 @@ use lazy_static::lazy_static;
 use std::env as std_env;
 
 lazy_static! {
-    pub static ref JWT_SECRET: String = set_token();
     pub static ref DATABASE_URL: String = set_db_url();
     pub static ref REDIS_HOST_NAME: String = set_redis_host_name();
-}
-
-fn set_token() -> String {
-    dotenv().ok();
-    let secret = std_env::var(env::JWT_SECRET_ENV_VAR).expect("JWT_SECRET must be set.");
-    if secret.is_empty() {
-        panic!("JWT_SECRET must not be empty.");
-    }
-    secret
+    pub static ref JWT_ISSUER: String = set_jwt_issuer();
+    pub static ref JWT_AUDIENCES: Vec<String> = set_jwt_audiences();
+    pub static ref PUBLIC_BASE_URL: String = set_public_base_url();
+    pub static ref TOTP_ENCRYPTION_KEY: String = set_totp_encryption_key();
+    pub static ref WEBAUTHN_RP_ID: String = set_webauthn_rp_id();
+    pub static ref ADMIN_API_KEY: Option<String> = set_admin_api_key();
+    pub static ref VERIFY_TOKEN_REQUIRES_CLIENT: bool = set_verify_token_requires_client();
+    pub static ref GOOGLE_CLIENT_CREDENTIALS: Option<(String, String)> = set_client_credentials(
+        env::GOOGLE_CLIENT_ID_ENV_VAR,
+        env::GOOGLE_CLIENT_SECRET_ENV_VAR
+    );
+    pub static ref GITHUB_CLIENT_CREDENTIALS: Option<(String, String)> = set_client_credentials(
+        env::GITHUB_CLIENT_ID_ENV_VAR,
+        env::GITHUB_CLIENT_SECRET_ENV_VAR
+    );
+    pub static ref EMAIL_PROVIDER: String = set_email_provider();
+    pub static ref SMTP_HOST: Option<String> = set_smtp_host();
+    pub static ref SMTP_PORT: Option<u16> = set_smtp_port();
+    pub static ref SMTP_TLS: String = set_smtp_tls();
+    pub static ref SMTP_CREDENTIALS: Option<(String, String)> =
+        set_client_credentials(env::SMTP_USERNAME_ENV_VAR, env::SMTP_PASSWORD_ENV_VAR);
+    pub static ref EMAIL_SENDER: String = set_email_sender();
+    pub static ref EMAIL_API_URL: String = set_email_api_url();
+    pub static ref EMAIL_API_TOKEN: Option<String> = set_email_api_token();
+    pub static ref BRAND_NAME: String = set_brand_name();
+    pub static ref BRAND_URL: String = set_brand_url();
+    pub static ref BRAND_LOGO_URL: Option<String> = set_brand_logo_url();
+    pub static ref BRAND_SUPPORT_EMAIL: Option<String> = set_brand_support_email();
+    pub static ref BRAND_COLOR: String = set_brand_color();
 }
 
 fn set_db_url() -> String {
@@ -27,10 +46,203 @@ fn set_redis_host_name() -> String {
     std_env::var(env::REDIS_HOST_NAME_ENV_VAR).unwrap_or(DEFAULT_REDIS_HOSTNAME.to_owned())
 }
 
+fn set_jwt_issuer() -> String {
+    dotenv().ok();
+    std_env::var(env::JWT_ISSUER_ENV_VAR).unwrap_or(DEFAULT_JWT_ISSUER.to_owned())
+}
+
+/// Comma separated list of the downstream services our tokens are meant for.
+fn set_jwt_audiences() -> Vec<String> {
+    dotenv().ok();
+    std_env::var(env::JWT_AUDIENCES_ENV_VAR)
+        .unwrap_or(DEFAULT_JWT_AUDIENCE.to_owned())
+        .split(',')
+        .map(str::trim)
+        .filter(|audience| !audience.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Where users reach the service, used to build links we send out by email.
+fn set_public_base_url() -> String {
+    dotenv().ok();
+    std_env::var(env::PUBLIC_BASE_URL_ENV_VAR)
+        .unwrap_or(DEFAULT_PUBLIC_BASE_URL.to_owned())
+        .trim_end_matches('/')
+        .to_owned()
+}
+
+/// Compose passes unset variables on as empty strings, which count as missing.
+fn set_totp_encryption_key() -> String {
+    dotenv().ok();
+    std_env::var(env::TOTP_ENCRYPTION_KEY_ENV_VAR)
+        .ok()
+        .filter(|key| !key.is_empty())
+        .expect("TOTP_ENCRYPTION_KEY must be set. Generate one with `openssl rand -base64 32`.")
+}
+
+/// The domain passkeys are scoped to. It must be the host of
+/// `PUBLIC_BASE_URL` or a parent domain of it.
+fn set_webauthn_rp_id() -> String {
+    dotenv().ok();
+    std_env::var(env::WEBAUTHN_RP_ID_ENV_VAR).unwrap_or(DEFAULT_WEBAUTHN_RP_ID.to_owned())
+}
+
+/// Bearer token for the `/admin` routes, which are disabled while it is unset.
+fn set_admin_api_key() -> Option<String> {
+    dotenv().ok();
+    std_env::var(env::ADMIN_API_KEY_ENV_VAR)
+        .ok()
+        .filter(|key| !key.is_empty())
+}
+
+/// Whether `/verify-token` rejects callers that don't present a service
+/// client's access token.
+fn set_verify_token_requires_client() -> bool {
+    dotenv().ok();
+    std_env::var(env::VERIFY_TOKEN_REQUIRE_CLIENT_ENV_VAR)
+        .is_ok_and(|value| matches!(value.trim(), "1" | "true"))
+}
+
+/// A username and secret we authenticate with elsewhere, like our client id
+/// and secret at an identity provider. Unused unless both are set.
+fn set_client_credentials(id_env_var: &str, secret_env_var: &str) -> Option<(String, String)> {
+    dotenv().ok();
+    let client_id = std_env::var(id_env_var).ok().filter(|id| !id.is_empty())?;
+    let client_secret = std_env::var(secret_env_var)
+        .ok()
+        .filter(|secret| !secret.is_empty())?;
+    Some((client_id, client_secret))
+}
+
+/// `smtp`, `http` for a transactional email API, or `mock`, which only logs
+/// the emails.
+fn set_email_provider() -> String {
+    dotenv().ok();
+    std_env::var(env::EMAIL_PROVIDER_ENV_VAR)
+        .ok()
+        .filter(|provider| !provider.is_empty())
+        .unwrap_or(DEFAULT_EMAIL_PROVIDER.to_owned())
+}
+
+fn set_smtp_host() -> Option<String> {
+    dotenv().ok();
+    std_env::var(env::SMTP_HOST_ENV_VAR)
+        .ok()
+        .filter(|host| !host.is_empty())
+}
+
+fn set_smtp_port() -> Option<u16> {
+    dotenv().ok();
+    std_env::var(env::SMTP_PORT_ENV_VAR)
+        .ok()
+        .filter(|port| !port.is_empty())
+        .map(|port| port.parse().expect("SMTP_PORT must be a port number."))
+}
+
+/// `starttls`, `tls` for implicit TLS, or `none`.
+fn set_smtp_tls() -> String {
+    dotenv().ok();
+    std_env::var(env::SMTP_TLS_ENV_VAR).unwrap_or(DEFAULT_SMTP_TLS.to_owned())
+}
+
+fn set_email_sender() -> String {
+    dotenv().ok();
+    std_env::var(env::EMAIL_SENDER_ENV_VAR)
+        .ok()
+        .filter(|sender| !sender.is_empty())
+        .unwrap_or(DEFAULT_EMAIL_SENDER.to_owned())
+}
+
+fn set_email_api_url() -> String {
+    dotenv().ok();
+    std_env::var(env::EMAIL_API_URL_ENV_VAR)
+        .ok()
+        .filter(|url| !url.is_empty())
+        .unwrap_or(DEFAULT_EMAIL_API_URL.to_owned())
+}
+
+fn set_email_api_token() -> Option<String> {
+    dotenv().ok();
+    std_env::var(env::EMAIL_API_TOKEN_ENV_VAR)
+        .ok()
+        .filter(|token| !token.is_empty())
+}
+
+fn set_brand_name() -> String {
+    dotenv().ok();
+    std_env::var(env::BRAND_NAME_ENV_VAR)
+        .ok()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(DEFAULT_BRAND_NAME.to_owned())
+}
+
+/// Where the logo and the name in emails link to. Defaults to the app itself.
+fn set_brand_url() -> String {
+    dotenv().ok();
+    std_env::var(env::BRAND_URL_ENV_VAR)
+        .ok()
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| PUBLIC_BASE_URL.clone())
+}
+
+fn set_brand_logo_url() -> Option<String> {
+    dotenv().ok();
+    std_env::var(env::BRAND_LOGO_URL_ENV_VAR)
+        .ok()
+        .filter(|url| !url.is_empty())
+}
+
+fn set_brand_support_email() -> Option<String> {
+    dotenv().ok();
+    std_env::var(env::BRAND_SUPPORT_EMAIL_ENV_VAR)
+        .ok()
+        .filter(|email| !email.is_empty())
+}
+
+/// The accent color of emails, used for buttons and links.
+fn set_brand_color() -> String {
+    dotenv().ok();
+    std_env::var(env::BRAND_COLOR_ENV_VAR)
+        .ok()
+        .filter(|color| !color.is_empty())
+        .unwrap_or(DEFAULT_BRAND_COLOR.to_owned())
+}
+
 pub mod env {
     pub const JWT_SECRET_ENV_VAR: &str = "JWT_SECRET";
     pub const DATABASE_URL_ENV_VAR: &str = "DATABASE_URL";
     pub const REDIS_HOST_NAME_ENV_VAR: &str = "REDIS_HOST_NAME";
+    pub const JWT_ALGORITHM_ENV_VAR: &str = "JWT_ALGORITHM";
+    pub const JWT_KEY_ID_ENV_VAR: &str = "JWT_KEY_ID";
+    pub const JWT_PRIVATE_KEY_PATH_ENV_VAR: &str = "JWT_PRIVATE_KEY_PATH";
+    pub const JWT_PUBLIC_KEY_PATH_ENV_VAR: &str = "JWT_PUBLIC_KEY_PATH";
+    pub const JWT_VERIFICATION_KEYS_ENV_VAR: &str = "JWT_VERIFICATION_KEYS";
+    pub const JWT_ISSUER_ENV_VAR: &str = "JWT_ISSUER";
+    pub const JWT_AUDIENCES_ENV_VAR: &str = "JWT_AUDIENCES";
+    pub const PUBLIC_BASE_URL_ENV_VAR: &str = "PUBLIC_BASE_URL";
+    pub const TOTP_ENCRYPTION_KEY_ENV_VAR: &str = "TOTP_ENCRYPTION_KEY";
+    pub const WEBAUTHN_RP_ID_ENV_VAR: &str = "WEBAUTHN_RP_ID";
+    pub const ADMIN_API_KEY_ENV_VAR: &str = "ADMIN_API_KEY";
+    pub const VERIFY_TOKEN_REQUIRE_CLIENT_ENV_VAR: &str = "VERIFY_TOKEN_REQUIRE_CLIENT";
+    pub const GOOGLE_CLIENT_ID_ENV_VAR: &str = "GOOGLE_CLIENT_ID";
+    pub const GOOGLE_CLIENT_SECRET_ENV_VAR: &str = "GOOGLE_CLIENT_SECRET";
+    pub const GITHUB_CLIENT_ID_ENV_VAR: &str = "GITHUB_CLIENT_ID";
+    pub const GITHUB_CLIENT_SECRET_ENV_VAR: &str = "GITHUB_CLIENT_SECRET";
+    pub const EMAIL_PROVIDER_ENV_VAR: &str = "EMAIL_PROVIDER";
+    pub const SMTP_HOST_ENV_VAR: &str = "SMTP_HOST";
+    pub const SMTP_PORT_ENV_VAR: &str = "SMTP_PORT";
+    pub const SMTP_TLS_ENV_VAR: &str = "SMTP_TLS";
+    pub const SMTP_USERNAME_ENV_VAR: &str = "SMTP_USERNAME";
+    pub const SMTP_PASSWORD_ENV_VAR: &str = "SMTP_PASSWORD";
+    pub const EMAIL_SENDER_ENV_VAR: &str = "EMAIL_SENDER";
+    pub const EMAIL_API_URL_ENV_VAR: &str = "EMAIL_API_URL";
+    pub const EMAIL_API_TOKEN_ENV_VAR: &str = "EMAIL_API_TOKEN";
+    pub const BRAND_NAME_ENV_VAR: &str = "BRAND_NAME";
+    pub const BRAND_URL_ENV_VAR: &str = "BRAND_URL";
+    pub const BRAND_LOGO_URL_ENV_VAR: &str = "BRAND_LOGO_URL";
+    pub const BRAND_SUPPORT_EMAIL_ENV_VAR: &str = "BRAND_SUPPORT_EMAIL";
+    pub const BRAND_COLOR_ENV_VAR: &str = "BRAND_COLOR";
 }
 
 pub mod prod {
@@ -42,4 +254,31 @@ pub mod test {
 }
 
 pub const JWT_COOKIE_NAME: &str = "jwt";
+pub const REFRESH_TOKEN_COOKIE_NAME: &str = "refresh_token";
+pub const SOCIAL_LOGIN_COOKIE_NAME: &str = "social_login";
 pub const DEFAULT_REDIS_HOSTNAME: &str = "127.0.0.1";
+pub const DEFAULT_JWT_ALGORITHM: &str = "HS256";
+pub const DEFAULT_JWT_KEY_ID: &str = "default";
+pub const DEFAULT_JWT_ISSUER: &str = "auth-service";
+pub const DEFAULT_JWT_AUDIENCE: &str = "app-service";
+pub const DEFAULT_PUBLIC_BASE_URL: &str = "http://localhost:3000";
+pub const DEFAULT_WEBAUTHN_RP_ID: &str = "localhost";
+pub const DEFAULT_EMAIL_PROVIDER: &str = "mock";
+pub const DEFAULT_SMTP_TLS: &str = "starttls";
+pub const DEFAULT_EMAIL_SENDER: &str = "Auth Service <no-reply@localhost>";
+pub const SMTP_TIMEOUT_SECONDS: u64 = 10;
+pub const DEFAULT_EMAIL_API_URL: &str = "https://api.postmarkapp.com";
+pub const EMAIL_API_TIMEOUT_SECONDS: u64 = 10;
+pub const EMAIL_API_MAX_RETRIES: u32 = 3;
+pub const EMAIL_API_RETRY_DELAY_MILLIS: u64 = 500;
+pub const EMAIL_OUTBOX_POLL_INTERVAL_MILLIS: u64 = 1000;
+pub const EMAIL_OUTBOX_BATCH_SIZE: i64 = 20;
+/// How long a claimed email stays hidden from other workers. Longer than a
+/// delivery with all its HTTP retries takes.
+pub const EMAIL_OUTBOX_LEASE_SECONDS: i64 = 60 * 5;
+/// With the doubling delays below, about half a day of retries.
+pub const EMAIL_OUTBOX_MAX_ATTEMPTS: i32 = 12;
+pub const EMAIL_OUTBOX_RETRY_DELAY_SECONDS: i64 = 30;
+pub const EMAIL_OUTBOX_MAX_RETRY_DELAY_SECONDS: i64 = 60 * 60 * 4;
+pub const DEFAULT_BRAND_NAME: &str = "Auth Service";
+pub const DEFAULT_BRAND_COLOR: &str = "#212529";
@@ -0,0 +1,378 @@
+use crate::{
+    domain::{EmailMessage, Locale},
+    utils::constants::{BRAND_COLOR, BRAND_LOGO_URL, BRAND_NAME, BRAND_SUPPORT_EMAIL, BRAND_URL},
+};
+use axum::http::{header, HeaderMap};
+use lazy_static::lazy_static;
+use minijinja::{context, Environment, UndefinedBehavior, Value};
+use serde::Serialize;
+use std::net::IpAddr;
+
+macro_rules! template {
+    ($path:literal) => {
+        (
+            $path,
+            include_str!(concat!("../../templates/email/", $path)),
+        )
+    };
+    ($locale:literal, $file:literal) => {
+        (
+            concat!($locale, "/", $file),
+            include_str!(concat!("../../templates/email/", $locale, "/", $file)),
+        )
+    };
+}
+
+/// Lists the shared templates, then each translation under its locale.
+macro_rules! email_templates {
+    ($($locale:literal),+) => {
+        &[
+            template!("layout.html"),
+            template!("layout.txt"),
+            template!("macros.html"),
+            $(
+                template!($locale, "layout.html"),
+                template!($locale, "layout.txt"),
+                template!($locale, "two_fa_code.txt"),
+                template!($locale, "two_fa_code.html"),
+                template!($locale, "verify_email.txt"),
+                template!($locale, "verify_email.html"),
+                template!($locale, "password_reset.txt"),
+                template!($locale, "password_reset.html"),
+                template!($locale, "account_locked.txt"),
+                template!($locale, "account_locked.html"),
+                template!($locale, "account_deleted.txt"),
+                template!($locale, "account_deleted.html"),
+                template!($locale, "security_alert.txt"),
+                template!($locale, "security_alert.html"),
+            )+
+        ]
+    };
+}
+
+/// The templates are compiled into the binary, so a missing or broken one
+/// fails the tests rather than a send in production.
+const TEMPLATE_SOURCES: &[(&str, &str)] = email_templates!("en", "de", "fr");
+
+lazy_static! {
+    static ref TEMPLATES: Environment<'static> = load_templates();
+    static ref BRANDING: Branding = Branding::default();
+}
+
+fn load_templates() -> Environment<'static> {
+    let mut env = Environment::new();
+    // A value missing from the context is a bug, not an empty string.
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+    env.set_trim_blocks(true);
+    for (name, source) in TEMPLATE_SOURCES {
+        env.add_template(name, source)
+            .expect("Invalid email template");
+    }
+    env
+}
+
+/// The language the request asks for in its `Accept-Language` header, if
+/// emails are translated into it.
+pub fn requested_locale(headers: &HeaderMap) -> Option<Locale> {
+    headers
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(Locale::from_accept_language)
+}
+
+/// How emails present the service, shared by every template.
+#[derive(Debug, Clone, Serialize)]
+pub struct Branding {
+    pub name: String,
+    pub url: String,
+    pub logo_url: Option<String>,
+    pub support_email: Option<String>,
+    /// A CSS color, like `#0d6efd`.
+    pub color: String,
+}
+
+impl Default for Branding {
+    fn default() -> Self {
+        Self {
+            name: BRAND_NAME.clone(),
+            url: BRAND_URL.clone(),
+            logo_url: BRAND_LOGO_URL.clone(),
+            support_email: BRAND_SUPPORT_EMAIL.clone(),
+            color: BRAND_COLOR.clone(),
+        }
+    }
+}
+
+/// Changes to an account that its owner is told about, in case someone
+/// else made them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SecurityEvent {
+    PasswordChanged,
+    TwoFAChanged,
+    PasskeyAdded,
+    RecoveryCodesGenerated,
+}
+
+impl AsRef<str> for SecurityEvent {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::PasswordChanged => "password_changed",
+            Self::TwoFAChanged => "two_fa_changed",
+            Self::PasskeyAdded => "passkey_added",
+            Self::RecoveryCodesGenerated => "recovery_codes_generated",
+        }
+    }
+}
+
+/// The emails we send. Each has a `.txt` template with `subject` and `body`
+/// blocks, and a `.html` template for the HTML body, which is escaped. Every
+/// locale has its own translation of both.
+#[derive(Debug)]
+pub enum EmailTemplate<'a> {
+    TwoFACode {
+        code: &'a str,
+    },
+    EmailVerification {
+        link: &'a str,
+        expires_in_hours: i64,
+    },
+    PasswordReset {
+        link: &'a str,
+        expires_in_minutes: i64,
+    },
+    AccountLocked {
+        link: &'a str,
+        expires_in_hours: i64,
+    },
+    AccountDeleted,
+    SecurityAlert {
+        event: SecurityEvent,
+        ip_address: IpAddr,
+    },
+}
+
+impl EmailTemplate<'_> {
+    fn name(&self) -> &'static str {
+        match self {
+            EmailTemplate::TwoFACode { .. } => "two_fa_code",
+            EmailTemplate::EmailVerification { .. } => "verify_email",
+            EmailTemplate::PasswordReset { .. } => "password_reset",
+            EmailTemplate::AccountLocked { .. } => "account_locked",
+            EmailTemplate::AccountDeleted => "account_deleted",
+            EmailTemplate::SecurityAlert { .. } => "security_alert",
+        }
+    }
+
+    fn context(&self) -> Value {
+        match self {
+            EmailTemplate::TwoFACode { code } => context! { code },
+            EmailTemplate::EmailVerification {
+                link,
+                expires_in_hours,
+            }
+            | EmailTemplate::AccountLocked {
+                link,
+                expires_in_hours,
+            } => context! { link, expires_in_hours },
+            EmailTemplate::PasswordReset {
+                link,
+                expires_in_minutes,
+            } => context! { link, expires_in_minutes },
+            EmailTemplate::AccountDeleted => context! {},
+            EmailTemplate::SecurityAlert { event, ip_address } => context! {
+                event => event.as_ref(),
+                ip_address,
+            },
+        }
+    }
+
+    /// Renders the email in the given language, branded as configured.
+    pub fn render(&self, locale: Locale) -> Result<EmailMessage, String> {
+        self.render_with_branding(locale, &BRANDING)
+    }
+
+    pub fn render_with_branding(
+        &self,
+        locale: Locale,
+        branding: &Branding,
+    ) -> Result<EmailMessage, String> {
+        let context = context! {
+            locale => locale.as_ref(),
+            brand => branding,
+            ..self.context()
+        };
+        let render_error = |e: minijinja::Error| {
+            format!(
+                "Failed to render {} in {}: {}",
+                self.name(),
+                locale.as_ref(),
+                e
+            )
+        };
+
+        let text_template = TEMPLATES
+            .get_template(&format!("{}/{}.txt", locale.as_ref(), self.name()))
+            .map_err(render_error)?;
+        let mut text = text_template
+            .render_captured(&context)
+            .map_err(render_error)?;
+        let (subject, text_body) = text
+            .with_state_mut(|state| {
+                Ok::<_, minijinja::Error>((
+                    state.render_block("subject")?,
+                    state.render_block("body")?,
+                ))
+            })
+            .map_err(render_error)?;
+
+        let html_body = TEMPLATES
+            .get_template(&format!("{}/{}.html", locale.as_ref(), self.name()))
+            .and_then(|template| template.render(&context))
+            .map_err(render_error)?;
+
+        Ok(EmailMessage {
+            subject: subject.trim().to_owned(),
+            text_body: format!("{}\n", text_body.trim()),
+            html_body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERIFY_EMAIL_LINK: &str = "http://localhost:3000/verify-email?token=abc";
+    const PASSWORD_RESET_LINK: &str = "http://localhost:3000/password-reset?token=abc";
+    const UNLOCK_ACCOUNT_LINK: &str = "http://localhost:3000/unlock-account?token=abc";
+
+    fn branding() -> Branding {
+        Branding {
+            name: "Acme".to_owned(),
+            url: "https://acme.example".to_owned(),
+            logo_url: None,
+            support_email: Some("support@acme.example".to_owned()),
+            color: "#0d6efd".to_owned(),
+        }
+    }
+
+    fn all_templates() -> Vec<EmailTemplate<'static>> {
+        let mut templates = vec![
+            EmailTemplate::TwoFACode { code: "123456" },
+            EmailTemplate::EmailVerification {
+                link: VERIFY_EMAIL_LINK,
+                expires_in_hours: 24,
+            },
+            EmailTemplate::PasswordReset {
+                link: PASSWORD_RESET_LINK,
+                expires_in_minutes: 30,
+            },
+            EmailTemplate::AccountLocked {
+                link: UNLOCK_ACCOUNT_LINK,
+                expires_in_hours: 24,
+            },
+            EmailTemplate::AccountDeleted,
+        ];
+        for event in [
+            SecurityEvent::PasswordChanged,
+            SecurityEvent::TwoFAChanged,
+            SecurityEvent::PasskeyAdded,
+            SecurityEvent::RecoveryCodesGenerated,
+        ] {
+            templates.push(EmailTemplate::SecurityAlert {
+                event,
+                ip_address: "203.0.113.7".parse().unwrap(),
+            });
+        }
+        templates
+    }
+
+    #[test]
+    fn test_all_templates_render_in_every_locale() {
+        for locale in Locale::ALL {
+            for template in all_templates() {
+                let message = template.render_with_branding(locale, &branding()).unwrap();
+                assert!(!message.subject.is_empty(), "{:?}", template);
+                assert!(!message.subject.contains('\n'));
+                assert!(!message.text_body.trim().is_empty());
+                assert!(message.text_body.contains("support@acme.example"));
+                assert!(message
+                    .html_body
+                    .contains(&format!("<html lang=\"{}\">", locale.as_ref())));
+            }
+        }
+    }
+
+    #[test]
+    fn test_html_body_is_escaped() {
+        let link = "http://localhost:3000/verify-email?token=abc&next=<script>";
+        let message = EmailTemplate::EmailVerification {
+            link,
+            expires_in_hours: 24,
+        }
+        .render_with_branding(Locale::En, &branding())
+        .unwrap();
+
+        assert_eq!(message.subject, "Verify your email address");
+        assert!(message.text_body.contains(link));
+        assert!(message.text_body.contains("24 hours"));
+        assert!(!message.html_body.contains("<script>"));
+        assert!(message.html_body.contains("&amp;next=&lt;script&gt;"));
+    }
+
+    #[test]
+    fn test_branding_without_optional_values() {
+        let branding = Branding {
+            logo_url: None,
+            support_email: None,
+            ..branding()
+        };
+        let message = EmailTemplate::AccountDeleted
+            .render_with_branding(Locale::De, &branding)
+            .unwrap();
+
+        assert_eq!(message.subject, "Ihr Konto wurde gelöscht");
+        assert!(!message.text_body.contains("Fragen?"));
+        assert!(!message.html_body.contains("mailto:"));
+        assert!(message.html_body.contains(">\n                Acme\n"));
+    }
+
+    #[test]
+    fn test_requested_locale() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(requested_locale(&headers), None);
+
+        headers.insert(
+            header::ACCEPT_LANGUAGE,
+            "fr-CH, fr;q=0.9, en;q=0.8".parse().unwrap(),
+        );
+        assert_eq!(requested_locale(&headers), Some(Locale::Fr));
+    }
+
+    #[test]
+    fn test_rendered_emails() {
+        let branding = Branding {
+            logo_url: Some("https://acme.example/logo.png".to_owned()),
+            ..branding()
+        };
+        for locale in Locale::ALL {
+            for template in all_templates() {
+                let message = template.render_with_branding(locale, &branding).unwrap();
+                let name = match &template {
+                    EmailTemplate::SecurityAlert { event, .. } => {
+                        format!("{}_{}", template.name(), event.as_ref())
+                    }
+                    _ => template.name().to_owned(),
+                };
+                insta::assert_snapshot!(
+                    format!("{}_{}_text", locale.as_ref(), name),
+                    format!("Subject: {}\n\n{}", message.subject, message.text_body)
+                );
+                insta::assert_snapshot!(
+                    format!("{}_{}_html", locale.as_ref(), name),
+                    message.html_body
+                );
+            }
+        }
+    }
+}
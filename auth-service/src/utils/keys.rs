@@ -0,0 +1,378 @@
+use super::constants::{env, DEFAULT_JWT_ALGORITHM, DEFAULT_JWT_KEY_ID};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters, Jwk,
+        KeyAlgorithm, OctetKeyPairParameters, PublicKeyUse, RSAKeyParameters,
+    },
+    Algorithm, DecodingKey, EncodingKey,
+};
+use simple_asn1::{from_der, ASN1Block};
+use std::{env as std_env, str::FromStr};
+
+/// Key material used to sign JWTs. Asymmetric keys also carry the public JWK
+/// that is published on `/.well-known/jwks.json`.
+pub struct SigningKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    verification_key: VerificationKey,
+}
+
+/// Key material that can only check signatures, e.g. a retired signing key
+/// whose tokens have not expired yet.
+#[derive(Clone)]
+pub struct VerificationKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    decoding_key: DecodingKey,
+    jwk: Option<Jwk>,
+}
+
+#[derive(Debug)]
+pub enum KeyError {
+    UnsupportedAlgorithm(String),
+    MissingConfig(&'static str),
+    Io(std::io::Error),
+    InvalidKey(String),
+}
+
+impl SigningKey {
+    pub fn from_secret(kid: &str, secret: &[u8]) -> Self {
+        Self {
+            kid: kid.to_owned(),
+            algorithm: Algorithm::HS256,
+            encoding_key: EncodingKey::from_secret(secret),
+            verification_key: VerificationKey::from_secret(kid, secret),
+        }
+    }
+
+    pub fn from_pem(
+        kid: &str,
+        algorithm: Algorithm,
+        private_pem: &[u8],
+        public_pem: &[u8],
+    ) -> Result<Self, KeyError> {
+        let encoding_key = match KeyFamily::of(algorithm)? {
+            KeyFamily::Rsa => EncodingKey::from_rsa_pem(private_pem),
+            KeyFamily::EllipticCurve => EncodingKey::from_ec_pem(private_pem),
+            KeyFamily::Edwards => EncodingKey::from_ed_pem(private_pem),
+        }
+        .map_err(|e| KeyError::InvalidKey(e.to_string()))?;
+
+        Ok(Self {
+            kid: kid.to_owned(),
+            algorithm,
+            encoding_key,
+            verification_key: VerificationKey::from_pem(kid, algorithm, public_pem)?,
+        })
+    }
+
+    /// Builds the signing key described by the `JWT_*` environment variables.
+    /// `HS256` keeps using `JWT_SECRET`; every other algorithm loads a PEM key pair.
+    /// The environment is read on every call so the key can be reloaded.
+    pub fn from_config() -> Result<Self, KeyError> {
+        let kid = config_var(env::JWT_KEY_ID_ENV_VAR).unwrap_or(DEFAULT_JWT_KEY_ID.to_owned());
+        let algorithm =
+            config_var(env::JWT_ALGORITHM_ENV_VAR).unwrap_or(DEFAULT_JWT_ALGORITHM.to_owned());
+        let algorithm = Algorithm::from_str(&algorithm)
+            .map_err(|_| KeyError::UnsupportedAlgorithm(algorithm))?;
+
+        if algorithm == Algorithm::HS256 {
+            let secret = config_var(env::JWT_SECRET_ENV_VAR)
+                .ok_or(KeyError::MissingConfig(env::JWT_SECRET_ENV_VAR))?;
+            return Ok(Self::from_secret(&kid, secret.as_bytes()));
+        }
+
+        let private_pem = read_key_file(env::JWT_PRIVATE_KEY_PATH_ENV_VAR)?;
+        let public_pem = read_key_file(env::JWT_PUBLIC_KEY_PATH_ENV_VAR)?;
+
+        Self::from_pem(&kid, algorithm, &private_pem, &public_pem)
+    }
+
+    pub fn encoding_key(&self) -> &EncodingKey {
+        &self.encoding_key
+    }
+
+    pub fn verification_key(&self) -> &VerificationKey {
+        &self.verification_key
+    }
+
+    pub fn jwk(&self) -> Option<&Jwk> {
+        self.verification_key.jwk()
+    }
+}
+
+impl VerificationKey {
+    pub fn from_secret(kid: &str, secret: &[u8]) -> Self {
+        Self {
+            kid: kid.to_owned(),
+            algorithm: Algorithm::HS256,
+            decoding_key: DecodingKey::from_secret(secret),
+            jwk: None,
+        }
+    }
+
+    pub fn from_pem(kid: &str, algorithm: Algorithm, public_pem: &[u8]) -> Result<Self, KeyError> {
+        let decoding_key = match KeyFamily::of(algorithm)? {
+            KeyFamily::Rsa => DecodingKey::from_rsa_pem(public_pem),
+            KeyFamily::EllipticCurve => DecodingKey::from_ec_pem(public_pem),
+            KeyFamily::Edwards => DecodingKey::from_ed_pem(public_pem),
+        }
+        .map_err(|e| KeyError::InvalidKey(e.to_string()))?;
+
+        Ok(Self {
+            kid: kid.to_owned(),
+            algorithm,
+            decoding_key,
+            jwk: Some(public_jwk(kid, algorithm, public_pem)?),
+        })
+    }
+
+    /// Parses `JWT_VERIFICATION_KEYS`, a comma separated list of `kid:ALG:path`
+    /// entries. The file holds a PEM public key, or the raw secret for `HS256`.
+    pub fn list_from_config() -> Result<Vec<Self>, KeyError> {
+        let Some(entries) = config_var(env::JWT_VERIFICATION_KEYS_ENV_VAR) else {
+            return Ok(Vec::new());
+        };
+
+        entries
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let mut parts = entry.splitn(3, ':');
+                let (Some(kid), Some(algorithm), Some(path)) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(KeyError::InvalidKey(format!(
+                        "Expected kid:ALG:path, got {}",
+                        entry
+                    )));
+                };
+                let algorithm = Algorithm::from_str(algorithm)
+                    .map_err(|_| KeyError::UnsupportedAlgorithm(algorithm.to_owned()))?;
+                let contents = std::fs::read(path).map_err(KeyError::Io)?;
+
+                if algorithm == Algorithm::HS256 {
+                    Ok(Self::from_secret(kid, contents.trim_ascii()))
+                } else {
+                    Self::from_pem(kid, algorithm, &contents)
+                }
+            })
+            .collect()
+    }
+
+    pub fn decoding_key(&self) -> &DecodingKey {
+        &self.decoding_key
+    }
+
+    pub fn jwk(&self) -> Option<&Jwk> {
+        self.jwk.as_ref()
+    }
+}
+
+enum KeyFamily {
+    Rsa,
+    EllipticCurve,
+    Edwards,
+}
+
+impl KeyFamily {
+    fn of(algorithm: Algorithm) -> Result<Self, KeyError> {
+        match algorithm {
+            Algorithm::RS256
+            | Algorithm::RS384
+            | Algorithm::RS512
+            | Algorithm::PS256
+            | Algorithm::PS384
+            | Algorithm::PS512 => Ok(Self::Rsa),
+            Algorithm::ES256 | Algorithm::ES384 => Ok(Self::EllipticCurve),
+            Algorithm::EdDSA => Ok(Self::Edwards),
+            _ => Err(KeyError::UnsupportedAlgorithm(format!("{:?}", algorithm))),
+        }
+    }
+}
+
+fn config_var(name: &str) -> Option<String> {
+    std_env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+fn read_key_file(name: &'static str) -> Result<Vec<u8>, KeyError> {
+    let path = config_var(name).ok_or(KeyError::MissingConfig(name))?;
+    std::fs::read(path).map_err(KeyError::Io)
+}
+
+fn public_jwk(kid: &str, algorithm: Algorithm, public_pem: &[u8]) -> Result<Jwk, KeyError> {
+    let pem = pem::parse(public_pem).map_err(|e| KeyError::InvalidKey(e.to_string()))?;
+    let key_bytes = match pem.tag() {
+        "PUBLIC KEY" => subject_public_key(pem.contents())?,
+        "RSA PUBLIC KEY" => pem.contents().to_vec(),
+        tag => return Err(KeyError::InvalidKey(format!("Unexpected PEM tag {}", tag))),
+    };
+
+    let parameters = match KeyFamily::of(algorithm)? {
+        KeyFamily::Rsa => {
+            let (n, e) = rsa_components(&key_bytes)?;
+            AlgorithmParameters::RSA(RSAKeyParameters {
+                key_type: Default::default(),
+                n: URL_SAFE_NO_PAD.encode(n),
+                e: URL_SAFE_NO_PAD.encode(e),
+            })
+        }
+        KeyFamily::EllipticCurve => {
+            // Uncompressed SEC1 point: 0x04 || x || y
+            let (curve, coordinate_length) = match algorithm {
+                Algorithm::ES256 => (EllipticCurve::P256, 32),
+                _ => (EllipticCurve::P384, 48),
+            };
+            if key_bytes.len() != 1 + 2 * coordinate_length || key_bytes[0] != 0x04 {
+                return Err(KeyError::InvalidKey("Unexpected EC point".to_owned()));
+            }
+            let (x, y) = key_bytes[1..].split_at(coordinate_length);
+            AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+                key_type: Default::default(),
+                curve,
+                x: URL_SAFE_NO_PAD.encode(x),
+                y: URL_SAFE_NO_PAD.encode(y),
+            })
+        }
+        KeyFamily::Edwards => AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+            key_type: Default::default(),
+            curve: EllipticCurve::Ed25519,
+            x: URL_SAFE_NO_PAD.encode(&key_bytes),
+        }),
+    };
+
+    let key_algorithm = KeyAlgorithm::from_str(&format!("{:?}", algorithm))
+        .map_err(|_| KeyError::UnsupportedAlgorithm(format!("{:?}", algorithm)))?;
+
+    Ok(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(kid.to_owned()),
+            ..Default::default()
+        },
+        algorithm: parameters,
+    })
+}
+
+/// Extracts the raw key from a DER encoded SubjectPublicKeyInfo.
+fn subject_public_key(der: &[u8]) -> Result<Vec<u8>, KeyError> {
+    let blocks = from_der(der).map_err(|e| KeyError::InvalidKey(e.to_string()))?;
+    match blocks.first() {
+        Some(ASN1Block::Sequence(_, fields)) => match fields.get(1) {
+            Some(ASN1Block::BitString(_, _, bytes)) => Ok(bytes.clone()),
+            _ => Err(KeyError::InvalidKey("Missing public key".to_owned())),
+        },
+        _ => Err(KeyError::InvalidKey(
+            "Expected SubjectPublicKeyInfo".to_owned(),
+        )),
+    }
+}
+
+/// Extracts the modulus and exponent from a DER encoded PKCS#1 RSAPublicKey.
+fn rsa_components(der: &[u8]) -> Result<(Vec<u8>, Vec<u8>), KeyError> {
+    let blocks = from_der(der).map_err(|e| KeyError::InvalidKey(e.to_string()))?;
+    match blocks.first() {
+        Some(ASN1Block::Sequence(_, fields)) => match (fields.first(), fields.get(1)) {
+            (Some(ASN1Block::Integer(_, n)), Some(ASN1Block::Integer(_, e))) => {
+                Ok((n.to_bytes_be().1, e.to_bytes_be().1))
+            }
+            _ => Err(KeyError::InvalidKey(
+                "Expected modulus and exponent".to_owned(),
+            )),
+        },
+        _ => Err(KeyError::InvalidKey("Expected RSAPublicKey".to_owned())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{decode, encode, Header, Validation};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct TestClaims {
+        sub: String,
+        exp: usize,
+    }
+
+    fn assert_published_jwk_verifies_tokens(key: SigningKey) {
+        let claims = TestClaims {
+            sub: "test@example.com".to_owned(),
+            exp: 4_000_000_000,
+        };
+        let mut header = Header::new(key.algorithm);
+        header.kid = Some(key.kid.clone());
+        let token = encode(&header, &claims, key.encoding_key()).unwrap();
+
+        let jwk = key.jwk().expect("Asymmetric keys should publish a JWK");
+        assert_eq!(jwk.common.key_id.as_deref(), Some("test-key"));
+
+        let decoded = decode::<TestClaims>(
+            &token,
+            &DecodingKey::from_jwk(jwk).unwrap(),
+            &Validation::new(key.algorithm),
+        )
+        .unwrap();
+        assert_eq!(decoded.claims, claims);
+        assert_eq!(decoded.header.kid.as_deref(), Some("test-key"));
+    }
+
+    #[test]
+    fn test_rs256_key_publishes_matching_jwk() {
+        let key = SigningKey::from_pem(
+            "test-key",
+            Algorithm::RS256,
+            include_bytes!("../../tests/fixtures/keys/rs256_private.pem"),
+            include_bytes!("../../tests/fixtures/keys/rs256_public.pem"),
+        )
+        .unwrap();
+        assert_published_jwk_verifies_tokens(key);
+    }
+
+    #[test]
+    fn test_es256_key_publishes_matching_jwk() {
+        let key = SigningKey::from_pem(
+            "test-key",
+            Algorithm::ES256,
+            include_bytes!("../../tests/fixtures/keys/es256_private.pem"),
+            include_bytes!("../../tests/fixtures/keys/es256_public.pem"),
+        )
+        .unwrap();
+        assert_published_jwk_verifies_tokens(key);
+    }
+
+    #[test]
+    fn test_eddsa_key_publishes_matching_jwk() {
+        let key = SigningKey::from_pem(
+            "test-key",
+            Algorithm::EdDSA,
+            include_bytes!("../../tests/fixtures/keys/eddsa_private.pem"),
+            include_bytes!("../../tests/fixtures/keys/eddsa_public.pem"),
+        )
+        .unwrap();
+        assert_published_jwk_verifies_tokens(key);
+    }
+
+    #[test]
+    fn test_secret_key_is_not_published() {
+        let key = SigningKey::from_secret("test-key", b"secret");
+        assert_eq!(key.algorithm, Algorithm::HS256);
+        assert!(key.jwk().is_none());
+    }
+
+    #[test]
+    fn test_mismatched_key_is_rejected() {
+        let result = SigningKey::from_pem(
+            "test-key",
+            Algorithm::RS256,
+            include_bytes!("../../tests/fixtures/keys/es256_private.pem"),
+            include_bytes!("../../tests/fixtures/keys/es256_public.pem"),
+        );
+        assert!(result.is_err());
+    }
+}
@@ -1,7 +1,17 @@
 pub mod auth;
 pub mod constants;
+pub mod email_templates;
+pub mod keys;
+pub mod rate_limit;
+pub mod totp;
 pub mod tracing;
+pub mod webauthn;
 
 pub use auth::*;
 pub use constants::*;
+pub use email_templates::*;
+pub use keys::*;
+pub use rate_limit::*;
+pub use totp::*;
 pub use tracing::*;
+pub use webauthn::*;
@@ -0,0 +1,208 @@
+use crate::{
+    app_state::RateLimitStoreType,
+    domain::{AuthAPIError, Email, FailedAttempts, LoginAttemptId},
+};
+use chrono::{Duration, Utc};
+use std::net::IpAddr;
+
+/// How many failures a key may collect before it is locked, and for how long.
+/// Every failure past the free ones doubles the lockout, up to the maximum.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitPolicy {
+    pub free_attempts: u32,
+    pub base_lockout_seconds: i64,
+    pub max_lockout_seconds: i64,
+    /// How long failures are remembered after the last one.
+    pub window_seconds: i64,
+}
+
+impl RateLimitPolicy {
+    pub fn lockout_seconds(&self, failures: u32) -> Option<i64> {
+        let excess = failures.checked_sub(self.free_attempts + 1)?;
+        let lockout = self
+            .base_lockout_seconds
+            .saturating_mul(2i64.saturating_pow(excess));
+
+        Some(lockout.min(self.max_lockout_seconds))
+    }
+}
+
+pub const ACCOUNT_RATE_LIMIT: RateLimitPolicy = RateLimitPolicy {
+    free_attempts: 5,
+    base_lockout_seconds: 30,
+    max_lockout_seconds: 60 * 15,
+    window_seconds: 60 * 15,
+};
+
+/// Looser than the account policy, as users behind a shared address (NAT,
+/// offices) all count towards the same key.
+pub const IP_RATE_LIMIT: RateLimitPolicy = RateLimitPolicy {
+    free_attempts: 20,
+    base_lockout_seconds: 30,
+    max_lockout_seconds: 60 * 15,
+    window_seconds: 60 * 15,
+};
+
+/// Failures on one account, within its rate limit window, after which it is
+/// locked until the user follows the emailed link or an admin unlocks it.
+pub const ACCOUNT_LOCK_THRESHOLD: u32 = 10;
+pub const ACCOUNT_LOCK_SECONDS: i64 = 60 * 60 * 24;
+
+/// Wrong codes a single 2FA login attempt may receive before it is thrown
+/// away and the user has to log in with their password again.
+pub const MAX_TWO_FA_CODE_ATTEMPTS: u32 = 5;
+/// Times a single 2FA login attempt may have its code emailed again.
+pub const MAX_TWO_FA_CODE_RESENDS: u32 = 3;
+/// As long as a 2FA code stays valid.
+pub const TWO_FA_ATTEMPT_WINDOW_SECONDS: i64 = 60 * 10;
+
+pub enum RateLimitKey<'a> {
+    Account(&'a Email),
+    Ip(IpAddr),
+    TwoFAAttempt(&'a LoginAttemptId),
+    TwoFAResend(&'a LoginAttemptId),
+}
+
+impl RateLimitKey<'_> {
+    pub fn key(&self) -> String {
+        match self {
+            RateLimitKey::Account(email) => format!("account:{}", email.as_ref()),
+            RateLimitKey::Ip(ip) => format!("ip:{}", ip),
+            RateLimitKey::TwoFAAttempt(id) => format!("2fa_attempt:{}", id.as_ref()),
+            RateLimitKey::TwoFAResend(id) => format!("2fa_resend:{}", id.as_ref()),
+        }
+    }
+
+    /// 2FA attempts and resends are only counted, they are capped instead of
+    /// locked.
+    fn policy(&self) -> Option<RateLimitPolicy> {
+        match self {
+            RateLimitKey::Account(_) => Some(ACCOUNT_RATE_LIMIT),
+            RateLimitKey::Ip(_) => Some(IP_RATE_LIMIT),
+            RateLimitKey::TwoFAAttempt(_) | RateLimitKey::TwoFAResend(_) => None,
+        }
+    }
+
+    fn window_seconds(&self) -> i64 {
+        self.policy()
+            .map_or(TWO_FA_ATTEMPT_WINDOW_SECONDS, |policy| {
+                policy.window_seconds
+            })
+    }
+}
+
+/// Rejects the request with `TooManyRequests` while any of the keys is
+/// locked.
+pub async fn check_rate_limit(
+    store: &RateLimitStoreType,
+    keys: &[RateLimitKey<'_>],
+) -> Result<(), AuthAPIError> {
+    let store = store.read().await;
+    let now = Utc::now();
+
+    for key in keys {
+        let attempts = store
+            .get_attempts(&key.key())
+            .await
+            .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+        if let Some(locked_until) = attempts.locked_until.filter(|until| *until > now) {
+            let retry_after = (locked_until - now).num_seconds().max(1).unsigned_abs();
+            return Err(AuthAPIError::TooManyRequests(retry_after));
+        }
+    }
+
+    Ok(())
+}
+
+/// Counts a failed attempt against each key and returns the new counts.
+pub async fn record_failure(
+    store: &RateLimitStoreType,
+    keys: &[RateLimitKey<'_>],
+) -> Result<Vec<u32>, AuthAPIError> {
+    let mut store = store.write().await;
+    let now = Utc::now();
+    let mut counts = Vec::with_capacity(keys.len());
+
+    for key in keys {
+        let attempts = store
+            .get_attempts(&key.key())
+            .await
+            .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+        let count = attempts.count.saturating_add(1);
+        let lockout_seconds = key
+            .policy()
+            .and_then(|policy| policy.lockout_seconds(count));
+        let attempts = FailedAttempts {
+            count,
+            locked_until: lockout_seconds.map(|seconds| now + Duration::seconds(seconds)),
+        };
+
+        store
+            .set_attempts(
+                &key.key(),
+                attempts,
+                key.window_seconds().max(lockout_seconds.unwrap_or(0)),
+            )
+            .await
+            .map_err(|_| AuthAPIError::UnexpectedError)?;
+        counts.push(count);
+    }
+
+    Ok(counts)
+}
+
+pub async fn clear_failures(
+    store: &RateLimitStoreType,
+    keys: &[RateLimitKey<'_>],
+) -> Result<(), AuthAPIError> {
+    let mut store = store.write().await;
+
+    for key in keys {
+        store
+            .clear_attempts(&key.key())
+            .await
+            .map_err(|_| AuthAPIError::UnexpectedError)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::HashmapRateLimitStore;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    #[test]
+    fn test_lockout_doubles_after_free_attempts() {
+        assert_eq!(ACCOUNT_RATE_LIMIT.lockout_seconds(5), None);
+        assert_eq!(ACCOUNT_RATE_LIMIT.lockout_seconds(6), Some(30));
+        assert_eq!(ACCOUNT_RATE_LIMIT.lockout_seconds(7), Some(60));
+        assert_eq!(ACCOUNT_RATE_LIMIT.lockout_seconds(8), Some(120));
+        assert_eq!(ACCOUNT_RATE_LIMIT.lockout_seconds(100), Some(60 * 15));
+    }
+
+    #[tokio::test]
+    async fn test_locks_key_after_free_attempts() {
+        let store: RateLimitStoreType = Arc::new(RwLock::new(HashmapRateLimitStore::new()));
+        let email = Email::parse("valid@mail.com".to_string()).unwrap();
+        let keys = [RateLimitKey::Account(&email)];
+
+        for _ in 0..ACCOUNT_RATE_LIMIT.free_attempts {
+            record_failure(&store, &keys).await.unwrap();
+            assert_eq!(check_rate_limit(&store, &keys).await, Ok(()));
+        }
+
+        record_failure(&store, &keys).await.unwrap();
+        assert!(matches!(
+            check_rate_limit(&store, &keys).await,
+            Err(AuthAPIError::TooManyRequests(retry_after)) if retry_after <= 30
+        ));
+
+        clear_failures(&store, &keys).await.unwrap();
+        assert_eq!(check_rate_limit(&store, &keys).await, Ok(()));
+    }
+}
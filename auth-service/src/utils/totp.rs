@@ -0,0 +1,193 @@
+use crate::domain::Email;
+use aes_gcm::{
+    aead::{Aead, AeadCore, OsRng},
+    Aes256Gcm, KeyInit, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::time::{SystemTime, UNIX_EPOCH};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+pub const TOTP_ISSUER: &str = "auth-service";
+pub const TOTP_DIGITS: usize = 6;
+pub const TOTP_STEP_SECONDS: u64 = 30;
+/// Codes from one step before or after the current one are still accepted to
+/// allow for clock drift between the server and the authenticator app.
+pub const TOTP_SKEW_STEPS: u8 = 1;
+
+const NONCE_LENGTH: usize = 12;
+
+#[derive(Debug)]
+pub enum TotpError {
+    InvalidKey,
+    InvalidSecret,
+    CryptoError,
+}
+
+/// Generates a new random RFC 6238 shared secret.
+pub fn generate_totp_secret() -> Result<Vec<u8>, TotpError> {
+    Secret::generate_secret()
+        .to_bytes()
+        .map_err(|_| TotpError::InvalidSecret)
+}
+
+fn totp(secret: &[u8], email: &Email) -> Result<TOTP, TotpError> {
+    TOTP::new(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        TOTP_SKEW_STEPS,
+        TOTP_STEP_SECONDS,
+        secret.to_vec(),
+        Some(TOTP_ISSUER.to_owned()),
+        email.as_ref().to_owned(),
+    )
+    .map_err(|_| TotpError::InvalidSecret)
+}
+
+/// The `otpauth://` URI authenticator apps use to enroll the secret.
+pub fn get_otpauth_uri(secret: &[u8], email: &Email) -> Result<String, TotpError> {
+    Ok(totp(secret, email)?.get_url())
+}
+
+/// Returns the time step the code belongs to, or `None` if it doesn't match
+/// any step within the skew. Callers record the step so the code can't be
+/// replayed while it is still valid.
+pub fn verify_totp_code(
+    secret: &[u8],
+    email: &Email,
+    code: &str,
+) -> Result<Option<u64>, TotpError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| TotpError::CryptoError)?
+        .as_secs();
+
+    Ok(find_step(&totp(secret, email)?, code, now))
+}
+
+fn find_step(totp: &TOTP, code: &str, time: u64) -> Option<u64> {
+    let current_step = time / TOTP_STEP_SECONDS;
+    let skew = u64::from(TOTP_SKEW_STEPS);
+    // Each step is checked on its own to learn which one matched. The latest
+    // match wins, since the earlier steps may already be used.
+    let mut exact = totp.clone();
+    exact.skew = 0;
+    (current_step.saturating_sub(skew)..=current_step + skew)
+        .rev()
+        .find(|step| exact.check(code, step * TOTP_STEP_SECONDS))
+}
+
+/// Encrypts TOTP secrets before they are stored, so a database dump alone is
+/// not enough to generate codes.
+#[derive(Clone)]
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl SecretCipher {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(key.into()),
+        }
+    }
+
+    /// Reads a base64 encoded 256 bit key.
+    pub fn from_base64(key: &str) -> Result<Self, TotpError> {
+        let key: [u8; 32] = STANDARD
+            .decode(key.trim())
+            .map_err(|_| TotpError::InvalidKey)?
+            .try_into()
+            .map_err(|_| TotpError::InvalidKey)?;
+
+        Ok(Self::new(&key))
+    }
+
+    /// Returns the random nonce followed by the ciphertext.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, TotpError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| TotpError::CryptoError)?;
+
+        Ok([nonce.as_slice(), &ciphertext].concat())
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, TotpError> {
+        if data.len() < NONCE_LENGTH {
+            return Err(TotpError::CryptoError);
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LENGTH);
+
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| TotpError::CryptoError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email() -> Email {
+        Email::parse("test@example.com".to_owned()).unwrap()
+    }
+
+    #[test]
+    fn test_verify_totp_code() {
+        let secret = generate_totp_secret().unwrap();
+        let code = totp(&secret, &email()).unwrap().generate_current().unwrap();
+
+        assert!(verify_totp_code(&secret, &email(), &code)
+            .unwrap()
+            .is_some());
+        assert!(
+            verify_totp_code(&generate_totp_secret().unwrap(), &email(), &code)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_verify_totp_code_within_skew() {
+        let totp = totp(&generate_totp_secret().unwrap(), &email()).unwrap();
+        let now = 100 * TOTP_STEP_SECONDS + 7;
+
+        let current = totp.generate(now);
+        let previous = totp.generate(now - TOTP_STEP_SECONDS);
+        let next = totp.generate(now + TOTP_STEP_SECONDS);
+        let stale = totp.generate(now - TOTP_STEP_SECONDS * 3);
+
+        assert_eq!(find_step(&totp, &current, now), Some(100));
+        assert_eq!(find_step(&totp, &previous, now), Some(99));
+        assert_eq!(find_step(&totp, &next, now), Some(101));
+        assert_eq!(find_step(&totp, &stale, now), None);
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        let uri = get_otpauth_uri(&generate_totp_secret().unwrap(), &email()).unwrap();
+
+        assert!(uri.starts_with("otpauth://totp/auth-service:test%40example.com?secret="));
+        assert!(uri.contains("issuer=auth-service"));
+    }
+
+    #[test]
+    fn test_secret_cipher_round_trip() {
+        let cipher = SecretCipher::new(&[7; 32]);
+        let secret = generate_totp_secret().unwrap();
+
+        let encrypted = cipher.encrypt(&secret).unwrap();
+        assert_ne!(encrypted[NONCE_LENGTH..], secret[..]);
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), secret);
+
+        let other = SecretCipher::new(&[8; 32]);
+        assert!(other.decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn test_secret_cipher_from_base64() {
+        assert!(SecretCipher::from_base64(&STANDARD.encode([1u8; 32])).is_ok());
+        assert!(SecretCipher::from_base64(&STANDARD.encode([1u8; 16])).is_err());
+        assert!(SecretCipher::from_base64("not base64").is_err());
+    }
+}
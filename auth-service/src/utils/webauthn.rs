@@ -0,0 +1,394 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ciborium::Value;
+use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// COSE identifier of ECDSA with P-256 and SHA-256, the only algorithm we
+/// accept for passkeys.
+pub const COSE_ALG_ES256: i64 = -7;
+pub const PUBLIC_KEY_CREDENTIAL_TYPE: &str = "public-key";
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+const RP_ID_HASH_LENGTH: usize = 32;
+const AAGUID_LENGTH: usize = 16;
+const COORDINATE_LENGTH: usize = 32;
+
+#[derive(Debug, PartialEq)]
+pub enum WebAuthnError {
+    MalformedResponse,
+    CeremonyMismatch,
+    OriginMismatch,
+    RelyingPartyMismatch,
+    UserNotPresent,
+    UserNotVerified,
+    UnsupportedKey,
+    InvalidSignature,
+}
+
+/// The two WebAuthn ceremonies. The browser records which one produced a
+/// response in the client data, so a response can't be replayed for the other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ceremony {
+    Registration,
+    Authentication,
+}
+
+impl AsRef<str> for Ceremony {
+    fn as_ref(&self) -> &str {
+        match self {
+            Ceremony::Registration => "webauthn.create",
+            Ceremony::Authentication => "webauthn.get",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ClientData {
+    #[serde(rename = "type")]
+    ceremony: String,
+    challenge: String,
+    origin: String,
+}
+
+pub fn encode_base64url(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+pub fn decode_base64url(encoded: &str) -> Result<Vec<u8>, WebAuthnError> {
+    URL_SAFE_NO_PAD
+        .decode(encoded.trim_end_matches('='))
+        .map_err(|_| WebAuthnError::MalformedResponse)
+}
+
+/// Checks the ceremony and origin recorded by the browser and returns the
+/// challenge the response was signed for.
+pub fn verify_client_data(
+    client_data_json: &[u8],
+    ceremony: Ceremony,
+    origin: &str,
+) -> Result<Vec<u8>, WebAuthnError> {
+    let client_data: ClientData =
+        serde_json::from_slice(client_data_json).map_err(|_| WebAuthnError::MalformedResponse)?;
+
+    if client_data.ceremony != ceremony.as_ref() {
+        return Err(WebAuthnError::CeremonyMismatch);
+    }
+    if client_data.origin != origin {
+        return Err(WebAuthnError::OriginMismatch);
+    }
+
+    decode_base64url(&client_data.challenge)
+}
+
+/// A newly created credential, taken from the attested credential data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttestedCredential {
+    pub credential_id: Vec<u8>,
+    /// SEC1 encoded, uncompressed P-256 public key.
+    pub public_key: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthenticatorData {
+    pub rp_id_hash: [u8; RP_ID_HASH_LENGTH],
+    pub flags: u8,
+    pub sign_count: u32,
+    pub attested_credential: Option<AttestedCredential>,
+}
+
+impl AuthenticatorData {
+    pub fn parse(data: &[u8]) -> Result<Self, WebAuthnError> {
+        if data.len() < RP_ID_HASH_LENGTH + 5 {
+            return Err(WebAuthnError::MalformedResponse);
+        }
+
+        let (rp_id_hash, rest) = data.split_at(RP_ID_HASH_LENGTH);
+        let flags = rest[0];
+        let sign_count = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]);
+        let rest = &rest[5..];
+
+        let attested_credential = if flags & FLAG_ATTESTED_CREDENTIAL_DATA != 0 {
+            Some(parse_attested_credential(rest)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            rp_id_hash: rp_id_hash
+                .try_into()
+                .map_err(|_| WebAuthnError::MalformedResponse)?,
+            flags,
+            sign_count,
+            attested_credential,
+        })
+    }
+
+    /// Checks the data was produced for our relying party with the user
+    /// present, and, if required, verified by PIN or biometrics.
+    pub fn verify(
+        &self,
+        rp_id: &str,
+        require_user_verification: bool,
+    ) -> Result<(), WebAuthnError> {
+        if self.rp_id_hash[..] != Sha256::digest(rp_id.as_bytes())[..] {
+            return Err(WebAuthnError::RelyingPartyMismatch);
+        }
+        if self.flags & FLAG_USER_PRESENT == 0 {
+            return Err(WebAuthnError::UserNotPresent);
+        }
+        if require_user_verification && !self.user_verified() {
+            return Err(WebAuthnError::UserNotVerified);
+        }
+
+        Ok(())
+    }
+
+    pub fn user_verified(&self) -> bool {
+        self.flags & FLAG_USER_VERIFIED != 0
+    }
+}
+
+fn parse_attested_credential(data: &[u8]) -> Result<AttestedCredential, WebAuthnError> {
+    let data = data
+        .get(AAGUID_LENGTH..)
+        .ok_or(WebAuthnError::MalformedResponse)?;
+    if data.len() < 2 {
+        return Err(WebAuthnError::MalformedResponse);
+    }
+
+    let credential_id_length = u16::from_be_bytes([data[0], data[1]]) as usize;
+    let credential_id = data
+        .get(2..2 + credential_id_length)
+        .ok_or(WebAuthnError::MalformedResponse)?
+        .to_vec();
+    let cose_key = &data[2 + credential_id_length..];
+
+    Ok(AttestedCredential {
+        credential_id,
+        public_key: parse_cose_key(cose_key)?,
+    })
+}
+
+/// Reads an ES256 COSE key and returns it SEC1 encoded.
+fn parse_cose_key(data: &[u8]) -> Result<Vec<u8>, WebAuthnError> {
+    let key: Value = ciborium::from_reader(data).map_err(|_| WebAuthnError::MalformedResponse)?;
+    let entries = key.as_map().ok_or(WebAuthnError::MalformedResponse)?;
+
+    let get = |label: i64| {
+        entries
+            .iter()
+            .find(|(key, _)| key.as_integer() == Some(label.into()))
+            .map(|(_, value)| value)
+    };
+    let get_integer = |label: i64| get(label).and_then(Value::as_integer).map(i128::from);
+    let get_coordinate = |label: i64| {
+        get(label)
+            .and_then(Value::as_bytes)
+            .filter(|bytes| bytes.len() == COORDINATE_LENGTH)
+    };
+
+    // kty EC2, alg ES256, crv P-256
+    if get_integer(1) != Some(2)
+        || get_integer(3) != Some(COSE_ALG_ES256.into())
+        || get_integer(-1) != Some(1)
+    {
+        return Err(WebAuthnError::UnsupportedKey);
+    }
+
+    let x = get_coordinate(-2).ok_or(WebAuthnError::UnsupportedKey)?;
+    let y = get_coordinate(-3).ok_or(WebAuthnError::UnsupportedKey)?;
+
+    let mut public_key = Vec::with_capacity(1 + 2 * COORDINATE_LENGTH);
+    public_key.push(0x04);
+    public_key.extend_from_slice(x);
+    public_key.extend_from_slice(y);
+
+    VerifyingKey::from_sec1_bytes(&public_key).map_err(|_| WebAuthnError::UnsupportedKey)?;
+
+    Ok(public_key)
+}
+
+/// Extracts the authenticator data from an attestation object. The
+/// attestation statement itself is not checked, as we ask browsers for `none`
+/// attestation and don't restrict which authenticators can be used.
+pub fn parse_attestation_object(data: &[u8]) -> Result<AuthenticatorData, WebAuthnError> {
+    let object: Value =
+        ciborium::from_reader(data).map_err(|_| WebAuthnError::MalformedResponse)?;
+
+    let auth_data = object
+        .as_map()
+        .and_then(|entries| {
+            entries
+                .iter()
+                .find(|(key, _)| key.as_text() == Some("authData"))
+        })
+        .and_then(|(_, value)| value.as_bytes())
+        .ok_or(WebAuthnError::MalformedResponse)?;
+
+    AuthenticatorData::parse(auth_data)
+}
+
+/// Verifies an assertion signature, which covers the authenticator data
+/// followed by the SHA-256 hash of the client data.
+pub fn verify_assertion_signature(
+    public_key: &[u8],
+    authenticator_data: &[u8],
+    client_data_json: &[u8],
+    signature: &[u8],
+) -> Result<(), WebAuthnError> {
+    let verifying_key =
+        VerifyingKey::from_sec1_bytes(public_key).map_err(|_| WebAuthnError::UnsupportedKey)?;
+    let signature = Signature::from_der(signature).map_err(|_| WebAuthnError::InvalidSignature)?;
+
+    let mut message = authenticator_data.to_vec();
+    message.extend_from_slice(&Sha256::digest(client_data_json));
+
+    verifying_key
+        .verify(&message, &signature)
+        .map_err(|_| WebAuthnError::InvalidSignature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::ecdsa::{signature::Signer, SigningKey};
+    use rand::rngs::OsRng;
+
+    const ORIGIN: &str = "http://localhost:3000";
+    const RP_ID: &str = "localhost";
+
+    fn cose_key(signing_key: &SigningKey) -> Vec<u8> {
+        let point = signing_key.verifying_key().to_encoded_point(false);
+        let key = Value::Map(vec![
+            (1.into(), 2.into()),
+            (3.into(), COSE_ALG_ES256.into()),
+            ((-1).into(), 1.into()),
+            ((-2).into(), Value::Bytes(point.x().unwrap().to_vec())),
+            ((-3).into(), Value::Bytes(point.y().unwrap().to_vec())),
+        ]);
+        let mut encoded = Vec::new();
+        ciborium::into_writer(&key, &mut encoded).unwrap();
+        encoded
+    }
+
+    fn authenticator_data(flags: u8, sign_count: u32, attested: Option<&[u8]>) -> Vec<u8> {
+        let mut data = Sha256::digest(RP_ID.as_bytes()).to_vec();
+        data.push(flags);
+        data.extend_from_slice(&sign_count.to_be_bytes());
+        if let Some(cose_key) = attested {
+            data.extend_from_slice(&[0; AAGUID_LENGTH]);
+            data.extend_from_slice(&4u16.to_be_bytes());
+            data.extend_from_slice(&[1, 2, 3, 4]);
+            data.extend_from_slice(cose_key);
+        }
+        data
+    }
+
+    #[test]
+    fn test_parses_attested_credential() {
+        let signing_key = SigningKey::random(&mut OsRng);
+        let data = authenticator_data(
+            FLAG_USER_PRESENT | FLAG_ATTESTED_CREDENTIAL_DATA,
+            0,
+            Some(&cose_key(&signing_key)),
+        );
+
+        let parsed = AuthenticatorData::parse(&data).unwrap();
+        let credential = parsed.attested_credential.unwrap();
+
+        assert_eq!(credential.credential_id, vec![1, 2, 3, 4]);
+        assert_eq!(
+            credential.public_key,
+            signing_key
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes()
+                .to_vec()
+        );
+    }
+
+    #[test]
+    fn test_verify_checks_rp_id_and_flags() {
+        let data =
+            AuthenticatorData::parse(&authenticator_data(FLAG_USER_PRESENT, 1, None)).unwrap();
+
+        assert_eq!(data.verify(RP_ID, false), Ok(()));
+        assert_eq!(
+            data.verify(RP_ID, true),
+            Err(WebAuthnError::UserNotVerified)
+        );
+        assert_eq!(
+            data.verify("example.com", false),
+            Err(WebAuthnError::RelyingPartyMismatch)
+        );
+
+        let data = AuthenticatorData::parse(&authenticator_data(0, 1, None)).unwrap();
+        assert_eq!(
+            data.verify(RP_ID, false),
+            Err(WebAuthnError::UserNotPresent)
+        );
+    }
+
+    #[test]
+    fn test_verify_client_data() {
+        let client_data = serde_json::json!({
+            "type": "webauthn.get",
+            "challenge": encode_base64url(b"challenge"),
+            "origin": ORIGIN,
+        })
+        .to_string();
+
+        assert_eq!(
+            verify_client_data(client_data.as_bytes(), Ceremony::Authentication, ORIGIN),
+            Ok(b"challenge".to_vec())
+        );
+        assert_eq!(
+            verify_client_data(client_data.as_bytes(), Ceremony::Registration, ORIGIN),
+            Err(WebAuthnError::CeremonyMismatch)
+        );
+        assert_eq!(
+            verify_client_data(
+                client_data.as_bytes(),
+                Ceremony::Authentication,
+                "http://evil.com"
+            ),
+            Err(WebAuthnError::OriginMismatch)
+        );
+    }
+
+    #[test]
+    fn test_verify_assertion_signature() {
+        let signing_key = SigningKey::random(&mut OsRng);
+        let public_key = signing_key.verifying_key().to_encoded_point(false);
+        let data = authenticator_data(FLAG_USER_PRESENT, 1, None);
+        let client_data = b"{}";
+
+        let mut message = data.clone();
+        message.extend_from_slice(&Sha256::digest(client_data));
+        let signature: Signature = signing_key.sign(&message);
+        let signature = signature.to_der();
+
+        assert_eq!(
+            verify_assertion_signature(
+                public_key.as_bytes(),
+                &data,
+                client_data,
+                signature.as_bytes()
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            verify_assertion_signature(
+                public_key.as_bytes(),
+                &data,
+                b"{\"tampered\":true}",
+                signature.as_bytes()
+            ),
+            Err(WebAuthnError::InvalidSignature)
+        );
+    }
+}
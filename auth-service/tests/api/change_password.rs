@@ -0,0 +1,116 @@
+use crate::helpers::{get_cookie, TestApp};
+use auth_service::{
+    domain::{RefreshToken, RefreshTokenStoreError},
+    utils::constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+};
+use test_helpers::api_test;
+
+#[api_test]
+async fn should_return_200_and_change_password() {
+    let (random_email, _) = app.signup_and_login().await;
+
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "currentPassword": "password123",
+            "newPassword": "newpassword123"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(get_cookie(&response, JWT_COOKIE_NAME).is_some());
+    assert!(get_cookie(&response, REFRESH_TOKEN_COOKIE_NAME).is_some());
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &random_email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &random_email,
+            "password": "newpassword123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[api_test]
+async fn should_revoke_other_sessions_but_keep_current_one() {
+    let (_, response) = app.signup_and_login().await;
+    let old_refresh_token =
+        get_cookie(&response, REFRESH_TOKEN_COOKIE_NAME).expect("No refresh cookie found");
+
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "currentPassword": "password123",
+            "newPassword": "newpassword123"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_eq!(
+        app.refresh_token_store
+            .read()
+            .await
+            .get_token(&RefreshToken::parse(old_refresh_token).unwrap())
+            .await,
+        Err(RefreshTokenStoreError::TokenNotFound)
+    );
+    assert_eq!(app.post_refresh().await.status().as_u16(), 200);
+}
+
+#[api_test]
+async fn should_return_401_if_current_password_is_incorrect() {
+    app.signup_and_login().await;
+
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "currentPassword": "wrongpassword",
+            "newPassword": "newpassword123"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_return_400_if_new_password_is_invalid() {
+    app.signup_and_login().await;
+
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "currentPassword": "password123",
+            "newPassword": "short"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[api_test]
+async fn should_return_400_if_jwt_cookie_is_missing() {
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "currentPassword": "password123",
+            "newPassword": "newpassword123"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[api_test]
+async fn should_return_422_if_malformed_input() {
+    app.signup_and_login().await;
+
+    let test_cases = [
+        serde_json::json!({ "currentPassword": "password123" }),
+        serde_json::json!({ "password": "password123", "newPassword": "newpassword123" }),
+    ];
+
+    for test_case in test_cases.iter() {
+        assert_eq!(
+            app.post_change_password(test_case).await.status().as_u16(),
+            422
+        );
+    }
+}
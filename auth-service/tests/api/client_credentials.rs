@@ -0,0 +1,177 @@
+use crate::{
+    helpers::{TestApp, ADMIN_API_KEY},
+    oauth::error,
+};
+use auth_service::{
+    routes::{ServiceClientResponse, TokenResponse},
+    utils::auth::generate_auth_cookie,
+};
+use reqwest::header::WWW_AUTHENTICATE;
+use test_helpers::api_test;
+
+async fn request_token(app: &TestApp, client: &ServiceClientResponse) -> reqwest::Response {
+    app.http_client
+        .post(format!("{}/token", &app.address))
+        .basic_auth(&client.client_id, Some(&client.client_secret))
+        .form(&[("grant_type", "client_credentials")])
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn access_token(app: &TestApp, client: &ServiceClientResponse) -> String {
+    let response = request_token(app, client).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse")
+        .access_token
+}
+
+async fn user_token(app: &TestApp) -> String {
+    generate_auth_cookie(&app.add_random_user().await)
+        .expect("Failed to generate token")
+        .value()
+        .to_owned()
+}
+
+#[api_test]
+async fn should_issue_token_to_service_client() {
+    let client = app.register_service_client(&["tokens:verify"]).await;
+
+    let response = request_token(&app, &client).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let token = response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse");
+    assert_eq!(token.token_type, "Bearer");
+    assert_eq!(token.scope, "tokens:verify");
+    assert_eq!(token.expires_in, 300);
+    assert!(token.id_token.is_none());
+
+    // Credentials may also be sent in the form.
+    let response = app
+        .post_token(&[
+            ("grant_type", "client_credentials"),
+            ("client_id", &client.client_id),
+            ("client_secret", &client.client_secret),
+            ("scope", "tokens:verify"),
+        ])
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[api_test]
+async fn should_reject_wrong_client_secret() {
+    let mut client = app.register_service_client(&["tokens:verify"]).await;
+    client.client_secret = "wrong-secret".to_owned();
+
+    let response = request_token(&app, &client).await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert!(response.headers().contains_key(WWW_AUTHENTICATE));
+    assert_eq!(error(response).await, "invalid_client");
+
+    let response = app
+        .post_token(&[("grant_type", "client_credentials")])
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_reject_scope_not_granted_to_client() {
+    let client = app.register_service_client(&["tokens:verify"]).await;
+
+    let response = app
+        .post_token(&[
+            ("grant_type", "client_credentials"),
+            ("client_id", &client.client_id),
+            ("client_secret", &client.client_secret),
+            ("scope", "email"),
+        ])
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(error(response).await, "invalid_scope");
+}
+
+#[api_test]
+async fn should_verify_token_for_service_client() {
+    let client = app.register_service_client(&["tokens:verify"]).await;
+    let access_token = access_token(&app, &client).await;
+
+    let response = app
+        .post_verify_token_as_client(
+            &serde_json::json!({ "token": user_token(&app).await }),
+            &access_token,
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // A caller credential that is presented has to be valid.
+    let response = app
+        .post_verify_token_as_client(
+            &serde_json::json!({ "token": user_token(&app).await }),
+            "invalid",
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // User tokens don't identify a service.
+    let response = app
+        .post_verify_token_as_client(
+            &serde_json::json!({ "token": user_token(&app).await }),
+            &user_token(&app).await,
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_require_service_client_if_configured() {
+    let app =
+        TestApp::with_state(|app_state| app_state.with_verify_token_requires_client(true)).await;
+    let client = app.register_service_client(&["tokens:verify"]).await;
+
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": user_token(&app).await }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let access_token = access_token(&app, &client).await;
+    let response = app
+        .post_verify_token_as_client(
+            &serde_json::json!({ "token": user_token(&app).await }),
+            &access_token,
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await;
+}
+
+#[api_test]
+async fn should_validate_service_client_registration() {
+    let response = app
+        .post_admin_service_client(
+            &serde_json::json!({
+                "name": "app-service",
+                "scopes": ["tokens:verify"],
+            }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app
+        .post_admin_service_client(
+            &serde_json::json!({
+                "name": "app-service",
+                "scopes": ["email"],
+            }),
+            Some(ADMIN_API_KEY),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
@@ -0,0 +1,146 @@
+use crate::helpers::{get_cookie, TestApp};
+use auth_service::{
+    domain::Email,
+    utils::{auth::generate_auth_cookie, constants::JWT_COOKIE_NAME},
+};
+use reqwest::Url;
+use test_helpers::api_test;
+
+#[api_test]
+async fn should_return_200_and_delete_account() {
+    let (random_email, response) = app.signup_and_login().await;
+    let token = get_cookie(&response, JWT_COOKIE_NAME).expect("No auth cookie found");
+
+    let response = app
+        .delete_account(&serde_json::json!({ "password": "password123" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &random_email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    assert_eq!(app.post_refresh().await.status().as_u16(), 400);
+}
+
+#[api_test]
+async fn should_reject_tokens_of_other_devices_after_deletion() {
+    let (random_email, _) = app.signup_and_login().await;
+    let other_device_token = generate_auth_cookie(
+        &app.user_store
+            .read()
+            .await
+            .get_user(&Email::parse(random_email.clone()).unwrap())
+            .await
+            .unwrap(),
+    )
+    .unwrap()
+    .value()
+    .to_owned();
+
+    let response = app
+        .delete_account(&serde_json::json!({ "password": "password123" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": &other_device_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // Nor does a new account under the same email accept them.
+    app.post_signup(&serde_json::json!({
+        "email": &random_email,
+        "password": "password123",
+        "requires2FA": false
+    }))
+    .await;
+    app.verify_email(&random_email).await;
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": &other_device_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_purge_pending_2fa_codes() {
+    let random_email = app.signup_random_user(true).await;
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &random_email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let email = Email::parse(random_email).expect("Failed to parse email");
+    let user = app.user_store.read().await.get_user(&email).await.unwrap();
+    let cookie = generate_auth_cookie(&user).unwrap();
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}={}; HttpOnly; SameSite=Lax; Path=/",
+            JWT_COOKIE_NAME,
+            cookie.value()
+        ),
+        &Url::parse(&app.address).expect("Failed to parse URL"),
+    );
+
+    let response = app
+        .delete_account(&serde_json::json!({ "password": "password123" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert!(app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&email)
+        .await
+        .is_err());
+}
+
+#[api_test]
+async fn should_return_401_if_password_is_incorrect() {
+    let (random_email, _) = app.signup_and_login().await;
+
+    let response = app
+        .delete_account(&serde_json::json!({ "password": "wrongpassword" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &random_email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[api_test]
+async fn should_return_400_if_jwt_cookie_is_missing() {
+    let response = app
+        .delete_account(&serde_json::json!({ "password": "password123" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[api_test]
+async fn should_return_422_if_malformed_input() {
+    app.signup_and_login().await;
+
+    let response = app
+        .delete_account(&serde_json::json!({ "pass": "password123" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 422);
+}
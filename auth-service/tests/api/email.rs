@@ -0,0 +1,131 @@
+use crate::{
+    helpers::{get_random_email, TestApp, ADMIN_API_KEY},
+    smtp_sink::SmtpSink,
+};
+use auth_service::{
+    domain::Email,
+    routes::EmailOutboxStatsResponse,
+    services::{SmtpConfig, SmtpEmailClient, SmtpTls},
+};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::RwLock;
+
+/// Builds an app that sends its emails to a local SMTP server on `port`.
+async fn spawn_app(port: u16) -> TestApp {
+    let email_client = SmtpEmailClient::new(SmtpConfig {
+        host: "127.0.0.1".to_owned(),
+        port: Some(port),
+        tls: SmtpTls::None,
+        credentials: None,
+        sender: "Auth Service <no-reply@example.com>".to_owned(),
+        timeout: Duration::from_secs(5),
+    })
+    .expect("Failed to build SMTP email client");
+
+    TestApp::with_state(|mut app_state| {
+        app_state.email_client = Arc::new(RwLock::new(email_client));
+        app_state
+    })
+    .await
+}
+
+async fn signup(app: &TestApp, email: &str, requires_2fa: bool) {
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": "password123",
+            "requires2FA": requires_2fa
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+}
+
+#[tokio::test]
+async fn should_send_verification_email_over_smtp() {
+    let sink = SmtpSink::start().await;
+    let app = spawn_app(sink.port).await;
+    let email = get_random_email();
+
+    signup(&app, &email, false).await;
+    app.deliver_emails().await;
+
+    let messages = sink.messages().await;
+    assert_eq!(messages.len(), 1);
+    let message = &messages[0];
+    assert!(message.contains("From: \"Auth Service\" <no-reply@example.com>"));
+    assert!(message.contains(&format!("To: {}", email)));
+    assert!(message.contains("Subject: Verify your email address"));
+    assert!(message.contains("Content-Type: multipart/alternative"));
+    assert!(message.contains("Content-Type: text/plain"));
+    assert!(message.contains("Content-Type: text/html"));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_send_2fa_code_over_smtp() {
+    let sink = SmtpSink::start().await;
+    let app = spawn_app(sink.port).await;
+    let email = get_random_email();
+    signup(&app, &email, true).await;
+    app.verify_email(&email).await;
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 206);
+    app.deliver_emails().await;
+
+    let (_, code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&Email::parse(email).unwrap())
+        .await
+        .unwrap();
+    let messages = sink.messages().await;
+    let message = messages
+        .iter()
+        .find(|message| message.contains("Subject: Your login code"))
+        .expect("No 2FA email sent");
+    assert!(message.contains(&format!("Your login code is {}.", code.unwrap().as_ref())));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_keep_emails_queued_if_smtp_server_is_unreachable() {
+    // Nothing listens on the port once the listener is dropped.
+    let port = {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let app = spawn_app(port).await;
+    let email = get_random_email();
+    signup(&app, &email, true).await;
+    app.verify_email(&email).await;
+
+    // The login doesn't wait on the email, so it works regardless.
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    app.deliver_emails().await;
+    let stats = app
+        .get_admin_email_outbox(Some(ADMIN_API_KEY))
+        .await
+        .json::<EmailOutboxStatsResponse>()
+        .await
+        .unwrap();
+    assert_eq!(stats.pending, 2);
+    assert_eq!(stats.dead_lettered, 0);
+
+    app.clean_up().await;
+}
@@ -0,0 +1,170 @@
+use crate::{
+    email_api_stub::EmailApiStub,
+    helpers::{get_random_email, TestApp, ADMIN_API_KEY},
+};
+use auth_service::{
+    domain::{Email, EmailClient, EmailClientError, EmailMessage},
+    routes::EmailOutboxStatsResponse,
+    services::{HttpEmailClient, HttpEmailConfig},
+};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::RwLock;
+
+const API_TOKEN: &str = "test-api-token";
+
+fn email_client(stub: &EmailApiStub, max_retries: u32) -> HttpEmailClient {
+    HttpEmailClient::new(HttpEmailConfig {
+        base_url: stub.address.clone(),
+        api_token: API_TOKEN.to_owned(),
+        sender: "Auth Service <no-reply@example.com>".to_owned(),
+        timeout: Duration::from_millis(500),
+        max_retries,
+        retry_delay: Duration::from_millis(10),
+    })
+    .expect("Failed to build HTTP email client")
+}
+
+fn message() -> EmailMessage {
+    EmailMessage {
+        subject: "Subject".to_owned(),
+        text_body: "Text".to_owned(),
+        html_body: "<p>HTML</p>".to_owned(),
+    }
+}
+
+async fn send_email(client: &HttpEmailClient) -> Result<(), EmailClientError> {
+    client
+        .send_email(&Email::parse(get_random_email()).unwrap(), &message())
+        .await
+}
+
+#[tokio::test]
+async fn should_send_verification_email_over_http() {
+    let stub = EmailApiStub::start(&[]).await;
+    let email_client = email_client(&stub, 3);
+    let app = TestApp::with_state(|mut app_state| {
+        app_state.email_client = Arc::new(RwLock::new(email_client));
+        app_state
+    })
+    .await;
+    let email = get_random_email();
+
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": &email,
+            "password": "password123",
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.deliver_emails().await;
+
+    let received = stub.received().await;
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].api_token.as_deref(), Some(API_TOKEN));
+    let body = &received[0].body;
+    assert_eq!(body["From"], "Auth Service <no-reply@example.com>");
+    assert_eq!(body["To"], email.as_str());
+    assert_eq!(body["Subject"], "Verify your email address");
+    assert!(body["TextBody"]
+        .as_str()
+        .unwrap()
+        .contains("/verify-email?token="));
+    assert!(body["HtmlBody"].as_str().unwrap().contains("<html"));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_retry_server_errors() {
+    let stub = EmailApiStub::start(&[500, 503]).await;
+
+    assert_eq!(send_email(&email_client(&stub, 3)).await, Ok(()));
+    assert_eq!(stub.received().await.len(), 3);
+}
+
+#[tokio::test]
+async fn should_give_up_after_max_retries() {
+    let stub = EmailApiStub::start(&[500, 500, 500, 500]).await;
+
+    let result = send_email(&email_client(&stub, 2)).await;
+    assert!(matches!(result, Err(EmailClientError::Unavailable(_))));
+    assert_eq!(stub.received().await.len(), 3);
+}
+
+#[tokio::test]
+async fn should_not_retry_rejected_emails() {
+    let stub = EmailApiStub::start(&[422]).await;
+
+    let result = send_email(&email_client(&stub, 3)).await;
+    assert_eq!(
+        result,
+        Err(EmailClientError::Rejected(
+            "422 Unprocessable Entity: Scripted failure".to_owned()
+        ))
+    );
+    assert_eq!(stub.received().await.len(), 1);
+}
+
+#[tokio::test]
+async fn should_time_out_slow_responses() {
+    let stub = EmailApiStub::start_with_delay(&[], Duration::from_secs(2)).await;
+
+    let result = send_email(&email_client(&stub, 1)).await;
+    assert_eq!(result, Err(EmailClientError::Timeout));
+    assert_eq!(stub.received().await.len(), 2);
+}
+
+#[tokio::test]
+async fn should_report_unreachable_api() {
+    // Nothing listens on the port once the listener is dropped.
+    let address = {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    };
+    let client = HttpEmailClient::new(HttpEmailConfig {
+        base_url: address,
+        api_token: API_TOKEN.to_owned(),
+        sender: "no-reply@example.com".to_owned(),
+        timeout: Duration::from_millis(500),
+        max_retries: 0,
+        retry_delay: Duration::from_millis(10),
+    })
+    .unwrap();
+
+    let result = send_email(&client).await;
+    assert!(matches!(result, Err(EmailClientError::Unavailable(_))));
+}
+
+#[tokio::test]
+async fn should_dead_letter_rejected_emails() {
+    let stub = EmailApiStub::start(&[422]).await;
+    let email_client = email_client(&stub, 3);
+    let app = TestApp::with_state(|mut app_state| {
+        app_state.email_client = Arc::new(RwLock::new(email_client));
+        app_state
+    })
+    .await;
+
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": get_random_email(),
+            "password": "password123",
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.deliver_emails().await;
+
+    let stats = app
+        .get_admin_email_outbox(Some(ADMIN_API_KEY))
+        .await
+        .json::<EmailOutboxStatsResponse>()
+        .await
+        .unwrap();
+    assert_eq!(stats.pending, 0);
+    assert_eq!(stats.dead_lettered, 1);
+    assert_eq!(stub.received().await.len(), 1);
+
+    app.clean_up().await;
+}
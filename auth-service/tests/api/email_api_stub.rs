@@ -0,0 +1,83 @@
+use axum::{
+    extract::State, http::HeaderMap, http::StatusCode, response::IntoResponse, routing::post, Json,
+    Router,
+};
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+use tokio::{net::TcpListener, sync::Mutex};
+
+/// An email request the stub received.
+#[derive(Clone, Debug)]
+pub struct ReceivedEmail {
+    pub api_token: Option<String>,
+    pub body: serde_json::Value,
+}
+
+struct StubState {
+    statuses: Mutex<VecDeque<u16>>,
+    delay: Duration,
+    received: Mutex<Vec<ReceivedEmail>>,
+}
+
+/// A local stand-in for a transactional email API. It records every email
+/// and answers with scripted statuses, so tests can make it fail.
+pub struct EmailApiStub {
+    pub address: String,
+    state: Arc<StubState>,
+}
+
+impl EmailApiStub {
+    /// Answers requests with `statuses` in turn, and with 200 once they run
+    /// out.
+    pub async fn start(statuses: &[u16]) -> Self {
+        Self::start_with_delay(statuses, Duration::ZERO).await
+    }
+
+    /// Like `start`, but waits `delay` before answering each request.
+    pub async fn start_with_delay(statuses: &[u16], delay: Duration) -> Self {
+        let state = Arc::new(StubState {
+            statuses: Mutex::new(statuses.iter().copied().collect()),
+            delay,
+            received: Mutex::new(Vec::new()),
+        });
+        let router = Router::new()
+            .route("/email", post(send_email))
+            .with_state(state.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind email API stub");
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        Self { address, state }
+    }
+
+    pub async fn received(&self) -> Vec<ReceivedEmail> {
+        self.state.received.lock().await.clone()
+    }
+}
+
+async fn send_email(
+    State(state): State<Arc<StubState>>,
+    headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    state.received.lock().await.push(ReceivedEmail {
+        api_token: headers
+            .get("X-Postmark-Server-Token")
+            .and_then(|token| token.to_str().ok())
+            .map(str::to_owned),
+        body,
+    });
+    tokio::time::sleep(state.delay).await;
+
+    let status = state.statuses.lock().await.pop_front().unwrap_or(200);
+    let status = StatusCode::from_u16(status).unwrap();
+    let body = if status.is_success() {
+        serde_json::json!({ "ErrorCode": 0, "Message": "OK" })
+    } else {
+        serde_json::json!({ "ErrorCode": 300, "Message": "Scripted failure" })
+    };
+
+    (status, Json(body))
+}
@@ -0,0 +1,51 @@
+use crate::helpers::{get_random_email, TestApp, ADMIN_API_KEY};
+use auth_service::routes::EmailOutboxStatsResponse;
+use test_helpers::api_test;
+
+#[api_test]
+async fn should_queue_emails_until_delivered() {
+    let random_email = get_random_email();
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": &random_email,
+            "password": "password123",
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = app.get_admin_email_outbox(Some(ADMIN_API_KEY)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let stats = response
+        .json::<EmailOutboxStatsResponse>()
+        .await
+        .expect("Failed to parse the outbox stats");
+    assert_eq!(stats.pending, 1);
+    assert_eq!(stats.dead_lettered, 0);
+    assert!(stats.oldest_pending_at.is_some());
+    assert!(app.email_client.sent_emails().is_empty());
+
+    app.deliver_emails().await;
+
+    let stats = app
+        .get_admin_email_outbox(Some(ADMIN_API_KEY))
+        .await
+        .json::<EmailOutboxStatsResponse>()
+        .await
+        .expect("Failed to parse the outbox stats");
+    assert_eq!(stats.pending, 0);
+    assert_eq!(stats.oldest_pending_at, None);
+    assert_eq!(
+        app.last_email_to(&random_email).await.subject,
+        "Verify your email address"
+    );
+}
+
+#[api_test]
+async fn should_require_admin_key_for_outbox_stats() {
+    let response = app.get_admin_email_outbox(None).await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app.get_admin_email_outbox(Some("wrong-key")).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
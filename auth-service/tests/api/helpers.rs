@@ -1,8 +1,24 @@
 use auth_service::{
-    app_state::{AppState, BannedTokenStoreType, TwoFACodeStoreType, UserStoreType},
+    app_state::{
+        AppState, BannedTokenStoreType, OneTimeTokenStoreType, RateLimitStoreType,
+        RefreshTokenStoreType, SessionStoreType, TwoFACodeStoreType, UserStoreType,
+    },
+    domain::{Email, Password, User},
     get_postgres_pool, get_redis_client,
-    services::{MockEmailClient, PostgresUserStore, RedisBannedTokenStore, RedisTwoFACodeStore},
-    utils::{constants::test, DATABASE_URL, REDIS_HOST_NAME},
+    routes::ServiceClientResponse,
+    services::{
+        CapturingEmailClient, EmailOutboxWorker, HashmapRateLimitStore, PostgresEmailOutboxStore,
+        PostgresLinkedIdentityStore, PostgresOAuthClientStore, PostgresPasskeyStore,
+        PostgresRecoveryCodeStore, PostgresServiceClientStore, PostgresTotpSecretStore,
+        PostgresUserStore, RedisAuthorizationCodeStore, RedisBannedTokenStore,
+        RedisOneTimeTokenStore, RedisRefreshTokenStore, RedisSessionStore, RedisTwoFACodeStore,
+        SentEmail,
+    },
+    utils::{
+        constants::{env, test, PUBLIC_BASE_URL},
+        totp::SecretCipher,
+        DATABASE_URL, REDIS_HOST_NAME,
+    },
     Application,
 };
 use reqwest::cookie::Jar;
@@ -14,33 +30,106 @@ use std::{str::FromStr, sync::Arc};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+pub const ADMIN_API_KEY: &str = "test-admin-api-key";
+pub const USER_AGENT: &str = "auth-service-tests";
+
 pub struct TestApp {
     pub address: String,
     pub cookie_jar: Arc<Jar>,
+    pub user_store: UserStoreType,
     pub banned_token_store: BannedTokenStoreType,
     pub two_fa_code_store: TwoFACodeStoreType,
+    pub refresh_token_store: RefreshTokenStoreType,
+    pub one_time_token_store: OneTimeTokenStoreType,
+    pub rate_limit_store: RateLimitStoreType,
+    /// Holds every email the app sends, unless a test swaps the client.
+    pub email_client: CapturingEmailClient,
+    pub email_outbox_worker: EmailOutboxWorker,
     pub http_client: reqwest::Client,
     pub db_name: String,
 }
 
 impl TestApp {
     pub async fn new() -> Self {
+        Self::with_state(|app_state| app_state).await
+    }
+
+    /// Builds an app whose state is adjusted by `configure` first.
+    pub async fn with_state(configure: impl FnOnce(AppState) -> AppState) -> Self {
+        // Read lazily by the admin routes, so every test sets the same value
+        // before its app can serve a request.
+        std::env::set_var(env::ADMIN_API_KEY_ENV_VAR, ADMIN_API_KEY);
+
         let db_name = Uuid::new_v4().to_string();
         let pg_pool = configure_postgresql(&db_name).await;
         let redis_connection = Arc::new(RwLock::new(configure_redis()));
 
-        let user_store: UserStoreType = Arc::new(RwLock::new(PostgresUserStore::new(pg_pool)));
+        let user_store: UserStoreType =
+            Arc::new(RwLock::new(PostgresUserStore::new(pg_pool.clone())));
         let banned_token_store: BannedTokenStoreType = Arc::new(RwLock::new(
             RedisBannedTokenStore::new(redis_connection.clone()),
         ));
-        let two_fa_code_store = Arc::new(RwLock::new(RedisTwoFACodeStore::new(redis_connection)));
-        let email_client = Arc::new(RwLock::new(MockEmailClient));
+        let two_fa_code_store = Arc::new(RwLock::new(RedisTwoFACodeStore::new(
+            redis_connection.clone(),
+        )));
+        let refresh_token_store: RefreshTokenStoreType = Arc::new(RwLock::new(
+            RedisRefreshTokenStore::new(redis_connection.clone()),
+        ));
+        let one_time_token_store: OneTimeTokenStoreType = Arc::new(RwLock::new(
+            RedisOneTimeTokenStore::new(redis_connection.clone()),
+        ));
+        let session_store: SessionStoreType = Arc::new(RwLock::new(RedisSessionStore::new(
+            redis_connection.clone(),
+        )));
+        let authorization_code_store = Arc::new(RwLock::new(RedisAuthorizationCodeStore::new(
+            redis_connection,
+        )));
+        let totp_secret_store = Arc::new(RwLock::new(PostgresTotpSecretStore::new(
+            pg_pool.clone(),
+            SecretCipher::new(&rand::random()),
+        )));
+        let recovery_code_store =
+            Arc::new(RwLock::new(PostgresRecoveryCodeStore::new(pg_pool.clone())));
+        let passkey_store = Arc::new(RwLock::new(PostgresPasskeyStore::new(pg_pool.clone())));
+        let oauth_client_store =
+            Arc::new(RwLock::new(PostgresOAuthClientStore::new(pg_pool.clone())));
+        let service_client_store = Arc::new(RwLock::new(PostgresServiceClientStore::new(
+            pg_pool.clone(),
+        )));
+        let linked_identity_store = Arc::new(RwLock::new(PostgresLinkedIdentityStore::new(
+            pg_pool.clone(),
+        )));
+        let email_outbox_store = Arc::new(RwLock::new(PostgresEmailOutboxStore::new(pg_pool)));
+        // Every test client connects from 127.0.0.1, so per-IP counters in the
+        // shared Redis would leak between tests running in parallel.
+        let rate_limit_store: RateLimitStoreType =
+            Arc::new(RwLock::new(HashmapRateLimitStore::new()));
+        let email_client = CapturingEmailClient::new();
 
         let app_state = AppState::new(
-            user_store,
+            user_store.clone(),
             banned_token_store.clone(),
             two_fa_code_store.clone(),
-            email_client,
+            Arc::new(RwLock::new(email_client.clone())),
+            refresh_token_store.clone(),
+            one_time_token_store.clone(),
+            totp_secret_store,
+            recovery_code_store,
+            passkey_store,
+            rate_limit_store.clone(),
+            session_store,
+            oauth_client_store,
+            authorization_code_store,
+            service_client_store,
+            linked_identity_store,
+            email_outbox_store,
+        );
+
+        let app_state = configure(app_state);
+        // Tests deliver queued emails themselves, so they know when it's done.
+        let email_outbox_worker = EmailOutboxWorker::new(
+            app_state.email_outbox_store.clone(),
+            app_state.email_client.clone(),
         );
 
         let app = Application::build(app_state, test::APP_ADDRESS)
@@ -57,14 +146,23 @@ impl TestApp {
         let cookie_jar = Arc::new(Jar::default());
         let http_client = reqwest::Client::builder()
             .cookie_provider(cookie_jar.clone())
+            .user_agent(USER_AGENT)
+            // The OAuth2 tests inspect the redirects themselves.
+            .redirect(reqwest::redirect::Policy::none())
             .build()
             .unwrap();
 
         Self {
             address,
             cookie_jar,
+            user_store,
             banned_token_store,
             two_fa_code_store,
+            refresh_token_store,
+            one_time_token_store,
+            rate_limit_store,
+            email_client,
+            email_outbox_worker,
             http_client,
             db_name,
         }
@@ -82,6 +180,34 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn get_jwks(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/.well-known/jwks.json", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_openid_configuration(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!(
+                "{}/.well-known/openid-configuration",
+                &self.address
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_userinfo(&self, access_token: Option<&str>) -> reqwest::Response {
+        let mut request = self.http_client.get(format!("{}/userinfo", &self.address));
+        if let Some(access_token) = access_token {
+            request = request.bearer_auth(access_token);
+        }
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
     pub async fn post_signup<Body>(&self, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
@@ -94,6 +220,155 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    /// Delivers the emails that are due, like the outbox worker would.
+    pub async fn deliver_emails(&self) {
+        self.email_outbox_worker
+            .deliver_due()
+            .await
+            .expect("Failed to deliver emails");
+    }
+
+    pub async fn last_email_to(&self, email: &str) -> SentEmail {
+        self.deliver_emails().await;
+        self.email_client
+            .last_email_to(&Email::parse(email.to_owned()).expect("Failed to parse email"))
+            .expect("No email was sent")
+    }
+
+    /// Finds the link to `path` in the last email to `email`.
+    pub async fn get_emailed_link(&self, email: &str, path: &str) -> String {
+        self.last_email_to(email)
+            .await
+            .text_body
+            .split_whitespace()
+            .find(|word| word.contains(path))
+            .expect("No link in the email")
+            .to_owned()
+    }
+
+    /// Opens a link from an email, which points at `PUBLIC_BASE_URL`, on the
+    /// test server.
+    pub async fn follow_link(&self, link: &str) -> reqwest::Response {
+        let path = link
+            .strip_prefix(PUBLIC_BASE_URL.as_str())
+            .expect("Link points at another site");
+
+        self.http_client
+            .get(format!("{}{}", &self.address, path))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Reads the code from the last login email, like the user would.
+    pub async fn get_2fa_code(&self, email: &str) -> String {
+        self.deliver_emails().await;
+        let email = self
+            .email_client
+            .last_email_with_subject(
+                &Email::parse(email.to_owned()).expect("Failed to parse email"),
+                "Your login code",
+            )
+            .expect("No 2FA email was sent");
+
+        email
+            .text_body
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|word| word.len() == 6)
+            .expect("No code in the 2FA email")
+            .to_owned()
+    }
+
+    /// Marks the account as verified without going through the emailed link.
+    pub async fn verify_email(&self, email: &str) {
+        self.user_store
+            .write()
+            .await
+            .mark_email_verified(&Email::parse(email.to_owned()).expect("Failed to parse email"))
+            .await
+            .expect("Failed to verify email");
+    }
+
+    /// Signs up a user with a random, verified email and the password
+    /// `password123`.
+    pub async fn signup_random_user(&self, requires_2fa: bool) -> String {
+        let random_email = get_random_email();
+
+        let response = self
+            .post_signup(&serde_json::json!({
+                "email": &random_email,
+                "password": "password123",
+                "requires2FA": requires_2fa
+            }))
+            .await;
+        assert_eq!(response.status().as_u16(), 201);
+        self.verify_email(&random_email).await;
+
+        random_email
+    }
+
+    /// Logs in a user without 2FA. The client keeps the session cookies, and
+    /// the response is returned to read them.
+    pub async fn login_user(&self, email: &str) -> reqwest::Response {
+        let response = self
+            .post_login(&serde_json::json!({
+                "email": email,
+                "password": "password123",
+            }))
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+
+        response
+    }
+
+    /// Signs up a user without 2FA and logs them in.
+    pub async fn signup_and_login(&self) -> (String, reqwest::Response) {
+        let random_email = self.signup_random_user(false).await;
+        let response = self.login_user(&random_email).await;
+
+        (random_email, response)
+    }
+
+    /// Stores a new user directly, for tests that sign its tokens themselves.
+    pub async fn add_random_user(&self) -> User {
+        let user = User::new(
+            Email::parse(get_random_email()).expect("Failed to parse email"),
+            Password::parse("password123".to_owned()).expect("Failed to parse password"),
+            false,
+        );
+        self.user_store
+            .write()
+            .await
+            .add_user(user.clone())
+            .await
+            .expect("Failed to add user");
+        user
+    }
+
+    pub async fn post_verify_email<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/verify-email", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_resend_verification_email<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/verify-email/resend", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn post_login<Body>(&self, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
@@ -106,6 +381,186 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn get_social_login(
+        &self,
+        provider: &str,
+        params: &[(&str, &str)],
+    ) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/login/{}", &self.address, provider))
+            .query(params)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_social_login_callback(
+        &self,
+        provider: &str,
+        params: &[(&str, &str)],
+    ) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/login/{}/callback", &self.address, provider))
+            .query(params)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_unlock_account<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/unlock-account", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_locked_accounts(&self, api_key: Option<&str>) -> reqwest::Response {
+        let mut request = self
+            .http_client
+            .get(format!("{}/admin/locked-accounts", &self.address));
+        if let Some(api_key) = api_key {
+            request = request.bearer_auth(api_key);
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_email_outbox(&self, api_key: Option<&str>) -> reqwest::Response {
+        let mut request = self
+            .http_client
+            .get(format!("{}/admin/email-outbox", &self.address));
+        if let Some(api_key) = api_key {
+            request = request.bearer_auth(api_key);
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn post_admin_unlock_account<Body>(
+        &self,
+        body: &Body,
+        api_key: Option<&str>,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let mut request = self
+            .http_client
+            .post(format!("{}/admin/unlock-account", &self.address))
+            .json(body);
+        if let Some(api_key) = api_key {
+            request = request.bearer_auth(api_key);
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn post_admin_oauth_client<Body>(
+        &self,
+        body: &Body,
+        api_key: Option<&str>,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let mut request = self
+            .http_client
+            .post(format!("{}/admin/oauth-clients", &self.address))
+            .json(body);
+        if let Some(api_key) = api_key {
+            request = request.bearer_auth(api_key);
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn post_admin_service_client<Body>(
+        &self,
+        body: &Body,
+        api_key: Option<&str>,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let mut request = self
+            .http_client
+            .post(format!("{}/admin/service-clients", &self.address))
+            .json(body);
+        if let Some(api_key) = api_key {
+            request = request.bearer_auth(api_key);
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    /// Registers a service client allowed the given scopes.
+    pub async fn register_service_client(&self, scopes: &[&str]) -> ServiceClientResponse {
+        let response = self
+            .post_admin_service_client(
+                &serde_json::json!({ "name": "app-service", "scopes": scopes }),
+                Some(ADMIN_API_KEY),
+            )
+            .await;
+        assert_eq!(response.status().as_u16(), 201);
+
+        response
+            .json::<ServiceClientResponse>()
+            .await
+            .expect("Could not deserialize response body to ServiceClientResponse")
+    }
+
+    pub async fn get_authorize(&self, params: &[(&str, &str)]) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/authorize", &self.address))
+            .query(params)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_authorize(&self, params: &[(&str, &str)]) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/authorize", &self.address))
+            .form(params)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_token(&self, params: &[(&str, &str)]) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/token", &self.address))
+            .form(params)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_revoke(&self, params: &[(&str, &str)]) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/revoke", &self.address))
+            .form(params)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_sessions(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/sessions", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_session(&self, id: &str) -> reqwest::Response {
+        self.http_client
+            .delete(format!("{}/sessions/{}", &self.address, id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn post_logout(&self) -> reqwest::Response {
         self.http_client
             .post(format!("{}/logout", &self.address))
@@ -114,6 +569,162 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn post_logout_all(&self) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/logout-all", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_refresh(&self) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/refresh", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_change_password<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/change-password", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_account<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .delete(format!("{}/account", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_totp_enroll(&self) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/totp/enroll", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_totp_confirm<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/totp/confirm", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_two_fa_method<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/2fa/method", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_recovery_codes(&self) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/recovery-codes", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_regenerate_recovery_codes(&self) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/recovery-codes/regenerate", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_passkey_register_start(&self) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/passkeys/register/start", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_passkey_register_finish<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/passkeys/register/finish", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_passkey_login_start<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/passkeys/login/start", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_passkey_login_finish<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/passkeys/login/finish", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_password_reset_request<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/password-reset/request", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_password_reset_confirm<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/password-reset/confirm", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn post_verify_token<Body>(&self, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
@@ -126,6 +737,24 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    /// `/verify-token` called by a service client with its access token.
+    pub async fn post_verify_token_as_client<Body>(
+        &self,
+        body: &Body,
+        access_token: &str,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/verify-token", &self.address))
+            .bearer_auth(access_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn post_verify_2fa<Body>(&self, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
@@ -137,6 +766,18 @@ impl TestApp {
             .await
             .expect("Failed to execute request.")
     }
+
+    pub async fn post_resend_2fa<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/resend-2fa", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
 }
 
 fn configure_redis() -> redis::Connection {
@@ -150,6 +791,13 @@ pub fn get_random_email() -> String {
     format!("{}@example.com", Uuid::new_v4())
 }
 
+pub fn get_cookie(response: &reqwest::Response, name: &str) -> Option<String> {
+    response
+        .cookies()
+        .find(|cookie| cookie.name() == name)
+        .map(|cookie| cookie.value().to_owned())
+}
+
 async fn configure_postgresql(db_name: &str) -> PgPool {
     let postgresql_conn_url = DATABASE_URL.to_owned();
 
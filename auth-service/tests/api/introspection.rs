@@ -0,0 +1,180 @@
+use crate::{
+    helpers::{get_cookie, TestApp},
+    oauth::error,
+};
+use auth_service::{
+    routes::{IntrospectionResponse, ServiceClientResponse, TokenResponse},
+    utils::constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+};
+use test_helpers::api_test;
+
+async fn introspect(
+    app: &TestApp,
+    client: &ServiceClientResponse,
+    token: &str,
+) -> reqwest::Response {
+    app.http_client
+        .post(format!("{}/introspect", &app.address))
+        .basic_auth(&client.client_id, Some(&client.client_secret))
+        .form(&[("token", token)])
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn introspection(
+    app: &TestApp,
+    client: &ServiceClientResponse,
+    token: &str,
+) -> IntrospectionResponse {
+    let response = introspect(app, client, token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response
+        .json::<IntrospectionResponse>()
+        .await
+        .expect("Could not deserialize response body to IntrospectionResponse")
+}
+
+/// Logs a new user in, returning their email and access and refresh tokens.
+async fn login(app: &TestApp) -> (String, String, String) {
+    let (random_email, response) = app.signup_and_login().await;
+    let cookie = |name: &str| get_cookie(&response, name).expect("No cookie found");
+
+    (
+        random_email.clone(),
+        cookie(JWT_COOKIE_NAME),
+        cookie(REFRESH_TOKEN_COOKIE_NAME),
+    )
+}
+
+#[api_test]
+async fn should_introspect_access_token() {
+    let client = app.register_service_client(&["tokens:introspect"]).await;
+    let (email, access_token, _) = login(&app).await;
+
+    let response = introspection(&app, &client, &access_token).await;
+    assert!(response.active);
+    assert_eq!(response.username.as_deref(), Some(email.as_str()));
+    assert_eq!(response.token_type.as_deref(), Some("Bearer"));
+    assert!(response.sub.is_some());
+    assert!(response.exp.is_some());
+    assert!(response.client_id.is_none());
+
+    let response = introspection(&app, &client, "not-a-token").await;
+    assert!(!response.active);
+    assert!(response.sub.is_none());
+}
+
+#[api_test]
+async fn should_introspect_service_token() {
+    let client = app.register_service_client(&["tokens:introspect"]).await;
+    let response = app
+        .post_token(&[
+            ("grant_type", "client_credentials"),
+            ("client_id", &client.client_id),
+            ("client_secret", &client.client_secret),
+        ])
+        .await;
+    let access_token = response.json::<TokenResponse>().await.unwrap().access_token;
+
+    // A service token also authenticates the caller.
+    let response = app
+        .http_client
+        .post(format!("{}/introspect", &app.address))
+        .bearer_auth(&access_token)
+        .form(&[("token", &access_token)])
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let response = response.json::<IntrospectionResponse>().await.unwrap();
+    assert!(response.active);
+    assert_eq!(response.client_id, Some(client.client_id));
+    assert_eq!(response.scope.as_deref(), Some("tokens:introspect"));
+    assert!(response.username.is_none());
+}
+
+#[api_test]
+async fn should_require_introspection_scope() {
+    let (_, access_token, _) = login(&app).await;
+
+    let response = app
+        .http_client
+        .post(format!("{}/introspect", &app.address))
+        .form(&[("token", &access_token)])
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(error(response).await, "invalid_client");
+
+    let client = app.register_service_client(&["tokens:verify"]).await;
+    let response = introspect(&app, &client, &access_token).await;
+    assert_eq!(response.status().as_u16(), 403);
+    assert_eq!(error(response).await, "insufficient_scope");
+}
+
+#[api_test]
+async fn should_revoke_access_token() {
+    let client = app.register_service_client(&["tokens:introspect"]).await;
+    let (_, access_token, refresh_token) = login(&app).await;
+
+    let response = app
+        .post_revoke(&[
+            ("token", &access_token),
+            ("token_type_hint", "access_token"),
+        ])
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert!(!introspection(&app, &client, &access_token).await.active);
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": &access_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // The refresh token outlives the access token it was issued with.
+    assert!(introspection(&app, &client, &refresh_token).await.active);
+}
+
+#[api_test]
+async fn should_revoke_refresh_token_with_its_session() {
+    let client = app.register_service_client(&["tokens:introspect"]).await;
+    let (email, access_token, refresh_token) = login(&app).await;
+
+    let response = introspection(&app, &client, &refresh_token).await;
+    assert!(response.active);
+    assert_eq!(response.token_type.as_deref(), Some("refresh_token"));
+    assert_eq!(response.username, Some(email));
+
+    let response = app.post_revoke(&[("token", &refresh_token)]).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert!(!introspection(&app, &client, &refresh_token).await.active);
+    assert!(!introspection(&app, &client, &access_token).await.active);
+    assert_eq!(app.post_refresh().await.status().as_u16(), 401);
+    assert!(app
+        .banned_token_store
+        .read()
+        .await
+        .contains_token(refresh_token)
+        .await
+        .unwrap());
+}
+
+#[api_test]
+async fn should_accept_revocation_of_unknown_tokens() {
+    let response = app.post_revoke(&[("token", "not-a-token")]).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_revoke(&[("token", "not-a-token"), ("token_type_hint", "id_token")])
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(error(response).await, "unsupported_token_type");
+
+    let response = app.post_revoke(&[]).await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(error(response).await, "invalid_request");
+}
@@ -0,0 +1,17 @@
+use crate::helpers::TestApp;
+use jsonwebtoken::jwk::JwkSet;
+use test_helpers::api_test;
+
+#[api_test]
+async fn should_return_200_with_jwk_set() {
+    let response = app.get_jwks().await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let jwks = response
+        .json::<JwkSet>()
+        .await
+        .expect("Could not deserialize response body to JwkSet");
+
+    // The test suite signs with the shared JWT_SECRET, which must never be published.
+    assert!(jwks.keys.is_empty());
+}
@@ -0,0 +1,120 @@
+use crate::helpers::{get_random_email, TestApp};
+use serde_json::json;
+use test_helpers::api_test;
+
+async fn signup(app: &TestApp, body: serde_json::Value, accept_language: &str) {
+    let response = app
+        .http_client
+        .post(format!("{}/signup", &app.address))
+        .header("Accept-Language", accept_language)
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201, "Failed to signup new user");
+}
+
+#[api_test]
+async fn should_send_emails_in_the_users_locale() {
+    let random_email = get_random_email();
+    signup(
+        &app,
+        json!({
+            "email": &random_email,
+            "password": "password123",
+            "requires2FA": false,
+            "locale": "de"
+        }),
+        "fr",
+    )
+    .await;
+
+    let email = app.last_email_to(&random_email).await;
+    assert_eq!(email.subject, "Bestätigen Sie Ihre E-Mail-Adresse");
+    assert!(email.html_body.contains("<html lang=\"de\">"));
+}
+
+#[api_test]
+async fn should_follow_accept_language_without_user_locale() {
+    let random_email = get_random_email();
+    signup(
+        &app,
+        json!({
+            "email": &random_email,
+            "password": "password123",
+            "requires2FA": false
+        }),
+        "fr-CH, fr;q=0.9, en;q=0.8",
+    )
+    .await;
+
+    let email = app.last_email_to(&random_email).await;
+    assert_eq!(email.subject, "Vérifiez votre adresse e-mail");
+
+    let response = app
+        .http_client
+        .post(format!("{}/password-reset/request", &app.address))
+        .header("Accept-Language", "de-AT")
+        .json(&json!({ "email": &random_email }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let email = app.last_email_to(&random_email).await;
+    assert_eq!(email.subject, "Setzen Sie Ihr Passwort zurück");
+}
+
+#[api_test]
+async fn should_fall_back_to_english_for_unsupported_languages() {
+    let random_email = get_random_email();
+    signup(
+        &app,
+        json!({
+            "email": &random_email,
+            "password": "password123",
+            "requires2FA": false
+        }),
+        "es-ES, it;q=0.5",
+    )
+    .await;
+
+    let email = app.last_email_to(&random_email).await;
+    assert_eq!(email.subject, "Verify your email address");
+}
+
+#[api_test]
+async fn should_send_security_alert_when_password_changes() {
+    let random_email = get_random_email();
+    signup(
+        &app,
+        json!({
+            "email": &random_email,
+            "password": "password123",
+            "requires2FA": false,
+            "locale": "fr"
+        }),
+        "en",
+    )
+    .await;
+    app.verify_email(&random_email).await;
+    let response = app
+        .post_login(&json!({
+            "email": &random_email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_change_password(&json!({
+            "currentPassword": "password123",
+            "newPassword": "password456",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let email = app.last_email_to(&random_email).await;
+    assert_eq!(email.subject, "Votre mot de passe a été modifié");
+    assert!(email.text_body.contains("127.0.0.1"));
+}
@@ -0,0 +1,183 @@
+use crate::helpers::{get_random_email, TestApp, ADMIN_API_KEY};
+use auth_service::{
+    domain::{AccountLock, Email, FailedAttempts, OneTimeToken, TokenPurpose},
+    routes::LockedAccountsResponse,
+    utils::rate_limit::{RateLimitKey, ACCOUNT_LOCK_THRESHOLD, ACCOUNT_RATE_LIMIT},
+};
+use chrono::{Duration, Utc};
+use test_helpers::api_test;
+
+async fn login(app: &TestApp, email: &Email, password: &str) -> reqwest::Response {
+    app.post_login(&serde_json::json!({
+        "email": email.as_ref(),
+        "password": password,
+    }))
+    .await
+}
+
+async fn lock(app: &TestApp, email: &Email) {
+    app.user_store
+        .write()
+        .await
+        .lock_user(
+            email,
+            AccountLock {
+                locked_until: Utc::now() + Duration::hours(1),
+                reason: "Too many failed login attempts".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+}
+
+/// Fails the last login before the account gets locked.
+async fn fail_until_locked(app: &TestApp, email: &Email) {
+    // Skip the throttled failures in between, as waiting them out takes
+    // minutes.
+    app.rate_limit_store
+        .write()
+        .await
+        .set_attempts(
+            &RateLimitKey::Account(email).key(),
+            FailedAttempts {
+                count: ACCOUNT_LOCK_THRESHOLD - 1,
+                locked_until: None,
+            },
+            ACCOUNT_RATE_LIMIT.window_seconds,
+        )
+        .await
+        .unwrap();
+
+    let response = login(app, email, "wrong-password").await;
+    assert_eq!(response.status().as_u16(), 423);
+}
+
+#[api_test]
+async fn should_lock_account_after_repeated_failures() {
+    let email = Email::parse(app.signup_random_user(false).await).unwrap();
+    fail_until_locked(&app, &email).await;
+
+    let user = app.user_store.read().await.get_user(&email).await.unwrap();
+    assert!(user.is_locked());
+}
+
+#[api_test]
+async fn should_return_423_if_account_is_locked() {
+    let email = Email::parse(app.signup_random_user(false).await).unwrap();
+    lock(&app, &email).await;
+
+    let response = login(&app, &email, "password123").await;
+    assert_eq!(response.status().as_u16(), 423);
+}
+
+#[api_test]
+async fn should_unlock_account_with_emailed_token() {
+    let email = Email::parse(app.signup_random_user(false).await).unwrap();
+    lock(&app, &email).await;
+
+    let token = OneTimeToken::default();
+    app.one_time_token_store
+        .write()
+        .await
+        .add_token(token.clone(), TokenPurpose::AccountUnlock, email.clone())
+        .await
+        .unwrap();
+
+    let response = app
+        .post_unlock_account(&serde_json::json!({ "token": token.as_ref() }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = login(&app, &email, "password123").await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // The token is single use.
+    let response = app
+        .post_unlock_account(&serde_json::json!({ "token": token.as_ref() }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_unlock_account_when_following_emailed_link() {
+    let random_email = app.signup_random_user(false).await;
+    let email = Email::parse(random_email.clone()).unwrap();
+    fail_until_locked(&app, &email).await;
+
+    let link = app
+        .get_emailed_link(&random_email, "/unlock-account?token=")
+        .await;
+    let response = app.follow_link(&link).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = login(&app, &email, "password123").await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // The link can only be used once.
+    let response = app.follow_link(&link).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_list_and_unlock_locked_accounts_as_admin() {
+    let email = Email::parse(app.signup_random_user(false).await).unwrap();
+    lock(&app, &email).await;
+
+    let response = app.get_admin_locked_accounts(Some(ADMIN_API_KEY)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let locked_accounts = response
+        .json::<LockedAccountsResponse>()
+        .await
+        .expect("Could not deserialize response body to LockedAccountsResponse")
+        .locked_accounts;
+    assert_eq!(locked_accounts.len(), 1);
+    assert_eq!(locked_accounts[0].email, email.as_ref());
+    assert_eq!(locked_accounts[0].reason, "Too many failed login attempts");
+
+    let response = app
+        .post_admin_unlock_account(
+            &serde_json::json!({ "email": email.as_ref() }),
+            Some(ADMIN_API_KEY),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = login(&app, &email, "password123").await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.get_admin_locked_accounts(Some(ADMIN_API_KEY)).await;
+    let locked_accounts = response
+        .json::<LockedAccountsResponse>()
+        .await
+        .unwrap()
+        .locked_accounts;
+    assert!(locked_accounts.is_empty());
+}
+
+#[api_test]
+async fn should_return_404_when_admin_unlocks_unknown_account() {
+    let response = app
+        .post_admin_unlock_account(
+            &serde_json::json!({ "email": get_random_email() }),
+            Some(ADMIN_API_KEY),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[api_test]
+async fn should_reject_admin_routes_without_valid_api_key() {
+    let response = app.get_admin_locked_accounts(None).await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app.get_admin_locked_accounts(Some("wrong-key")).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app
+        .post_admin_unlock_account(
+            &serde_json::json!({ "email": get_random_email() }),
+            Some("wrong-key"),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
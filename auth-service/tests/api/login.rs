@@ -13,6 +13,7 @@ async fn should_return_200_if_2fa_is_not_required() {
         "requires2FA": false
     }))
     .await;
+    app.verify_email("requires2fa@mail.com").await;
 
     let body = serde_json::json!({
         "email": "requires2fa@mail.com",
@@ -36,6 +37,7 @@ async fn should_return_206_if_2fa_is_required() {
         "requires2FA": true
     }))
     .await;
+    app.verify_email("login@mail.com").await;
 
     let body = serde_json::json!({
         "email": "login@mail.com",
@@ -155,3 +157,21 @@ async fn should_return_422_if_malformed_credentials() {
         );
     }
 }
+
+#[api_test]
+async fn should_return_403_if_email_is_not_verified() {
+    app.post_signup(&serde_json::json!({
+        "email": "unverified@mail.com",
+        "password": "password123",
+        "requires2FA": false
+    }))
+    .await;
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": "unverified@mail.com",
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 403);
+}
@@ -1,15 +1,11 @@
-use crate::helpers::{get_random_email, TestApp};
-use auth_service::{
-    domain::Email,
-    utils::{constants::JWT_COOKIE_NAME, generate_auth_cookie},
-};
+use crate::helpers::TestApp;
+use auth_service::utils::{auth::validate_token, constants::JWT_COOKIE_NAME, generate_auth_cookie};
 use reqwest::Url;
 use test_helpers::api_test;
 
 #[api_test]
 async fn should_return_200_if_valid_jwt_cookie() {
-    let email = Email::parse(get_random_email()).expect("Couldn't parse email");
-    let cookie = generate_auth_cookie(&email).unwrap();
+    let cookie = generate_auth_cookie(&app.add_random_user().await).unwrap();
 
     app.cookie_jar.add_cookie_str(
         &format!(
@@ -20,20 +16,27 @@ async fn should_return_200_if_valid_jwt_cookie() {
         &Url::parse(&app.address).expect("Failed to parse URL"),
     );
 
+    let claims = validate_token(
+        cookie.value(),
+        app.banned_token_store.clone(),
+        app.user_store.clone(),
+    )
+    .await
+    .expect("Failed to validate token");
+
     assert_eq!(app.post_logout().await.status().as_u16(), 200);
     assert!(app
         .banned_token_store
         .read()
         .await
-        .contains_token(cookie.value().to_string())
+        .contains_token(claims.jti)
         .await
         .unwrap());
 }
 
 #[api_test]
 async fn should_return_401_if_token_is_already_banned() {
-    let email = Email::parse(get_random_email()).expect("Couldn't parse email");
-    let cookie = generate_auth_cookie(&email).unwrap();
+    let cookie = generate_auth_cookie(&app.add_random_user().await).unwrap();
 
     app.cookie_jar.add_cookie_str(
         &format!(
@@ -60,8 +63,7 @@ async fn should_return_401_if_token_is_already_banned() {
 
 #[api_test]
 async fn should_return_400_if_logout_called_twice() {
-    let email = Email::parse(get_random_email()).expect("Couldn't parse email");
-    let cookie = generate_auth_cookie(&email).unwrap();
+    let cookie = generate_auth_cookie(&app.add_random_user().await).unwrap();
 
     app.cookie_jar.add_cookie_str(
         &format!(
@@ -0,0 +1,66 @@
+use crate::helpers::{get_cookie, TestApp};
+use auth_service::utils::constants::JWT_COOKIE_NAME;
+use test_helpers::api_test;
+
+#[api_test]
+async fn should_invalidate_all_tokens_of_user() {
+    let random_email = app.signup_random_user(false).await;
+    let first_token = get_cookie(&app.login_user(&random_email).await, JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+    let second_token = get_cookie(&app.login_user(&random_email).await, JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+
+    let response = app.post_logout_all().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    for token in [first_token, second_token] {
+        let response = app
+            .post_verify_token(&serde_json::json!({ "token": token }))
+            .await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    // The refresh cookie is cleared and its token revoked.
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[api_test]
+async fn should_accept_tokens_issued_afterwards() {
+    let random_email = app.signup_random_user(false).await;
+    app.login_user(&random_email).await;
+
+    let response = app.post_logout_all().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let token = get_cookie(&app.login_user(&random_email).await, JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[api_test]
+async fn should_not_affect_other_users() {
+    let other_email = app.signup_random_user(false).await;
+    let other_token = get_cookie(&app.login_user(&other_email).await, JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+
+    let random_email = app.signup_random_user(false).await;
+    app.login_user(&random_email).await;
+
+    let response = app.post_logout_all().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": other_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[api_test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let response = app.post_logout_all().await;
+    assert_eq!(response.status().as_u16(), 400);
+}
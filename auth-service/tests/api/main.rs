@@ -1,7 +1,32 @@
+mod change_password;
+mod client_credentials;
+mod delete_account;
+mod email;
+mod email_api;
+mod email_api_stub;
+mod email_outbox;
 mod helpers;
+mod introspection;
+mod jwks;
+mod localization;
+mod lockout;
 mod login;
 mod logout;
+mod logout_all;
+mod oauth;
+mod openid;
+mod passkeys;
+mod password_reset;
+mod rate_limit;
+mod recovery_codes;
+mod refresh;
+mod resend_2fa;
 mod root;
+mod sessions;
 mod signup;
+mod smtp_sink;
+mod social_login;
+mod totp;
 mod verify_2fa;
+mod verify_email;
 mod verify_token;
@@ -0,0 +1,324 @@
+use crate::helpers::{get_cookie, TestApp, ADMIN_API_KEY};
+use auth_service::{
+    routes::{OAuthClientResponse, TokenResponse},
+    utils::{
+        auth::{decode_access_token, AccessTokenClaims},
+        constants::JWT_COOKIE_NAME,
+    },
+    ErrorResponse,
+};
+use reqwest::{header::LOCATION, Url};
+use test_helpers::api_test;
+
+pub const REDIRECT_URI: &str = "https://wiki.example.com/callback";
+// The example from RFC 7636, appendix B.
+pub const CODE_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+pub const CODE_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+pub async fn register_client(app: &TestApp) -> String {
+    let response = app
+        .post_admin_oauth_client(
+            &serde_json::json!({
+                "name": "Wiki",
+                "redirectUris": [REDIRECT_URI],
+            }),
+            Some(ADMIN_API_KEY),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    response
+        .json::<OAuthClientResponse>()
+        .await
+        .expect("Could not deserialize response body to OAuthClientResponse")
+        .client_id
+}
+
+/// Logs a new user in, returning their first-party access token.
+pub async fn login(app: &TestApp) -> String {
+    let (_, response) = app.signup_and_login().await;
+
+    get_cookie(&response, JWT_COOKIE_NAME).expect("No auth cookie found")
+}
+
+pub fn authorization_params(client_id: &str) -> Vec<(&str, &str)> {
+    vec![
+        ("response_type", "code"),
+        ("client_id", client_id),
+        ("redirect_uri", REDIRECT_URI),
+        ("scope", "email"),
+        ("state", "af0ifjsldkj"),
+        ("code_challenge", CODE_CHALLENGE),
+        ("code_challenge_method", "S256"),
+    ]
+}
+
+pub fn redirect_location(response: &reqwest::Response) -> String {
+    assert!(response.status().is_redirection());
+
+    response
+        .headers()
+        .get(LOCATION)
+        .expect("No location header found")
+        .to_str()
+        .unwrap()
+        .to_owned()
+}
+
+pub fn query_param(location: &str, name: &str) -> Option<String> {
+    Url::parse(location)
+        .expect("Failed to parse redirect")
+        .query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+}
+
+/// Approves the consent page and returns the authorization code.
+pub async fn approve(app: &TestApp, mut params: Vec<(&str, &str)>) -> String {
+    params.push(("decision", "approve"));
+
+    let response = app.post_authorize(&params).await;
+    let location = redirect_location(&response);
+    assert!(location.starts_with(REDIRECT_URI));
+    assert_eq!(
+        query_param(&location, "state").as_deref(),
+        Some("af0ifjsldkj")
+    );
+
+    query_param(&location, "code").expect("No code found")
+}
+
+pub async fn exchange(
+    app: &TestApp,
+    client_id: &str,
+    code: &str,
+    code_verifier: &str,
+) -> reqwest::Response {
+    app.post_token(&[
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", REDIRECT_URI),
+        ("client_id", client_id),
+        ("code_verifier", code_verifier),
+    ])
+    .await
+}
+
+pub async fn error(response: reqwest::Response) -> String {
+    response
+        .json::<ErrorResponse>()
+        .await
+        .expect("Could not deserialize response body to ErrorResponse")
+        .error
+}
+
+#[api_test]
+async fn should_issue_access_token_through_authorization_code_flow() {
+    let client_id = register_client(&app).await;
+    login(&app).await;
+
+    let response = app.get_authorize(&authorization_params(&client_id)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("Wiki wants to access"));
+
+    let code = approve(&app, authorization_params(&client_id)).await;
+
+    let response = exchange(&app, &client_id, &code, CODE_VERIFIER).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let token = response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse");
+    assert_eq!(token.token_type, "Bearer");
+    assert_eq!(token.scope, "email");
+
+    // The token is meant for the client, not our own downstream services.
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": &token.access_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // Nor can callers ask for the client as the audience, clients check their
+    // tokens through introspection instead.
+    let response = app
+        .post_verify_token(&serde_json::json!({
+            "token": &token.access_token,
+            "audience": &client_id,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let claims = match decode_access_token(&token.access_token).unwrap() {
+        AccessTokenClaims::User(claims) => claims,
+        AccessTokenClaims::Service(_) => panic!("Expected a user's access token"),
+    };
+    assert_eq!(claims.aud, vec![client_id]);
+    assert_eq!(claims.scope.as_deref(), Some("email"));
+}
+
+#[api_test]
+async fn should_skip_consent_once_granted() {
+    let client_id = register_client(&app).await;
+    login(&app).await;
+    approve(&app, authorization_params(&client_id)).await;
+
+    let response = app.get_authorize(&authorization_params(&client_id)).await;
+    let location = redirect_location(&response);
+    assert!(location.starts_with(REDIRECT_URI));
+    assert!(query_param(&location, "code").is_some());
+
+    // Asking for more than was granted needs consent again.
+    let mut params = authorization_params(&client_id);
+    params[3] = ("scope", "email profile");
+    let response = app.get_authorize(&params).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[api_test]
+async fn should_redirect_to_login_if_not_logged_in() {
+    let client_id = register_client(&app).await;
+
+    let response = app.get_authorize(&authorization_params(&client_id)).await;
+    assert!(redirect_location(&response).starts_with("/?return_to=%2Fauthorize%3F"));
+}
+
+#[api_test]
+async fn should_redirect_with_access_denied_if_user_denies() {
+    let client_id = register_client(&app).await;
+    login(&app).await;
+
+    let mut params = authorization_params(&client_id);
+    params.push(("decision", "deny"));
+
+    let response = app.post_authorize(&params).await;
+    let location = redirect_location(&response);
+    assert_eq!(
+        query_param(&location, "error").as_deref(),
+        Some("access_denied")
+    );
+    assert!(query_param(&location, "code").is_none());
+}
+
+#[api_test]
+async fn should_not_redirect_to_unregistered_redirect_uri() {
+    let client_id = register_client(&app).await;
+    login(&app).await;
+
+    let mut params = authorization_params(&client_id);
+    params[2] = ("redirect_uri", "https://attacker.example.com/callback");
+    let response = app.get_authorize(&params).await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(error(response).await, "invalid_request");
+
+    let response = app.get_authorize(&authorization_params("unknown")).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[api_test]
+async fn should_require_pkce() {
+    let client_id = register_client(&app).await;
+    login(&app).await;
+
+    let params: Vec<_> = authorization_params(&client_id)
+        .into_iter()
+        .filter(|(name, _)| !name.starts_with("code_challenge"))
+        .collect();
+    let response = app.get_authorize(&params).await;
+    let location = redirect_location(&response);
+    assert_eq!(
+        query_param(&location, "error").as_deref(),
+        Some("invalid_request")
+    );
+    assert_eq!(
+        query_param(&location, "state").as_deref(),
+        Some("af0ifjsldkj")
+    );
+
+    let mut params = authorization_params(&client_id);
+    params[6] = ("code_challenge_method", "plain");
+    let response = app.get_authorize(&params).await;
+    assert_eq!(
+        query_param(&redirect_location(&response), "error").as_deref(),
+        Some("invalid_request")
+    );
+}
+
+#[api_test]
+async fn should_reject_unsupported_scope() {
+    let client_id = register_client(&app).await;
+    login(&app).await;
+
+    let mut params = authorization_params(&client_id);
+    params[3] = ("scope", "email admin");
+    let response = app.get_authorize(&params).await;
+    assert_eq!(
+        query_param(&redirect_location(&response), "error").as_deref(),
+        Some("invalid_scope")
+    );
+}
+
+#[api_test]
+async fn should_reject_code_with_wrong_verifier() {
+    let client_id = register_client(&app).await;
+    login(&app).await;
+    let code = approve(&app, authorization_params(&client_id)).await;
+
+    let response = exchange(&app, &client_id, &code, &"a".repeat(43)).await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(error(response).await, "invalid_grant");
+
+    // A failed exchange uses the code up.
+    let response = exchange(&app, &client_id, &code, CODE_VERIFIER).await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(error(response).await, "invalid_grant");
+}
+
+#[api_test]
+async fn should_reject_reused_code() {
+    let client_id = register_client(&app).await;
+    login(&app).await;
+    let code = approve(&app, authorization_params(&client_id)).await;
+
+    let response = exchange(&app, &client_id, &code, CODE_VERIFIER).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = exchange(&app, &client_id, &code, CODE_VERIFIER).await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(error(response).await, "invalid_grant");
+}
+
+#[api_test]
+async fn should_reject_unsupported_grant_type() {
+    let response = app.post_token(&[("grant_type", "password")]).await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(error(response).await, "unsupported_grant_type");
+}
+
+#[api_test]
+async fn should_validate_client_registration() {
+    let response = app
+        .post_admin_oauth_client(
+            &serde_json::json!({
+                "name": "Wiki",
+                "redirectUris": [REDIRECT_URI],
+            }),
+            Some("wrong-key"),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app
+        .post_admin_oauth_client(
+            &serde_json::json!({
+                "name": "Wiki",
+                "redirectUris": ["/callback"],
+            }),
+            Some(ADMIN_API_KEY),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
@@ -0,0 +1,128 @@
+use crate::{
+    helpers::TestApp,
+    oauth::{approve, authorization_params, exchange, login, register_client, CODE_VERIFIER},
+};
+use auth_service::{
+    routes::{OpenIdConfiguration, TokenResponse, UserInfoResponse},
+    utils::auth::IdTokenClaims,
+};
+use jsonwebtoken::{DecodingKey, Validation};
+use test_helpers::api_test;
+
+/// Runs the authorization code flow with the given scope and a nonce.
+async fn authorize(app: &TestApp, scope: &str) -> (String, TokenResponse) {
+    let client_id = register_client(app).await;
+    login(app).await;
+
+    let mut params = authorization_params(&client_id);
+    params[3] = ("scope", scope);
+    params.push(("nonce", "n-0S6_WzA2Mj"));
+    let code = approve(app, params).await;
+
+    let response = exchange(app, &client_id, &code, CODE_VERIFIER).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let token = response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse");
+
+    (client_id, token)
+}
+
+fn id_token_claims(id_token: &str) -> IdTokenClaims {
+    let mut validation = Validation::default();
+    validation.insecure_disable_signature_validation();
+    validation.validate_aud = false;
+
+    jsonwebtoken::decode::<IdTokenClaims>(id_token, &DecodingKey::from_secret(&[]), &validation)
+        .expect("Could not decode ID token")
+        .claims
+}
+
+#[api_test]
+async fn should_return_discovery_document() {
+    let response = app.get_openid_configuration().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let configuration = response
+        .json::<OpenIdConfiguration>()
+        .await
+        .expect("Could not deserialize response body to OpenIdConfiguration");
+    assert_eq!(
+        configuration.authorization_endpoint,
+        format!("{}/authorize", configuration.issuer)
+    );
+    assert_eq!(
+        configuration.jwks_uri,
+        format!("{}/.well-known/jwks.json", configuration.issuer)
+    );
+    assert!(configuration
+        .scopes_supported
+        .contains(&"openid".to_owned()));
+    assert_eq!(configuration.code_challenge_methods_supported, ["S256"]);
+}
+
+#[api_test]
+async fn should_issue_id_token_for_openid_scope() {
+    let (client_id, token) = authorize(&app, "openid email").await;
+    let issuer = app
+        .get_openid_configuration()
+        .await
+        .json::<OpenIdConfiguration>()
+        .await
+        .unwrap()
+        .issuer;
+
+    let claims = id_token_claims(&token.id_token.expect("No ID token issued"));
+    assert_eq!(claims.iss, issuer);
+    assert_eq!(claims.aud, client_id);
+    assert_eq!(claims.nonce.as_deref(), Some("n-0S6_WzA2Mj"));
+    assert!(claims.email.is_some());
+    assert_eq!(claims.email_verified, Some(true));
+}
+
+#[api_test]
+async fn should_not_issue_id_token_without_openid_scope() {
+    let (_, token) = authorize(&app, "email").await;
+    assert!(token.id_token.is_none());
+}
+
+#[api_test]
+async fn should_return_user_info_for_client_access_token() {
+    let (_, token) = authorize(&app, "openid email").await;
+    let id_token = id_token_claims(&token.id_token.unwrap());
+
+    let response = app.get_userinfo(Some(&token.access_token)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let user_info = response
+        .json::<UserInfoResponse>()
+        .await
+        .expect("Could not deserialize response body to UserInfoResponse");
+    assert_eq!(user_info.sub, id_token.sub);
+    assert_eq!(user_info.email, id_token.email);
+
+    // Without the email scope only the subject is released.
+    let (_, token) = authorize(&app, "openid").await;
+    let user_info = app
+        .get_userinfo(Some(&token.access_token))
+        .await
+        .json::<UserInfoResponse>()
+        .await
+        .unwrap();
+    assert!(user_info.email.is_none());
+}
+
+#[api_test]
+async fn should_reject_user_info_without_openid_token() {
+    let response = app.get_userinfo(None).await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let (_, token) = authorize(&app, "email").await;
+    let response = app.get_userinfo(Some(&token.access_token)).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // First-party tokens aren't OAuth2 access tokens.
+    let first_party_token = login(&app).await;
+    let response = app.get_userinfo(Some(&first_party_token)).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
@@ -0,0 +1,329 @@
+use crate::helpers::TestApp;
+use auth_service::{
+    domain::{AccountLock, Email},
+    routes::{LoginResponse, PasskeyLoginOptions, PasskeyRegistrationOptions},
+    utils::{
+        constants::{JWT_COOKIE_NAME, PUBLIC_BASE_URL},
+        webauthn::{decode_base64url, encode_base64url, COSE_ALG_ES256},
+    },
+};
+use chrono::{Duration, Utc};
+use ciborium::Value;
+use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha256};
+use test_helpers::api_test;
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+/// Plays the part of the browser and authenticator in WebAuthn ceremonies.
+struct SoftwareAuthenticator {
+    signing_key: SigningKey,
+    credential_id: Vec<u8>,
+    sign_count: u32,
+    user_verified: bool,
+}
+
+impl SoftwareAuthenticator {
+    fn new() -> Self {
+        Self {
+            signing_key: SigningKey::random(&mut OsRng),
+            credential_id: rand::random::<[u8; 16]>().to_vec(),
+            sign_count: 0,
+            user_verified: true,
+        }
+    }
+
+    fn client_data(ceremony: &str, challenge: &str) -> Vec<u8> {
+        serde_json::json!({
+            "type": ceremony,
+            "challenge": challenge,
+            "origin": PUBLIC_BASE_URL.as_str(),
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    fn authenticator_data(&self, rp_id: &str, attested: bool) -> Vec<u8> {
+        let mut flags = FLAG_USER_PRESENT;
+        if self.user_verified {
+            flags |= FLAG_USER_VERIFIED;
+        }
+        if attested {
+            flags |= FLAG_ATTESTED_CREDENTIAL_DATA;
+        }
+
+        let mut data = Sha256::digest(rp_id.as_bytes()).to_vec();
+        data.push(flags);
+        data.extend_from_slice(&self.sign_count.to_be_bytes());
+
+        if attested {
+            let point = self.signing_key.verifying_key().to_encoded_point(false);
+            let cose_key = Value::Map(vec![
+                (1.into(), 2.into()),
+                (3.into(), COSE_ALG_ES256.into()),
+                ((-1).into(), 1.into()),
+                ((-2).into(), Value::Bytes(point.x().unwrap().to_vec())),
+                ((-3).into(), Value::Bytes(point.y().unwrap().to_vec())),
+            ]);
+
+            data.extend_from_slice(&[0; 16]);
+            data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+            data.extend_from_slice(&self.credential_id);
+            ciborium::into_writer(&cose_key, &mut data).unwrap();
+        }
+
+        data
+    }
+
+    fn register(&self, options: &PasskeyRegistrationOptions) -> serde_json::Value {
+        let attestation_object = Value::Map(vec![
+            ("fmt".into(), "none".into()),
+            ("attStmt".into(), Value::Map(vec![])),
+            (
+                "authData".into(),
+                Value::Bytes(self.authenticator_data(&options.rp.id, true)),
+            ),
+        ]);
+        let mut encoded = Vec::new();
+        ciborium::into_writer(&attestation_object, &mut encoded).unwrap();
+
+        serde_json::json!({
+            "id": encode_base64url(&self.credential_id),
+            "response": {
+                "clientDataJSON": encode_base64url(&Self::client_data("webauthn.create", &options.challenge)),
+                "attestationObject": encode_base64url(&encoded),
+            }
+        })
+    }
+
+    fn authenticate(&mut self, options: &PasskeyLoginOptions) -> serde_json::Value {
+        self.sign_count += 1;
+
+        let client_data = Self::client_data("webauthn.get", &options.challenge);
+        let authenticator_data = self.authenticator_data(&options.rp_id, false);
+
+        let mut message = authenticator_data.clone();
+        message.extend_from_slice(&Sha256::digest(&client_data));
+        let signature: Signature = self.signing_key.sign(&message);
+
+        serde_json::json!({
+            "id": encode_base64url(&self.credential_id),
+            "response": {
+                "clientDataJSON": encode_base64url(&client_data),
+                "authenticatorData": encode_base64url(&authenticator_data),
+                "signature": encode_base64url(signature.to_der().as_bytes()),
+            }
+        })
+    }
+}
+
+async fn register(app: &TestApp, authenticator: &SoftwareAuthenticator) {
+    let response = app.post_passkey_register_start().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let options = response
+        .json::<PasskeyRegistrationOptions>()
+        .await
+        .expect("Could not deserialize response body to PasskeyRegistrationOptions");
+
+    let response = app
+        .post_passkey_register_finish(&authenticator.register(&options))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+}
+
+async fn login_options(app: &TestApp, email: &str) -> PasskeyLoginOptions {
+    let response = app
+        .post_passkey_login_start(&serde_json::json!({ "email": email }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response
+        .json::<PasskeyLoginOptions>()
+        .await
+        .expect("Could not deserialize response body to PasskeyLoginOptions")
+}
+
+fn with_fields(mut body: serde_json::Value, fields: serde_json::Value) -> serde_json::Value {
+    for (key, value) in fields.as_object().unwrap() {
+        body[key] = value.clone();
+    }
+    body
+}
+
+#[api_test]
+async fn should_register_passkey_and_login_without_password() {
+    let (random_email, _) = app.signup_and_login().await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    register(&app, &authenticator).await;
+
+    let options = login_options(&app, &random_email).await;
+    assert_eq!(options.allow_credentials.len(), 1);
+    assert_eq!(
+        decode_base64url(&options.allow_credentials[0].id).unwrap(),
+        authenticator.credential_id
+    );
+
+    let response = app
+        .post_passkey_login_finish(&with_fields(
+            authenticator.authenticate(&options),
+            serde_json::json!({ "email": &random_email }),
+        ))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response
+        .cookies()
+        .any(|cookie| cookie.name() == JWT_COOKIE_NAME && !cookie.value().is_empty()));
+}
+
+#[api_test]
+async fn should_use_passkey_in_place_of_2fa_code() {
+    let random_email = app.signup_random_user(true).await;
+
+    let login = || async {
+        let response = app
+            .post_login(&serde_json::json!({
+                "email": &random_email,
+                "password": "password123",
+            }))
+            .await;
+        assert_eq!(response.status().as_u16(), 206);
+        match response.json::<LoginResponse>().await.unwrap() {
+            LoginResponse::TwoFactorAuth(val) => val.login_attempt_id,
+            _ => panic!("Expected a 2FA response"),
+        }
+    };
+
+    // The first login completes 2FA with the emailed code to get a session
+    // the passkey can be registered with.
+    let login_attempt_id = login().await;
+    let two_fa_code = app.get_2fa_code(&random_email).await;
+    app.post_verify_2fa(&serde_json::json!({
+        "email": &random_email,
+        "loginAttemptId": login_attempt_id,
+        "2FACode": &two_fa_code,
+    }))
+    .await;
+
+    let mut authenticator = SoftwareAuthenticator::new();
+    register(&app, &authenticator).await;
+
+    // As a second factor, user presence without verification is enough.
+    authenticator.user_verified = false;
+    let login_attempt_id = login().await;
+    let options = login_options(&app, &random_email).await;
+
+    let response = app
+        .post_passkey_login_finish(&with_fields(
+            authenticator.authenticate(&options),
+            serde_json::json!({ "email": &random_email, "loginAttemptId": login_attempt_id }),
+        ))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert!(app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&Email::parse(random_email).unwrap())
+        .await
+        .is_err());
+}
+
+#[api_test]
+async fn should_return_423_if_account_is_locked() {
+    let (random_email, _) = app.signup_and_login().await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    register(&app, &authenticator).await;
+
+    app.user_store
+        .write()
+        .await
+        .lock_user(
+            &Email::parse(random_email.clone()).unwrap(),
+            AccountLock {
+                locked_until: Utc::now() + Duration::hours(1),
+                reason: "Too many failed login attempts".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+    let options = login_options(&app, &random_email).await;
+    let response = app
+        .post_passkey_login_finish(&with_fields(
+            authenticator.authenticate(&options),
+            serde_json::json!({ "email": &random_email }),
+        ))
+        .await;
+    assert_eq!(response.status().as_u16(), 423);
+    assert!(!response
+        .cookies()
+        .any(|cookie| cookie.name() == JWT_COOKIE_NAME && !cookie.value().is_empty()));
+}
+
+#[api_test]
+async fn should_return_401_for_passwordless_login_without_user_verification() {
+    let (random_email, _) = app.signup_and_login().await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    register(&app, &authenticator).await;
+
+    authenticator.user_verified = false;
+    let options = login_options(&app, &random_email).await;
+
+    let response = app
+        .post_passkey_login_finish(&with_fields(
+            authenticator.authenticate(&options),
+            serde_json::json!({ "email": &random_email }),
+        ))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_return_401_if_assertion_is_replayed() {
+    let (random_email, _) = app.signup_and_login().await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    register(&app, &authenticator).await;
+
+    let options = login_options(&app, &random_email).await;
+    let assertion = with_fields(
+        authenticator.authenticate(&options),
+        serde_json::json!({ "email": &random_email }),
+    );
+
+    let response = app.post_passkey_login_finish(&assertion).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_passkey_login_finish(&assertion).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_return_401_if_signature_is_invalid() {
+    let (random_email, _) = app.signup_and_login().await;
+    let authenticator = SoftwareAuthenticator::new();
+    register(&app, &authenticator).await;
+
+    // A different key claiming the registered credential id.
+    let mut impostor = SoftwareAuthenticator::new();
+    impostor.credential_id = authenticator.credential_id.clone();
+
+    let options = login_options(&app, &random_email).await;
+
+    let response = app
+        .post_passkey_login_finish(&with_fields(
+            impostor.authenticate(&options),
+            serde_json::json!({ "email": &random_email }),
+        ))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_return_400_if_registering_without_jwt() {
+    let response = app.post_passkey_register_start().await;
+    assert_eq!(response.status().as_u16(), 400);
+}
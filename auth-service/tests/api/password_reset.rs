@@ -0,0 +1,229 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::{
+    domain::{Email, OneTimeToken, TokenPurpose},
+    routes::PasswordResetResponse,
+};
+use test_helpers::api_test;
+
+async fn add_reset_token(app: &TestApp, email: &str) -> OneTimeToken {
+    let token = OneTimeToken::default();
+    app.one_time_token_store
+        .write()
+        .await
+        .add_token(
+            token.clone(),
+            TokenPurpose::PasswordReset,
+            Email::parse(email.to_owned()).expect("Failed to parse email"),
+        )
+        .await
+        .expect("Failed to add reset token");
+    token
+}
+
+#[api_test]
+async fn should_return_same_response_whether_or_not_account_exists() {
+    let random_email = get_random_email();
+
+    app.post_signup(&serde_json::json!({
+        "email": &random_email,
+        "password": "password123",
+        "requires2FA": false
+    }))
+    .await;
+
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "email": &random_email }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let existing = response
+        .json::<PasswordResetResponse>()
+        .await
+        .expect("Could not deserialize response body to PasswordResetResponse");
+
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "email": get_random_email() }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let missing = response
+        .json::<PasswordResetResponse>()
+        .await
+        .expect("Could not deserialize response body to PasswordResetResponse");
+
+    assert_eq!(existing, missing);
+}
+
+#[api_test]
+async fn should_return_400_if_invalid_email() {
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "email": "invalid" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[api_test]
+async fn should_return_422_if_malformed_input() {
+    let test_cases = [
+        serde_json::json!({ "mail": "test@example.com" }),
+        serde_json::json!({ "token": "token" }),
+    ];
+
+    for test_case in test_cases.iter() {
+        assert_eq!(
+            app.post_password_reset_request(test_case)
+                .await
+                .status()
+                .as_u16(),
+            422
+        );
+        assert_eq!(
+            app.post_password_reset_confirm(test_case)
+                .await
+                .status()
+                .as_u16(),
+            422
+        );
+    }
+}
+
+#[api_test]
+async fn should_reset_password_with_valid_token() {
+    let random_email = app.signup_random_user(false).await;
+
+    let token = add_reset_token(&app, &random_email).await;
+
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({
+            "token": token.as_ref(),
+            "newPassword": "newpassword123"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &random_email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &random_email,
+            "password": "newpassword123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[api_test]
+async fn should_serve_reset_form_at_emailed_link() {
+    let random_email = app.signup_random_user(false).await;
+
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "email": &random_email }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let link = app
+        .get_emailed_link(&random_email, "/password-reset?token=")
+        .await;
+    let response = app.follow_link(&link).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let token = link.split_once("token=").unwrap().1;
+    let page = response.text().await.unwrap();
+    assert!(page.contains(&format!(r#"name="token" value="{}""#, token)));
+    assert!(page.contains("/password-reset.js"));
+
+    // The form posts the token from the link like this.
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({
+            "token": token,
+            "newPassword": "newpassword123"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[api_test]
+async fn should_return_401_if_token_is_reused() {
+    let random_email = app.signup_random_user(false).await;
+
+    let token = add_reset_token(&app, &random_email).await;
+    let body = serde_json::json!({
+        "token": token.as_ref(),
+        "newPassword": "newpassword123"
+    });
+
+    assert_eq!(
+        app.post_password_reset_confirm(&body)
+            .await
+            .status()
+            .as_u16(),
+        200
+    );
+    assert_eq!(
+        app.post_password_reset_confirm(&body)
+            .await
+            .status()
+            .as_u16(),
+        401
+    );
+}
+
+#[api_test]
+async fn should_revoke_sessions_after_reset() {
+    let random_email = app.signup_random_user(false).await;
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &random_email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let token = add_reset_token(&app, &random_email).await;
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({
+            "token": token.as_ref(),
+            "newPassword": "newpassword123"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_eq!(app.post_refresh().await.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_return_401_if_invalid_token() {
+    let test_cases = [
+        "invalid".to_owned(),
+        OneTimeToken::default().as_ref().to_owned(),
+    ];
+
+    for token in test_cases {
+        let response = app
+            .post_password_reset_confirm(&serde_json::json!({
+                "token": token,
+                "newPassword": "newpassword123"
+            }))
+            .await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+}
+
+#[api_test]
+async fn should_return_400_if_invalid_new_password() {
+    let random_email = get_random_email();
+    let token = add_reset_token(&app, &random_email).await;
+
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({
+            "token": token.as_ref(),
+            "newPassword": "short"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
@@ -0,0 +1,92 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::{
+    routes::LoginResponse,
+    utils::rate_limit::{ACCOUNT_RATE_LIMIT, IP_RATE_LIMIT, MAX_TWO_FA_CODE_ATTEMPTS},
+};
+use test_helpers::api_test;
+
+async fn login(app: &TestApp, email: &str, password: &str) -> reqwest::Response {
+    app.post_login(&serde_json::json!({
+        "email": email,
+        "password": password,
+    }))
+    .await
+}
+
+#[api_test]
+async fn should_return_429_after_too_many_wrong_passwords() {
+    let random_email = app.signup_random_user(false).await;
+
+    for _ in 0..ACCOUNT_RATE_LIMIT.free_attempts {
+        let response = login(&app, &random_email, "wrong-password").await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    // This failure locks the account.
+    let response = login(&app, &random_email, "wrong-password").await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // Even the right password is refused until the lockout ends.
+    let response = login(&app, &random_email, "password123").await;
+    assert_eq!(response.status().as_u16(), 429);
+
+    let retry_after = response
+        .headers()
+        .get("Retry-After")
+        .expect("No Retry-After header")
+        .to_str()
+        .unwrap()
+        .parse::<u64>()
+        .unwrap();
+    assert!(retry_after > 0 && retry_after <= 30);
+}
+
+#[api_test]
+async fn should_return_429_after_too_many_failures_from_one_address() {
+    for _ in 0..=IP_RATE_LIMIT.free_attempts {
+        let response = login(&app, &get_random_email(), "wrong-password").await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let random_email = app.signup_random_user(false).await;
+    let response = login(&app, &random_email, "password123").await;
+    assert_eq!(response.status().as_u16(), 429);
+}
+
+#[api_test]
+async fn should_invalidate_2fa_attempt_after_too_many_wrong_codes() {
+    let random_email = app.signup_random_user(true).await;
+
+    let response = login(&app, &random_email, "password123").await;
+    assert_eq!(response.status().as_u16(), 206);
+    let login_attempt_id = match response.json::<LoginResponse>().await.unwrap() {
+        LoginResponse::TwoFactorAuth(val) => val.login_attempt_id,
+        _ => panic!("Expected a 2FA response"),
+    };
+    let two_fa_code = app.get_2fa_code(&random_email).await;
+    let wrong_code = if two_fa_code == "000000" {
+        "111111"
+    } else {
+        "000000"
+    };
+
+    for _ in 0..MAX_TWO_FA_CODE_ATTEMPTS {
+        let response = app
+            .post_verify_2fa(&serde_json::json!({
+                "email": &random_email,
+                "loginAttemptId": &login_attempt_id,
+                "2FACode": wrong_code,
+            }))
+            .await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": &random_email,
+            "loginAttemptId": &login_attempt_id,
+            "2FACode": &two_fa_code,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
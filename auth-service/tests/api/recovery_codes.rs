@@ -0,0 +1,139 @@
+use crate::helpers::TestApp;
+use auth_service::routes::{LoginResponse, RecoveryCodesResponse};
+use test_helpers::api_test;
+
+async fn login(app: &TestApp, email: &str) -> String {
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    match response
+        .json::<LoginResponse>()
+        .await
+        .expect("Failed to parse the response from login.")
+    {
+        LoginResponse::TwoFactorAuth(val) => val.login_attempt_id,
+        _ => panic!("Expected a 2FA response"),
+    }
+}
+
+async fn signup_and_login_with_2fa(app: &TestApp) -> String {
+    let random_email = app.signup_random_user(true).await;
+    let login_attempt_id = login(app, &random_email).await;
+    let two_fa_code = app.get_2fa_code(&random_email).await;
+
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": &random_email,
+            "loginAttemptId": login_attempt_id,
+            "2FACode": &two_fa_code,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    random_email
+}
+
+async fn get_recovery_codes(response: reqwest::Response) -> Vec<String> {
+    assert_eq!(response.status().as_u16(), 201);
+
+    response
+        .json::<RecoveryCodesResponse>()
+        .await
+        .expect("Could not deserialize response body to RecoveryCodesResponse")
+        .recovery_codes
+}
+
+#[api_test]
+async fn should_return_201_with_ten_unique_codes() {
+    signup_and_login_with_2fa(&app).await;
+
+    let mut codes = get_recovery_codes(app.post_recovery_codes().await).await;
+    assert_eq!(codes.len(), 10);
+
+    codes.sort();
+    codes.dedup();
+    assert_eq!(codes.len(), 10);
+}
+
+#[api_test]
+async fn should_return_409_if_codes_already_generated() {
+    signup_and_login_with_2fa(&app).await;
+
+    get_recovery_codes(app.post_recovery_codes().await).await;
+
+    let response = app.post_recovery_codes().await;
+    assert_eq!(response.status().as_u16(), 409);
+}
+
+#[api_test]
+async fn should_accept_recovery_code_only_once() {
+    let random_email = signup_and_login_with_2fa(&app).await;
+    let codes = get_recovery_codes(app.post_recovery_codes().await).await;
+
+    let login_attempt_id = login(&app, &random_email).await;
+    let verify_request = serde_json::json!({
+        "email": &random_email,
+        "loginAttemptId": login_attempt_id,
+        "2FACode": codes[0].to_uppercase(),
+    });
+    let response = app.post_verify_2fa(&verify_request).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let login_attempt_id = login(&app, &random_email).await;
+    let verify_request = serde_json::json!({
+        "email": &random_email,
+        "loginAttemptId": login_attempt_id,
+        "2FACode": &codes[0],
+    });
+    let response = app.post_verify_2fa(&verify_request).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_invalidate_old_codes_when_regenerated() {
+    let random_email = signup_and_login_with_2fa(&app).await;
+    let old_codes = get_recovery_codes(app.post_recovery_codes().await).await;
+    let new_codes = get_recovery_codes(app.post_regenerate_recovery_codes().await).await;
+    assert_eq!(new_codes.len(), 10);
+
+    let login_attempt_id = login(&app, &random_email).await;
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": &random_email,
+            "loginAttemptId": &login_attempt_id,
+            "2FACode": &old_codes[0],
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": &random_email,
+            "loginAttemptId": &login_attempt_id,
+            "2FACode": &new_codes[0],
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[api_test]
+async fn should_return_400_if_2fa_is_not_enabled() {
+    app.signup_and_login().await;
+
+    let response = app.post_recovery_codes().await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[api_test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let response = app.post_recovery_codes().await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app.post_regenerate_recovery_codes().await;
+    assert_eq!(response.status().as_u16(), 400);
+}
@@ -0,0 +1,97 @@
+use crate::helpers::{get_cookie, TestApp};
+use auth_service::{
+    domain::RefreshToken,
+    utils::constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+};
+use reqwest::Url;
+use test_helpers::api_test;
+
+#[api_test]
+async fn should_return_200_and_rotate_refresh_token() {
+    let (_, response) = app.signup_and_login().await;
+    let first_token =
+        get_cookie(&response, REFRESH_TOKEN_COOKIE_NAME).expect("No refresh cookie found");
+
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(get_cookie(&response, JWT_COOKIE_NAME).is_some());
+    let second_token =
+        get_cookie(&response, REFRESH_TOKEN_COOKIE_NAME).expect("No refresh cookie found");
+
+    assert_ne!(first_token, second_token);
+    assert!(
+        app.refresh_token_store
+            .read()
+            .await
+            .get_token(&RefreshToken::parse(first_token).unwrap())
+            .await
+            .unwrap()
+            .used
+    );
+}
+
+#[api_test]
+async fn should_return_401_and_revoke_family_if_token_is_reused() {
+    let (_, response) = app.signup_and_login().await;
+    let first_token =
+        get_cookie(&response, REFRESH_TOKEN_COOKIE_NAME).expect("No refresh cookie found");
+
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let second_token =
+        get_cookie(&response, REFRESH_TOKEN_COOKIE_NAME).expect("No refresh cookie found");
+
+    let url = Url::parse(&app.address).expect("Failed to parse URL");
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}={}; HttpOnly; SameSite=Lax; Path=/",
+            REFRESH_TOKEN_COOKIE_NAME, first_token
+        ),
+        &url,
+    );
+    assert_eq!(app.post_refresh().await.status().as_u16(), 401);
+
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}={}; HttpOnly; SameSite=Lax; Path=/",
+            REFRESH_TOKEN_COOKIE_NAME, second_token
+        ),
+        &url,
+    );
+    assert_eq!(app.post_refresh().await.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_return_401_if_refresh_token_was_logged_out() {
+    let (_, response) = app.signup_and_login().await;
+    let token = get_cookie(&response, REFRESH_TOKEN_COOKIE_NAME).expect("No refresh cookie found");
+
+    assert_eq!(app.post_logout().await.status().as_u16(), 200);
+
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}={}; HttpOnly; SameSite=Lax; Path=/",
+            REFRESH_TOKEN_COOKIE_NAME, token
+        ),
+        &Url::parse(&app.address).expect("Failed to parse URL"),
+    );
+    assert_eq!(app.post_refresh().await.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_return_400_if_refresh_cookie_is_missing() {
+    assert_eq!(app.post_refresh().await.status().as_u16(), 400);
+}
+
+#[api_test]
+async fn should_return_401_if_invalid_refresh_token() {
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}=invalid; HttpOnly; SameSite=Lax; Path=/",
+            REFRESH_TOKEN_COOKIE_NAME
+        ),
+        &Url::parse(&app.address).expect("Failed to parse URL"),
+    );
+
+    assert_eq!(app.post_refresh().await.status().as_u16(), 401);
+}
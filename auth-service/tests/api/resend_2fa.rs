@@ -0,0 +1,123 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::{
+    domain::Email, routes::LoginResponse, utils::rate_limit::MAX_TWO_FA_CODE_RESENDS,
+};
+use test_helpers::api_test;
+
+/// Signs up a user with email 2FA and logs in, returning the email and the
+/// login attempt id.
+async fn start_login(app: &TestApp) -> (String, String) {
+    let random_email = app.signup_random_user(true).await;
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &random_email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let login_attempt_id = match response
+        .json::<LoginResponse>()
+        .await
+        .expect("Failed to parse the response from login.")
+    {
+        LoginResponse::TwoFactorAuth(val) => val.login_attempt_id,
+        _ => panic!("Expected a 2FA response"),
+    };
+
+    (random_email, login_attempt_id)
+}
+
+#[api_test]
+async fn should_resend_code_of_pending_login() {
+    let (random_email, login_attempt_id) = start_login(&app).await;
+    let two_fa_code = app.get_2fa_code(&random_email).await;
+
+    let response = app
+        .post_resend_2fa(&serde_json::json!({
+            "email": &random_email,
+            "loginAttemptId": &login_attempt_id,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.deliver_emails().await;
+    let code_emails: Vec<_> = app
+        .email_client
+        .emails_to(&Email::parse(random_email.clone()).unwrap())
+        .into_iter()
+        .filter(|email| email.subject == "Your login code")
+        .collect();
+    assert_eq!(code_emails.len(), 2);
+    assert_eq!(app.get_2fa_code(&random_email).await, two_fa_code);
+
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": &random_email,
+            "loginAttemptId": &login_attempt_id,
+            "2FACode": &two_fa_code,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[api_test]
+async fn should_return_401_for_other_login_attempt() {
+    let (random_email, _) = start_login(&app).await;
+
+    let response = app
+        .post_resend_2fa(&serde_json::json!({
+            "email": &random_email,
+            "loginAttemptId": uuid::Uuid::new_v4().to_string(),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // No login is pending for this user.
+    let response = app
+        .post_resend_2fa(&serde_json::json!({
+            "email": get_random_email(),
+            "loginAttemptId": uuid::Uuid::new_v4().to_string(),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_return_429_after_too_many_resends() {
+    let (random_email, login_attempt_id) = start_login(&app).await;
+    let body = serde_json::json!({
+        "email": &random_email,
+        "loginAttemptId": &login_attempt_id,
+    });
+
+    for _ in 0..MAX_TWO_FA_CODE_RESENDS {
+        assert_eq!(app.post_resend_2fa(&body).await.status().as_u16(), 200);
+    }
+    assert_eq!(app.post_resend_2fa(&body).await.status().as_u16(), 429);
+}
+
+#[api_test]
+async fn should_return_400_if_invalid_input() {
+    let test_cases = [
+        serde_json::json!({
+            "email": "invalid",
+            "loginAttemptId": uuid::Uuid::new_v4().to_string(),
+        }),
+        serde_json::json!({
+            "email": get_random_email(),
+            "loginAttemptId": "invalid",
+        }),
+    ];
+
+    for test_case in test_cases {
+        let response = app.post_resend_2fa(&test_case).await;
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "Failed for {:?}",
+            test_case
+        );
+    }
+}
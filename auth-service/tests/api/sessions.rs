@@ -0,0 +1,118 @@
+use crate::helpers::{get_cookie, TestApp, USER_AGENT};
+use auth_service::{
+    routes::{SessionResponse, SessionsResponse},
+    utils::constants::JWT_COOKIE_NAME,
+};
+use test_helpers::api_test;
+
+async fn get_sessions(app: &TestApp) -> Vec<SessionResponse> {
+    let response = app.get_sessions().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response
+        .json::<SessionsResponse>()
+        .await
+        .expect("Could not deserialize response body to SessionsResponse")
+        .sessions
+}
+
+#[api_test]
+async fn should_list_sessions_of_current_user() {
+    let other_email = app.signup_random_user(false).await;
+    app.login_user(&other_email).await;
+
+    let random_email = app.signup_random_user(false).await;
+    app.login_user(&random_email).await;
+    app.login_user(&random_email).await;
+
+    let sessions = get_sessions(&app).await;
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions.iter().filter(|session| session.current).count(), 1);
+    assert!(sessions.iter().all(|session| {
+        session.user_agent.as_deref() == Some(USER_AGENT) && session.ip == "127.0.0.1"
+    }));
+}
+
+#[api_test]
+async fn should_revoke_other_session() {
+    let random_email = app.signup_random_user(false).await;
+    let first_token = get_cookie(&app.login_user(&random_email).await, JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+    app.login_user(&random_email).await;
+
+    let sessions = get_sessions(&app).await;
+    let first_session = sessions
+        .iter()
+        .find(|session| !session.current)
+        .expect("No other session found");
+
+    let response = app.delete_session(&first_session.id).await;
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": first_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let sessions = get_sessions(&app).await;
+    assert_eq!(sessions.len(), 1);
+    assert!(sessions[0].current);
+}
+
+#[api_test]
+async fn should_log_out_when_revoking_current_session() {
+    let random_email = app.signup_random_user(false).await;
+    app.login_user(&random_email).await;
+
+    let sessions = get_sessions(&app).await;
+    let response = app.delete_session(&sessions[0].id).await;
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = app.get_sessions().await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    // The refresh cookie is cleared as well.
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[api_test]
+async fn should_keep_session_across_refresh() {
+    let random_email = app.signup_random_user(false).await;
+    app.login_user(&random_email).await;
+    let before = get_sessions(&app).await;
+
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let after = get_sessions(&app).await;
+    assert_eq!(after.len(), 1);
+    assert!(after[0].current);
+    assert_ne!(after[0].id, before[0].id);
+    assert_eq!(after[0].created_at, before[0].created_at);
+}
+
+#[api_test]
+async fn should_return_404_for_session_of_other_user() {
+    let other_email = app.signup_random_user(false).await;
+    app.login_user(&other_email).await;
+    let other_session = get_sessions(&app).await.remove(0);
+
+    let random_email = app.signup_random_user(false).await;
+    app.login_user(&random_email).await;
+
+    let response = app.delete_session(&other_session.id).await;
+    assert_eq!(response.status().as_u16(), 404);
+
+    let response = app.delete_session("unknown").await;
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[api_test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let response = app.get_sessions().await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app.delete_session("unknown").await;
+    assert_eq!(response.status().as_u16(), 400);
+}
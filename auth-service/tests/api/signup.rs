@@ -75,6 +75,12 @@ async fn should_return_400_if_invalid_input() {
             "password": "pass",
             "requires2FA": true
         }),
+        serde_json::json!({
+            "email": &random_email,
+            "password": "password123",
+            "requires2FA": true,
+            "locale": "xx"
+        }),
     ];
 
     for test_case in test_cases.iter() {
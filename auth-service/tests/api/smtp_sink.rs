@@ -0,0 +1,88 @@
+use std::sync::Arc;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::Mutex,
+};
+
+/// A local SMTP server that accepts every message and keeps it instead of
+/// delivering it. It only speaks as much SMTP as our client needs.
+pub struct SmtpSink {
+    pub port: u16,
+    messages: Arc<Mutex<Vec<String>>>,
+}
+
+impl SmtpSink {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind SMTP sink");
+        let port = listener.local_addr().unwrap().port();
+        let messages = Arc::new(Mutex::new(Vec::new()));
+
+        let sink_messages = messages.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(handle_connection(stream, sink_messages.clone()));
+            }
+        });
+
+        Self { port, messages }
+    }
+
+    /// The raw messages received so far, headers included.
+    pub async fn messages(&self) -> Vec<String> {
+        self.messages.lock().await.clone()
+    }
+}
+
+async fn handle_connection(stream: TcpStream, messages: Arc<Mutex<Vec<String>>>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    if writer
+        .write_all(b"220 localhost ESMTP sink\r\n")
+        .await
+        .is_err()
+    {
+        return;
+    }
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let command = line.to_ascii_uppercase();
+        let reply: &[u8] = if command.starts_with("EHLO") || command.starts_with("HELO") {
+            b"250 localhost\r\n"
+        } else if command == "DATA" {
+            if writer
+                .write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n")
+                .await
+                .is_err()
+            {
+                return;
+            }
+            let mut message = String::new();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if line == "." {
+                    break;
+                }
+                // Undo the client's dot-stuffing.
+                message.push_str(
+                    line.strip_prefix('.')
+                        .filter(|_| line.starts_with(".."))
+                        .unwrap_or(&line),
+                );
+                message.push('\n');
+            }
+            messages.lock().await.push(message);
+            b"250 OK\r\n"
+        } else if command == "QUIT" {
+            let _ = writer.write_all(b"221 Bye\r\n").await;
+            return;
+        } else {
+            b"250 OK\r\n"
+        };
+
+        if writer.write_all(reply).await.is_err() {
+            return;
+        }
+    }
+}
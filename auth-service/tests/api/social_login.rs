@@ -0,0 +1,281 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::{
+    domain::{Email, ExternalIdentity},
+    services::MockIdentityProvider,
+    utils::constants::JWT_COOKIE_NAME,
+};
+use reqwest::header::LOCATION;
+use std::sync::Arc;
+use test_helpers::api_test;
+use url::Url;
+
+/// Builds an app whose mock provider logs everyone in with the given email.
+async fn spawn_app(email: &str, email_verified: bool) -> TestApp {
+    let identity = ExternalIdentity {
+        subject: uuid::Uuid::new_v4().to_string(),
+        email: Email::parse(email.to_owned()).unwrap(),
+        email_verified,
+    };
+
+    TestApp::with_state(|app_state| {
+        app_state.with_identity_provider(Arc::new(MockIdentityProvider::new(identity)))
+    })
+    .await
+}
+
+fn location(response: &reqwest::Response) -> String {
+    response
+        .headers()
+        .get(LOCATION)
+        .expect("No redirect location")
+        .to_str()
+        .unwrap()
+        .to_owned()
+}
+
+fn has_auth_cookie(response: &reqwest::Response) -> bool {
+    response
+        .cookies()
+        .any(|cookie| cookie.name() == JWT_COOKIE_NAME)
+}
+
+/// The query the provider sends the user back to us with.
+async fn start_login(app: &TestApp, params: &[(&str, &str)]) -> Vec<(String, String)> {
+    let response = app
+        .get_social_login(MockIdentityProvider::NAME, params)
+        .await;
+    assert_eq!(response.status().as_u16(), 303);
+
+    Url::parse(&location(&response))
+        .expect("Invalid authorization URL")
+        .query_pairs()
+        .into_owned()
+        .collect()
+}
+
+async fn social_login(app: &TestApp, params: &[(&str, &str)]) -> reqwest::Response {
+    let callback_params = start_login(app, params).await;
+    let callback_params: Vec<(&str, &str)> = callback_params
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+
+    app.get_social_login_callback(MockIdentityProvider::NAME, &callback_params)
+        .await
+}
+
+async fn signup(app: &TestApp, email: &str, requires_2fa: bool) {
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": "password123",
+            "requires2FA": requires_2fa
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+}
+
+#[tokio::test]
+async fn should_create_user_on_first_social_login() {
+    let email = get_random_email();
+    let app = spawn_app(&email, true).await;
+
+    let response = social_login(&app, &[]).await;
+    assert_eq!(response.status().as_u16(), 303);
+    assert_eq!(location(&response), "/");
+    assert!(has_auth_cookie(&response));
+
+    let user = app
+        .user_store
+        .read()
+        .await
+        .get_user(&Email::parse(email).unwrap())
+        .await
+        .expect("User was not created");
+    assert!(user.email_verified);
+    assert!(!user.requires_2fa);
+
+    // The identity is linked now, so it logs the same user in again.
+    let response = social_login(&app, &[]).await;
+    assert_eq!(response.status().as_u16(), 303);
+    assert!(has_auth_cookie(&response));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_link_identity_to_user_with_verified_email() {
+    let email = get_random_email();
+    let app = spawn_app(&email, true).await;
+    signup(&app, &email, false).await;
+    app.verify_email(&email).await;
+
+    let response = social_login(&app, &[]).await;
+    assert_eq!(response.status().as_u16(), 303);
+    assert!(has_auth_cookie(&response));
+
+    // The password keeps working alongside the provider.
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_refuse_unverified_provider_email() {
+    let email = get_random_email();
+    let app = spawn_app(&email, false).await;
+
+    let response = social_login(&app, &[]).await;
+    assert_eq!(response.status().as_u16(), 403);
+    assert!(!has_auth_cookie(&response));
+    assert!(app
+        .user_store
+        .read()
+        .await
+        .get_user(&Email::parse(email).unwrap())
+        .await
+        .is_err());
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_not_link_identity_to_unverified_user() {
+    let email = get_random_email();
+    let app = spawn_app(&email, true).await;
+    // Whoever signed up never proved they own the address.
+    signup(&app, &email, false).await;
+
+    let response = social_login(&app, &[]).await;
+    assert_eq!(response.status().as_u16(), 403);
+    assert!(!has_auth_cookie(&response));
+
+    // Once the owner verifies the address, they can link the identity.
+    app.verify_email(&email).await;
+    let response = social_login(&app, &[]).await;
+    assert_eq!(response.status().as_u16(), 303);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_callback_not_matching_started_login() {
+    let app = spawn_app(&get_random_email(), true).await;
+
+    // No login was started in this browser.
+    let response = app
+        .get_social_login_callback(
+            MockIdentityProvider::NAME,
+            &[("code", "code"), ("state", "state")],
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let params = start_login(&app, &[]).await;
+    let code = &params.iter().find(|(name, _)| name == "code").unwrap().1;
+    let response = app
+        .get_social_login_callback(
+            MockIdentityProvider::NAME,
+            &[("code", code), ("state", "wrong-state")],
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    // A login can't be finished after a failed attempt.
+    let state = &params.iter().find(|(name, _)| name == "state").unwrap().1;
+    let response = app
+        .get_social_login_callback(
+            MockIdentityProvider::NAME,
+            &[("code", code), ("state", state)],
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    // The code has to have been issued for this login's verifier.
+    let params = start_login(&app, &[]).await;
+    let state = &params.iter().find(|(name, _)| name == "state").unwrap().1;
+    let response = app
+        .get_social_login_callback(
+            MockIdentityProvider::NAME,
+            &[("code", code), ("state", state)],
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert!(!has_auth_cookie(&response));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_to_local_paths_only() {
+    let app = spawn_app(&get_random_email(), true).await;
+
+    let response = social_login(&app, &[("return_to", "/authorize?client_id=wiki")]).await;
+    assert_eq!(response.status().as_u16(), 303);
+    assert_eq!(location(&response), "/authorize?client_id=wiki");
+
+    for return_to in ["//evil.example.com", "https://evil.example.com", "/\\evil"] {
+        let response = social_login(&app, &[("return_to", return_to)]).await;
+        assert_eq!(response.status().as_u16(), 303);
+        assert_eq!(location(&response), "/");
+    }
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_require_2fa_after_social_login() {
+    let email = get_random_email();
+    let app = spawn_app(&email, true).await;
+    signup(&app, &email, true).await;
+    app.verify_email(&email).await;
+
+    let response = social_login(&app, &[]).await;
+    assert_eq!(response.status().as_u16(), 303);
+    assert!(!has_auth_cookie(&response));
+
+    let two_fa_url = Url::parse(&format!("{}{}", &app.address, location(&response))).unwrap();
+    let login_attempt_id = two_fa_url
+        .query_pairs()
+        .find(|(name, _)| name == "login_attempt_id")
+        .expect("No login attempt id")
+        .1
+        .into_owned();
+    let (expected_login_attempt_id, _) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&Email::parse(email.clone()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(login_attempt_id, expected_login_attempt_id.as_ref());
+    let code = app.get_2fa_code(&email).await;
+
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": &email,
+            "loginAttemptId": &login_attempt_id,
+            "2FACode": &code,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await;
+}
+
+#[api_test]
+async fn should_return_404_for_unknown_provider() {
+    let response = app.get_social_login("github", &[]).await;
+    assert_eq!(response.status().as_u16(), 404);
+
+    let response = app
+        .get_social_login_callback("github", &[("code", "code"), ("state", "state")])
+        .await;
+    assert_eq!(response.status().as_u16(), 404);
+}
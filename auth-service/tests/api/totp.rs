@@ -0,0 +1,236 @@
+use crate::helpers::TestApp;
+use auth_service::{
+    domain::Email,
+    routes::{EnrollTotpResponse, TwoFactorAuthResponse},
+};
+use test_helpers::api_test;
+use totp_rs::TOTP;
+
+async fn enroll(app: &TestApp) -> TOTP {
+    let response = app.post_totp_enroll().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body = response
+        .json::<EnrollTotpResponse>()
+        .await
+        .expect("Could not deserialize response body to EnrollTotpResponse");
+
+    TOTP::from_url(body.otpauth_uri).expect("Invalid otpauth URI")
+}
+
+/// The code of the next time step. It is still accepted after the current
+/// code was used to confirm the enrollment.
+fn next_code(totp: &TOTP) -> String {
+    totp.generate(totp.next_step_current().unwrap())
+}
+
+#[api_test]
+async fn should_return_200_and_use_totp_for_login() {
+    let (random_email, _) = app.signup_and_login().await;
+    let totp = enroll(&app).await;
+
+    let response = app
+        .post_totp_confirm(&serde_json::json!({
+            "code": totp.generate_current().unwrap()
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &random_email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let body = response
+        .json::<TwoFactorAuthResponse>()
+        .await
+        .expect("Could not deserialize response body to TwoFactorAuthResponse");
+    assert_eq!(body.two_fa_method.as_ref(), "totp");
+
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": &random_email,
+            "loginAttemptId": body.login_attempt_id,
+            "2FACode": next_code(&totp)
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[api_test]
+async fn should_return_401_if_totp_code_is_reused() {
+    let (random_email, _) = app.signup_and_login().await;
+    let totp = enroll(&app).await;
+
+    let code = totp.generate_current().unwrap();
+    let response = app
+        .post_totp_confirm(&serde_json::json!({ "code": &code }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let login = || async {
+        app.post_login(&serde_json::json!({
+            "email": &random_email,
+            "password": "password123",
+        }))
+        .await
+        .json::<TwoFactorAuthResponse>()
+        .await
+        .expect("Could not deserialize response body to TwoFactorAuthResponse")
+        .login_attempt_id
+    };
+
+    // The code used to confirm the enrollment can't log in.
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": &random_email,
+            "loginAttemptId": login().await,
+            "2FACode": &code
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let code = next_code(&totp);
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": &random_email,
+            "loginAttemptId": login().await,
+            "2FACode": &code
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": &random_email,
+            "loginAttemptId": login().await,
+            "2FACode": &code
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_not_email_a_code_to_totp_users() {
+    let (random_email, _) = app.signup_and_login().await;
+    let totp = enroll(&app).await;
+
+    let response = app
+        .post_totp_confirm(&serde_json::json!({
+            "code": totp.generate_current().unwrap()
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &random_email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 206);
+    app.deliver_emails().await;
+
+    let email = Email::parse(random_email).unwrap();
+    let (_, code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&email)
+        .await
+        .unwrap();
+    assert!(code.is_none());
+    assert!(app
+        .email_client
+        .last_email_with_subject(&email, "Your login code")
+        .is_none());
+}
+
+#[api_test]
+async fn should_return_401_for_totp_code_if_email_method_is_chosen() {
+    let (random_email, _) = app.signup_and_login().await;
+    let totp = enroll(&app).await;
+
+    let response = app
+        .post_totp_confirm(&serde_json::json!({
+            "code": totp.generate_current().unwrap()
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_two_fa_method(&serde_json::json!({ "method": "email" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &random_email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let body = response
+        .json::<TwoFactorAuthResponse>()
+        .await
+        .expect("Could not deserialize response body to TwoFactorAuthResponse");
+    assert_eq!(body.two_fa_method.as_ref(), "email");
+
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": &random_email,
+            "loginAttemptId": body.login_attempt_id,
+            "2FACode": next_code(&totp)
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_return_401_if_confirm_code_is_incorrect() {
+    app.signup_and_login().await;
+    let totp = enroll(&app).await;
+
+    let code = totp.generate_current().unwrap();
+    let wrong_code = if code == "000000" { "111111" } else { "000000" };
+
+    let response = app
+        .post_totp_confirm(&serde_json::json!({ "code": wrong_code }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_return_400_if_confirming_without_enrollment() {
+    app.signup_and_login().await;
+
+    let response = app
+        .post_totp_confirm(&serde_json::json!({ "code": "123456" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[api_test]
+async fn should_return_400_if_selecting_totp_without_enrollment() {
+    app.signup_and_login().await;
+
+    let response = app
+        .post_two_fa_method(&serde_json::json!({ "method": "totp" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app
+        .post_two_fa_method(&serde_json::json!({ "method": "email" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[api_test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let response = app.post_totp_enroll().await;
+    assert_eq!(response.status().as_u16(), 400);
+}
@@ -1,5 +1,5 @@
 use crate::helpers::{get_random_email, TestApp};
-use auth_service::{domain::Email, routes::LoginResponse};
+use auth_service::routes::LoginResponse;
 use test_helpers::api_test;
 
 #[api_test]
@@ -14,6 +14,7 @@ async fn should_return_200_with_correct_code() {
 
     let response = app.post_signup(&test_case).await;
     assert_eq!(response.status().as_u16(), 201, "Failed to signup new user");
+    app.verify_email(&random_email).await;
 
     let test_case = serde_json::json!({
         "email": &random_email,
@@ -37,20 +38,12 @@ async fn should_return_200_with_correct_code() {
     } else {
         panic!();
     };
-    let email = Email::parse(random_email.clone()).unwrap();
-    let two_fa_code = app
-        .two_fa_code_store
-        .read()
-        .await
-        .get_code(&email)
-        .await
-        .unwrap()
-        .1;
+    let two_fa_code = app.get_2fa_code(&random_email).await;
 
     let test_case = serde_json::json!({
         "email": random_email,
         "loginAttemptId": login_attempt_id,
-        "2FACode": two_fa_code.as_ref().to_string(),
+        "2FACode": two_fa_code,
     });
 
     assert_eq!(app.post_verify_2fa(&test_case).await.status().as_u16(), 200);
@@ -82,6 +75,7 @@ async fn should_return_401_with_incorrect_code() {
         201,
         "Failed to signup new user"
     );
+    app.verify_email(&random_email).await;
 
     let test_case = serde_json::json!({
         "email": &random_email,
@@ -124,6 +118,7 @@ async fn should_return_401_if_same_token_is_used_twice() {
 
     let response = app.post_signup(&test_case).await;
     assert_eq!(response.status().as_u16(), 201, "Failed to signup new user");
+    app.verify_email(&random_email).await;
 
     let test_case = serde_json::json!({
         "email": &random_email,
@@ -147,20 +142,12 @@ async fn should_return_401_if_same_token_is_used_twice() {
     } else {
         panic!();
     };
-    let email = Email::parse(random_email.clone()).unwrap();
-    let two_fa_code = app
-        .two_fa_code_store
-        .read()
-        .await
-        .get_code(&email)
-        .await
-        .unwrap()
-        .1;
+    let two_fa_code = app.get_2fa_code(&random_email).await;
 
     let test_case = serde_json::json!({
         "email": random_email,
         "loginAttemptId": login_attempt_id,
-        "2FACode": two_fa_code.as_ref().to_string(),
+        "2FACode": two_fa_code,
     });
 
     assert_eq!(app.post_verify_2fa(&test_case).await.status().as_u16(), 200);
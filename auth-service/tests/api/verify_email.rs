@@ -0,0 +1,197 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::{
+    domain::{Email, OneTimeToken, TokenPurpose},
+    routes::VerifyEmailResponse,
+};
+use test_helpers::api_test;
+
+async fn signup(app: &TestApp, email: &str) {
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": "password123",
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201, "Failed to signup new user");
+}
+
+async fn add_verification_token(app: &TestApp, email: &str) -> OneTimeToken {
+    let token = OneTimeToken::default();
+    app.one_time_token_store
+        .write()
+        .await
+        .add_token(
+            token.clone(),
+            TokenPurpose::EmailVerification,
+            Email::parse(email.to_owned()).expect("Failed to parse email"),
+        )
+        .await
+        .expect("Failed to add verification token");
+    token
+}
+
+#[api_test]
+async fn should_issue_verification_token_on_signup() {
+    let random_email = get_random_email();
+    signup(&app, &random_email).await;
+
+    assert!(app
+        .one_time_token_store
+        .read()
+        .await
+        .last_issued_at(
+            &Email::parse(random_email).expect("Failed to parse email"),
+            TokenPurpose::EmailVerification
+        )
+        .await
+        .unwrap()
+        .is_some());
+}
+
+#[api_test]
+async fn should_verify_email_with_link_from_signup_email() {
+    let random_email = get_random_email();
+    signup(&app, &random_email).await;
+
+    let email = app.last_email_to(&random_email).await;
+    assert_eq!(email.subject, "Verify your email address");
+    let token = email
+        .text_body
+        .split_whitespace()
+        .find_map(|word| word.split_once("/verify-email?token="))
+        .expect("No verification link in the email")
+        .1;
+
+    let response = app
+        .post_verify_email(&serde_json::json!({ "token": token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[api_test]
+async fn should_verify_email_when_following_emailed_link() {
+    let random_email = get_random_email();
+    signup(&app, &random_email).await;
+
+    let link = app
+        .get_emailed_link(&random_email, "/verify-email?token=")
+        .await;
+    let response = app.follow_link(&link).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": &random_email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // The link can only be used once.
+    let response = app.follow_link(&link).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_allow_login_after_verifying_email() {
+    let random_email = get_random_email();
+    signup(&app, &random_email).await;
+
+    let login_body = serde_json::json!({
+        "email": &random_email,
+        "password": "password123",
+    });
+    assert_eq!(app.post_login(&login_body).await.status().as_u16(), 403);
+
+    let token = add_verification_token(&app, &random_email).await;
+    let response = app
+        .post_verify_email(&serde_json::json!({ "token": token.as_ref() }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_eq!(app.post_login(&login_body).await.status().as_u16(), 200);
+}
+
+#[api_test]
+async fn should_return_401_if_token_is_reused() {
+    let random_email = get_random_email();
+    signup(&app, &random_email).await;
+
+    let token = add_verification_token(&app, &random_email).await;
+    let body = serde_json::json!({ "token": token.as_ref() });
+
+    assert_eq!(app.post_verify_email(&body).await.status().as_u16(), 200);
+    assert_eq!(app.post_verify_email(&body).await.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_return_401_if_invalid_token() {
+    let test_cases = [
+        "invalid".to_owned(),
+        OneTimeToken::default().as_ref().to_owned(),
+    ];
+
+    for token in test_cases {
+        let response = app
+            .post_verify_email(&serde_json::json!({ "token": token }))
+            .await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+}
+
+#[api_test]
+async fn should_return_401_if_token_was_issued_for_another_purpose() {
+    let random_email = get_random_email();
+    signup(&app, &random_email).await;
+
+    let token = OneTimeToken::default();
+    app.one_time_token_store
+        .write()
+        .await
+        .add_token(
+            token.clone(),
+            TokenPurpose::PasswordReset,
+            Email::parse(random_email).expect("Failed to parse email"),
+        )
+        .await
+        .unwrap();
+
+    let response = app
+        .post_verify_email(&serde_json::json!({ "token": token.as_ref() }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[api_test]
+async fn should_return_same_resend_response_for_any_account() {
+    let unverified_email = get_random_email();
+    signup(&app, &unverified_email).await;
+    let verified_email = get_random_email();
+    signup(&app, &verified_email).await;
+    app.verify_email(&verified_email).await;
+
+    let mut responses = vec![];
+    for email in [unverified_email, verified_email, get_random_email()] {
+        let response = app
+            .post_resend_verification_email(&serde_json::json!({ "email": email }))
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+        responses.push(
+            response
+                .json::<VerifyEmailResponse>()
+                .await
+                .expect("Could not deserialize response body to VerifyEmailResponse"),
+        );
+    }
+
+    assert!(responses.windows(2).all(|pair| pair[0] == pair[1]));
+}
+
+#[api_test]
+async fn should_return_400_if_resend_email_is_invalid() {
+    let response = app
+        .post_resend_verification_email(&serde_json::json!({ "email": "invalid" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
@@ -1,5 +1,5 @@
-use crate::helpers::{get_random_email, TestApp};
-use auth_service::{domain::Email, utils::auth::generate_auth_cookie};
+use crate::helpers::TestApp;
+use auth_service::utils::{auth::generate_auth_cookie, constants::JWT_AUDIENCES};
 use test_helpers::api_test;
 
 #[api_test]
@@ -29,17 +29,54 @@ async fn should_return_401_if_invalid_token() {
 }
 
 #[api_test]
-async fn should_return_200_if_valid_token() {
-    let random_email = get_random_email();
-    let email = Email::parse(random_email).expect("Failed to parse Email");
+async fn should_return_200_and_claims_if_valid_token() {
+    let user = app.add_random_user().await;
+    let token = generate_auth_cookie(&user).expect("Failed to generate token");
 
-    let token = generate_auth_cookie(&email).expect("Failed to generate token");
+    let response = app
+        .post_verify_token(&serde_json::json!({"token": token.value()}))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let claims = response
+        .json::<serde_json::Value>()
+        .await
+        .expect("Could not deserialize response body");
+    assert_eq!(claims["sub"], user.id.to_string());
+    assert_eq!(claims["email"], user.email.as_ref());
+    assert!(claims["jti"].is_string());
+}
+
+#[api_test]
+async fn should_return_200_if_audience_is_configured() {
+    let token =
+        generate_auth_cookie(&app.add_random_user().await).expect("Failed to generate token");
 
     assert_eq!(
-        app.post_verify_token(&serde_json::json!({"token": token.value()}))
-            .await
-            .status()
-            .as_u16(),
+        app.post_verify_token(&serde_json::json!({
+            "token": token.value(),
+            "audience": &JWT_AUDIENCES[0]
+        }))
+        .await
+        .status()
+        .as_u16(),
         200
     );
 }
+
+#[api_test]
+async fn should_return_400_if_audience_is_not_configured() {
+    let token =
+        generate_auth_cookie(&app.add_random_user().await).expect("Failed to generate token");
+
+    assert_eq!(
+        app.post_verify_token(&serde_json::json!({
+            "token": token.value(),
+            "audience": "another-service"
+        }))
+        .await
+        .status()
+        .as_u16(),
+        400
+    );
+}